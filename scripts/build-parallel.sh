@@ -0,0 +1,23 @@
+#! /bin/bash
+
+# Browser build with the `parallel` feature. Needs nightly Rust with the rust-src component,
+# and the page must be cross-origin isolated (COOP/COEP headers) for SharedArrayBuffer.
+
+TARGET_DIR="`dirname $0`"
+CRATE_PATH="$TARGET_DIR/.."
+BINDGEN_BIN=$(which wasm-pack)
+WASM_BUILD_PATH="$CRATE_PATH/wasm"
+
+if [ ! -f "$HOME/.cargo/bin/wasm-pack" ]; then
+  echo "wasm-pack is not installed, please install and try again";
+  exit 1;
+fi
+
+if [ ! -d $WASM_BUILD_PATH ]; then mkdir $WASM_BUILD_PATH; fi
+if [ ! -d "$WASM_BUILD_PATH/browser-parallel" ]; then mkdir "$WASM_BUILD_PATH/browser-parallel"; fi
+
+RUSTFLAGS='-C target-feature=+atomics,+bulk-memory,+mutable-globals' \
+  rustup run nightly $BINDGEN_BIN build --release --target browser --out-dir $WASM_BUILD_PATH/browser-parallel $CRATE_PATH \
+  -- --features parallel -Z build-std=panic_abort,std
+
+rm -rf $WASM_BUILD_PATH/browser-parallel/.gitignore $WASM_BUILD_PATH/browser-parallel/package.json $WASM_BUILD_PATH/browser-parallel/README.md
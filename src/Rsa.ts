@@ -4,8 +4,34 @@ import {
   RSAInterface,
   RSAPublicKeyInterface,
   RSAPrivateKeyInterface,
+  RSASignerInterface,
+  RSAVerifierInterface,
+  RSABlindingResultInterface,
+  RSAEnvelopeBuilderInterface,
+  RSAAgeEncryptorInterface,
+  RSAKeyGeneratorInterface,
+  RSAKeyGenerationProgress,
+  RSAKeyGeneration,
+  RSAKeyGenerationOptions,
+  RSAFipsPrivate,
+  RSAPrivateCrt,
+  RSAKeyValidationReport,
+  RSAPublicKeyAnalysis,
+  RSASharedFactors,
+  RSABatchGcdInterface,
+  RSAPolicyInterface,
+  RSAPolicyOptions,
+  HttpMessageInterface,
+  HttpRequest,
+  HttpSignatureParameters,
+  HttpSignatureHeaders,
+  WebAuthnAssertion,
 } from './interfaces'
-import randomBytes from 'randombytes'
+
+/**
+ * @desc seed argument that makes wasm methods draw from crypto.getRandomValues / OS entropy
+ */
+const OS_RANDOM = 'os'
 
 /**
  * @desc facade on wasm code.
@@ -17,10 +43,14 @@ import randomBytes from 'randombytes'
 export default class RSA implements RSAInterface {
   public static IS_BROWSER: boolean = (typeof window !== 'undefined')
 
+  private wasm
   private publicInstance: RSAPublicKeyInterface
   private privateInstance: RSAPrivateKeyInterface
+  private threadPool: Promise<void>
   /** @ignore */
   constructor(wasm) {
+    /** @ignore */
+    this.wasm = wasm
     /** @ignore */
     this.publicInstance = new wasm.RSAPublicKeyPair()
     /** @ignore */
@@ -41,20 +71,72 @@ export default class RSA implements RSAInterface {
     * @property {string} e - public piece rsa key
     */
 
+  /**
+   * @typedef {Object} RSAPolicyOptions
+   * @property {number} minModulusBits - smallest key size accepted
+   * @property {Array|null} exponents - hex public exponents accepted, null for any
+   * @property {Array} paddings - RSAES-PKCS1-v1_5, RSAES-OAEP, RSASSA-PKCS1-v1_5 and / or RSASSA-PSS
+   * @property {Array} hashes - SHA-1, SHA-256, SHA-384 and / or SHA-512
+   * @property {boolean} sha1Verification - accept SHA-1 signatures on verify only
+   */
+
+  /**
+   * @desc Set policy checked on every generate, import, sign, verify, encrypt and decrypt.
+   * strict is the default: 2048 bits, e = 10001, no RSAES-PKCS1-v1_5 (publicEncrypt, privateDecrypt) and no SHA-1.
+   * legacy accepts 1024 bits, any exponent, every padding and hash.
+   * @param {string|RSAPolicyOptions} policy - 'strict', 'legacy' or options, missing ones are strict
+   * @returns {RSAPolicyOptions} - policy in effect
+   * @example
+   * rsaInstance.setRSAPolicy('legacy')
+   * rsaInstance.setRSAPolicy({ minModulusBits: 3072, hashes: ['SHA-384', 'SHA-512'] })
+   */
+  setRSAPolicy(policy: 'strict' | 'legacy' | RSAPolicyOptions): RSAPolicyOptions {
+    const instance = this.createPolicy(policy)
+    try {
+      this.privateInstance.set_policy(instance)
+      this.publicInstance.set_policy(instance)
+      return JSON.parse(instance.to_json())
+    } finally {
+      instance.free()
+    }
+  }
+
+  private createPolicy(policy: 'strict' | 'legacy' | RSAPolicyOptions): RSAPolicyInterface {
+    if (policy === 'strict') {
+      return this.wasm.RSAPolicy.strict()
+    }
+    if (policy === 'legacy') {
+      return this.wasm.RSAPolicy.legacy()
+    }
+    return this.wasm.RSAPolicy.from_json(JSON.stringify(policy))
+  }
+
+  /**
+   * @typedef {Object} RSAKeyGenerationOptions
+   * @property {string} publicExponent - hex public exponent, e.g. '3', '11' or '10001'
+   * @property {number} primeCount - count of primes, 2 to 5 depending on bits
+   */
+
   /**
    * @desc Generate private key pair
    * @param {number} bits - count bits for create rsa keys.
+   * @param {RSAKeyGenerationOptions} options - public exponent and count of primes, 10001 and 2 by default
    * @returns {RSAPrivate} - generated keys
    * @example
    * const { n, e, d, primes } = rsaInstance.generateRSAPrivate(2048)
+   * const threePrimes = rsaInstance.generateRSAPrivate(3072, { publicExponent: '11', primeCount: 3 })
    */
-  generateRSAPrivate(bits: number): RSAPrivate {
+  generateRSAPrivate(bits: number, options?: RSAKeyGenerationOptions): RSAPrivate {
     if (typeof bits !== 'number') {
       throw new Error(`Invalid bits ${bits}`)
     }
 
-    const randomSeed = randomBytes(32).toString('hex')
-    this.privateInstance.generate(bits, randomSeed)
+    if (options) {
+      const { publicExponent = '10001', primeCount = 2 } = options
+      this.privateInstance.generate_with(bits, publicExponent, primeCount, OS_RANDOM)
+    } else {
+      this.privateInstance.generate_seedless(bits)
+    }
 
     return {
       d: this.privateInstance.get_d(),
@@ -63,6 +145,172 @@ export default class RSA implements RSAInterface {
       primes: this.privateInstance.get_primes().split('_'),
     }
   }
+
+  /**
+   * @desc Generate private key pair following FIPS 186-5 A.1 with e = 10001, from an SP 800-90A HMAC_DRBG.
+   * The library is not a validated module, so this alone does not make the key FIPS compliant.
+   * @param {number} bits - 2048, 3072 or 4096
+   * @param {string} method - 'A.1.2' provable primes, 'A.1.3' random probable primes or 'A.1.6' probable primes
+   * based on auxiliary probable primes
+   * @returns {RSAFipsPrivate} - generated keys and report of the generation method
+   * @example
+   * const { n, e, d, primes, report } = rsaInstance.generateRSAPrivateFips(3072, 'A.1.6')
+   */
+  generateRSAPrivateFips(bits: number, method: string = 'A.1.3'): RSAFipsPrivate {
+    if (typeof bits !== 'number') {
+      throw new Error(`Invalid bits ${bits}`)
+    }
+
+    const report = JSON.parse(this.privateInstance.generate_fips(bits, method, OS_RANDOM))
+
+    return {
+      d: this.privateInstance.get_d(),
+      n: this.privateInstance.get_n(),
+      e: this.privateInstance.get_e(),
+      primes: this.privateInstance.get_primes().split('_'),
+      report,
+    }
+  }
+
+  /**
+   * @desc Derive the same private key pair from the same passphrase and salt, in every release
+   * @param {number} bits - count bits for create rsa keys, a multiple of 16.
+   * @param {string} passphrase - user secret
+   * @param {string} salt - unique per user, e.g. account id
+   * @param {number} version - derivation algorithm version
+   * @returns {RSAPrivate} - derived keys
+   * @example
+   * const { n, e, d, primes } = rsaInstance.deriveRSAPrivateFromPassphrase(2048, passphrase, 'user@example.com')
+   */
+  deriveRSAPrivateFromPassphrase(bits: number, passphrase: string, salt: string, version = 1): RSAPrivate {
+    this.privateInstance.derive_from_passphrase(bits, passphrase.normalize('NFKD'), salt.normalize('NFKD'), version)
+
+    return this.getRSAPrivate()
+  }
+
+  /**
+   * @desc Derive the same private key pair from the same BIP-39 mnemonic and passphrase, in every release
+   * @param {number} bits - count bits for create rsa keys, a multiple of 16.
+   * @param {string} mnemonic - BIP-39 words
+   * @param {string} passphrase - optional BIP-39 passphrase
+   * @param {number} version - derivation algorithm version
+   * @returns {RSAPrivate} - derived keys
+   * @example
+   * const { n, e, d, primes } = rsaInstance.deriveRSAPrivateFromMnemonic(2048, mnemonic)
+   */
+  deriveRSAPrivateFromMnemonic(bits: number, mnemonic: string, passphrase = '', version = 1): RSAPrivate {
+    this.privateInstance.derive_from_mnemonic(bits, mnemonic.normalize('NFKD'), passphrase.normalize('NFKD'), version)
+
+    return this.getRSAPrivate()
+  }
+
+  /**
+   * @typedef {Object} RSAKeyGenerationProgress
+   * @property {number} candidatesTested - prime candidates tested so far
+   * @property {number} primesFound - primes found so far
+   */
+
+  /**
+   * @desc Generate private key pair without blocking the event loop
+   * @param {number} bits - count bits for create rsa keys.
+   * @param {function} onProgress - called with RSAKeyGenerationProgress after every step
+   * @param {number} candidatesPerStep - prime candidates tested before yielding
+   * @returns {RSAKeyGeneration} - promise of generated keys and cancel function, a cancelled promise rejects
+   * @example
+   * const generation = rsaInstance.generateRSAPrivateAsync(4096, ({ candidatesTested }) => console.log(candidatesTested))
+   * cancelButton.onclick = () => generation.cancel()
+   * const { n, e, d, primes } = await generation.promise
+   */
+  generateRSAPrivateAsync(
+    bits: number,
+    onProgress?: (progress: RSAKeyGenerationProgress) => void,
+    candidatesPerStep: number = 4,
+  ): RSAKeyGeneration {
+    if (typeof bits !== 'number') {
+      throw new Error(`Invalid bits ${bits}`)
+    }
+
+    const generator: RSAKeyGeneratorInterface = new this.wasm.RSAKeyGenerator(bits, this.privateInstance, OS_RANDOM)
+    let cancelled = false
+
+    const promise = new Promise<RSAPrivate>((resolve, reject) => {
+      const step = () => {
+        if (cancelled) {
+          generator.free()
+          reject(new Error('key generation cancelled'))
+          return
+        }
+
+        try {
+          const done = generator.step(candidatesPerStep)
+          if (onProgress) {
+            onProgress({ candidatesTested: generator.candidates_tested(), primesFound: generator.primes_found() })
+          }
+          if (!done) {
+            setTimeout(step, 0)
+            return
+          }
+
+          generator.finish(this.privateInstance)
+          generator.free()
+        } catch (error) {
+          generator.free()
+          reject(error)
+          return
+        }
+
+        resolve({
+          d: this.privateInstance.get_d(),
+          n: this.privateInstance.get_n(),
+          e: this.privateInstance.get_e(),
+          primes: this.privateInstance.get_primes().split('_'),
+        })
+      }
+
+      setTimeout(step, 0)
+    })
+
+    return {
+      promise,
+      cancel: () => {
+        cancelled = true
+      },
+    }
+  }
+
+  /**
+   * @desc Generate private key pair with the primes searched on a thread pool. Needs the wasm build
+   * from build:wasm:parallel and has to be called from a Web Worker.
+   * @param {number} bits - count bits for create rsa keys.
+   * @param {number} threads - thread pool size, used by the first call only
+   * @returns {Promise<RSAPrivate>} - generated keys
+   * @example
+   * const { n, e, d, primes } = await rsaInstance.generateRSAPrivateParallel(4096, navigator.hardwareConcurrency)
+   */
+  async generateRSAPrivateParallel(bits: number, threads: number): Promise<RSAPrivate> {
+    if (typeof bits !== 'number') {
+      throw new Error(`Invalid bits ${bits}`)
+    }
+
+    if (!this.wasm.initThreadPool || !this.privateInstance.generate_parallel) {
+      throw new Error('wasm module is built without the parallel feature')
+    }
+
+    if (!this.threadPool) {
+      this.threadPool = this.wasm.initThreadPool(threads)
+    }
+    await this.threadPool
+
+    this.privateInstance.generate_parallel(bits, OS_RANDOM)
+
+    return {
+      d: this.privateInstance.get_d(),
+      n: this.privateInstance.get_n(),
+      e: this.privateInstance.get_e(),
+      primes: this.privateInstance.get_primes().split('_'),
+    }
+  }
+
   /**
    * @desc Generate private key pair from n, d, e, primes
    * @param {string} n - public piece rsa key
@@ -92,6 +340,79 @@ export default class RSA implements RSAInterface {
     }
   }
 
+  /**
+   * @typedef {Object} RSAPrivateCrt
+   * @property {string} kty - always 'RSA'
+   * @property {string} n - public piece rsa key
+   * @property {string} e - public piece rsa key
+   * @property {string} d - private piece rsa key
+   * @property {string} p - first prime
+   * @property {string} q - second prime
+   * @property {string} dp - d mod (p - 1)
+   * @property {string} dq - d mod (q - 1)
+   * @property {string} qi - q^-1 mod p
+   * @property {Array} oth - r, d, t of every additional prime
+   */
+
+  /**
+   * @desc Generate private key from an RSA JWK, every component base64url as in RFC 7518
+   * @param {RSAPrivateCrt} components - private key components, checked for consistency
+   * @returns {RSAPrivate} - generated keys
+   * @example
+   * const privateKeys = rsaInstance.createRSAPrivateFromCrt({ kty: 'RSA', n, e, d, p, q, dp, dq, qi })
+   */
+  createRSAPrivateFromCrt(components: RSAPrivateCrt): RSAPrivate {
+    this.privateInstance.from_crt_components(JSON.stringify(components))
+
+    return this.getRSAPrivate()
+  }
+
+  /**
+   * @desc Get CRT components of private key
+   * @returns {RSAPrivateCrt} - private key as an RSA JWK, components in base64url
+   * @example
+   * const { dp, dq, qi } = rsaInstance.getRSAPrivateCrt()
+   */
+  getRSAPrivateCrt(): RSAPrivateCrt {
+    return JSON.parse(this.privateInstance.get_crt_components())
+  }
+
+  /**
+   * @desc Generate private key pair from n, d, e, recovering the primes
+   * @param {string} n - public piece rsa key
+   * @param {string} d - private piece rsa key
+   * @param {string} e - public piece rsa key
+   * @returns {RSAPrivate} - generated keys
+   * @example
+   * const privateKeys = rsaInstance.recoverRSAPrivateFrom(n, d, e)
+   */
+  recoverRSAPrivateFrom(n: string, d: string, e: string): RSAPrivate {
+    if (!n || !d || !e) {
+      throw new Error('not all data for create keys')
+    }
+
+    this.privateInstance.recover_from(n, d, e)
+
+    return this.getRSAPrivate()
+  }
+
+  /**
+   * @typedef {Object} RSAKeyValidationReport
+   * @property {boolean} valid - all checks passed
+   * @property {Object} checks - result of every check: modulusSize, primeCount, modulus, primes, publicExponent, privateExponent
+   * @property {Array} failed - names of the failed checks
+   */
+
+  /**
+   * @desc Check consistency of private key components, imported keys are checked already
+   * @returns {RSAKeyValidationReport} - result of every check
+   * @example
+   * const { valid, failed } = rsaInstance.validateRSAPrivate()
+   */
+  validateRSAPrivate(): RSAKeyValidationReport {
+    return JSON.parse(this.privateInstance.validate())
+  }
+
   /**
    * @desc Generate private key from pem key format
    * @param {string} key - private key in PEM format
@@ -162,6 +483,68 @@ export default class RSA implements RSAInterface {
     }
   }
 
+  /**
+   * @typedef {Object} RSAPublicKeyAnalysis
+   * @property {number} bits - modulus size
+   * @property {string} exponent - e in hex
+   * @property {boolean} exponentEven - e is even
+   * @property {boolean} exponentTooSmall - e is below 65537
+   * @property {boolean} exponentTooLarge - e is above 2^256 or not below n
+   * @property {number|null} smallFactor - smallest prime factor of n below 2^16
+   * @property {boolean} perfectPower - n is a power of an integer
+   * @property {boolean} closePrimes - n is factored by Fermat's method
+   * @property {boolean} rocaFingerprint - n has the fingerprint of CVE-2017-15361 (ROCA)
+   * @property {boolean} weak - any weakness found
+   * @property {Array} weaknesses - names of the weaknesses, modulusSize for n below 2048 bits
+   */
+
+  /**
+   * @desc Check public key for weaknesses before accepting it, e.g. from users
+   * @param {string} n - public piece rsa key
+   * @param {string} e - public piece rsa key
+   * @returns {RSAPublicKeyAnalysis} - result of every check
+   * @example
+   * const { weak, weaknesses } = rsaInstance.analyzeRSAPublic(n, e)
+   */
+  analyzeRSAPublic(n: string, e: string): RSAPublicKeyAnalysis {
+    return JSON.parse(this.wasm.analyze_public_key(n, e))
+  }
+
+  /**
+   * @typedef {Object} RSASharedFactors
+   * @property {Array} groups - keys sharing a prime: factor and keys with index, shared prime p and cofactor q
+   * @property {Array} duplicates - groups of indexes of keys with the same modulus
+   */
+
+  /**
+   * @desc Find keys sharing a prime factor with batch GCD, such keys are compromised
+   * @param {Array} keys - public keys as n, e
+   * @returns {RSASharedFactors} - compromised keys by index in keys
+   * @example
+   * const { groups, duplicates } = rsaInstance.findSharedFactors(uploadedKeys)
+   */
+  findSharedFactors(keys: RSAPublic[]): RSASharedFactors {
+    const batch: RSABatchGcdInterface = new this.wasm.RSABatchGcd()
+    const permissive: RSAPolicyInterface = this.wasm.RSAPolicy.from_json('{"minModulusBits":0,"exponents":null}')
+    try {
+      keys.forEach(({ n, e }) => {
+        const key: RSAPublicKeyInterface = new this.wasm.RSAPublicKeyPair()
+        try {
+          // weak keys are what is looked for, so they must not be rejected by the policy
+          key.set_policy(permissive)
+          key.create(n, e)
+          batch.add_key(key)
+        } finally {
+          key.free()
+        }
+      })
+      return JSON.parse(batch.find_shared_factors())
+    } finally {
+      permissive.free()
+      batch.free()
+    }
+  }
+
   /**
    * @desc Create public key from pem key format
    * @param {string} key - public key in PEM format
@@ -211,7 +594,7 @@ export default class RSA implements RSAInterface {
 
   /**
    * @desc Get private primes
-   * @returns {Array} - private primes
+   * @returns {Array} - private primes in decimal
    * @example
    * const primes = rsaInstance.getPrivatePrimes()
    */
@@ -220,6 +603,16 @@ export default class RSA implements RSAInterface {
     return primes.split('_')
   }
 
+  /**
+   * @desc Get private primes in hex, like the other key components
+   * @returns {Array} - private primes in hex
+   * @example
+   * const primes = rsaInstance.getPrivatePrimesHex()
+   */
+  getPrivatePrimesHex(): string[] {
+    return this.privateInstance.get_primes_hex().split('_')
+  }
+
   /**
    * @desc Get public keys
    * @returns {RSAPublic} - private keys
@@ -281,6 +674,447 @@ export default class RSA implements RSAInterface {
     return verify
   }
 
+  /**
+   * @desc create incremental signer with private keys
+   * @param {string} hash - hash algorithm
+   * @returns {RSASignerInterface} - signer with update/finalize
+   * @example
+   * const signer = rsaInstance.createSigner('SHA-256')
+   * signer.update(firstChunk)
+   * signer.update(secondChunk)
+   * const signature = signer.finalize()
+   * signer.free()
+   */
+  createSigner(hash: string = 'SHA-256'): RSASignerInterface {
+    return new this.wasm.RSASigner(this.privateInstance, hash)
+  }
+
+  /**
+   * @desc create incremental verifier with public keys
+   * @param {string} hash - hash algorithm
+   * @returns {RSAVerifierInterface} - verifier with update/finalize
+   * @example
+   * const verifier = rsaInstance.createVerifier('SHA-256')
+   * verifier.update(firstChunk)
+   * verifier.update(secondChunk)
+   * const verify = verifier.finalize(signature)
+   * verifier.free()
+   */
+  createVerifier(hash: string = 'SHA-256'): RSAVerifierInterface {
+    return new this.wasm.RSAVerifier(this.publicInstance, hash)
+  }
+
+  /**
+   * @desc blind message with public keys (RFC 9474), client side
+   * @param {string} message - message to get signed
+   * @param {string} variant - RSABSSA-SHA384-PSS-Randomized, RSABSSA-SHA384-PSSZERO-Randomized,
+   * RSABSSA-SHA384-PSS-Deterministic or RSABSSA-SHA384-PSSZERO-Deterministic
+   * @returns {RSABlindingResultInterface} - blinded message for the signer, inv and message prefix to finalize
+   * @example
+   * const blinding = rsaInstance.blindMessage('voucher')
+   * const blindSignature = await requestSignature(blinding.get_blinded_message())
+   * const signature = rsaInstance.finalizeBlindSignature('voucher', blindSignature, blinding)
+   * blinding.free()
+   */
+  blindMessage(message: string, variant: string = 'RSABSSA-SHA384-PSS-Randomized'): RSABlindingResultInterface {
+    return this.publicInstance.blind(message, variant, OS_RANDOM)
+  }
+
+  /**
+   * @desc sign blinded message with private keys (RFC 9474), signer side
+   * @param {string} blindedMessage - blinded message in hex
+   * @returns {string} - blind signature in hex
+   * @example
+   * const blindSignature = rsaInstance.blindSign(blindedMessage)
+   */
+  blindSign(blindedMessage: string): string {
+    return this.privateInstance.blind_sign(blindedMessage)
+  }
+
+  /**
+   * @desc unblind blind signature with public keys (RFC 9474), client side
+   * @param {string} message - message given to blindMessage
+   * @param {string} blindSignature - blind signature in hex
+   * @param {RSABlindingResultInterface} blinding - result of blindMessage
+   * @returns {string} - RSASSA-PSS signature in hex
+   */
+  finalizeBlindSignature(message: string, blindSignature: string, blinding: RSABlindingResultInterface): string {
+    return this.publicInstance.finalize(message, blindSignature, blinding)
+  }
+
+  /**
+   * @desc verify finalized blind signature with public keys
+   * @param {string} message - signed message
+   * @param {string} signature - signature in hex
+   * @param {string} messagePrefix - message prefix of the blinding in hex, empty for deterministic variants
+   * @param {string} variant - variant given to blindMessage
+   * @returns {boolean} - verify result
+   */
+  verifyBlindSignature(
+    message: string,
+    signature: string,
+    messagePrefix: string,
+    variant: string = 'RSABSSA-SHA384-PSS-Randomized',
+  ): boolean {
+    return this.publicInstance.verify_blind_signature(message, signature, messagePrefix, variant)
+  }
+
+  /**
+   * @desc create detached CMS (PKCS#7) SignedData signature with private keys
+   * @param {Uint8Array} content - signed document
+   * @param {string} certificate - PEM or hex DER X.509 certificate of the private keys
+   * @param {boolean} pem - return PEM ("PKCS7") instead of hex DER
+   * @returns {string} - .p7s signature
+   * @example
+   * const signature = rsaInstance.signCmsDetached(document, certificatePem, true)
+   */
+  signCmsDetached(content: Uint8Array, certificate: string, pem: boolean = false): string {
+    const signingTime = Math.floor(Date.now() / 1000)
+    return pem
+      ? this.privateInstance.sign_cms_detached_pem(content, certificate, signingTime)
+      : this.privateInstance.sign_cms_detached(content, certificate, signingTime)
+  }
+
+  /**
+   * @desc verify detached CMS (PKCS#7) SignedData signature with public keys
+   * @param {Uint8Array} content - signed document
+   * @param {string} signature - PEM or hex DER signature
+   * @returns {boolean} - result verify
+   * @example
+   * const verify = rsaInstance.verifyCmsDetached(document, signature)
+   */
+  verifyCmsDetached(content: Uint8Array, signature: string): boolean {
+    return this.publicInstance.verify_cms_detached(content, signature)
+  }
+
+  /**
+   * @desc verify detached CMS (PKCS#7) SignedData signature with the certificates embedded in it.
+   * The certificates are not checked against any trust anchor, the caller decides whether to trust them.
+   * @param {Uint8Array} content - signed document
+   * @param {string} signature - PEM or hex DER signature
+   * @param {string|RSAPolicyOptions} policy - checked against every signer key, strict by default
+   * @returns {string|undefined} - PEM certificates of the signers, undefined if verification fails
+   * @example
+   * const signers = rsaInstance.verifyCmsDetachedWithCertificate(document, signature)
+   */
+  verifyCmsDetachedWithCertificate(
+    content: Uint8Array,
+    signature: string,
+    policy: 'strict' | 'legacy' | RSAPolicyOptions = 'strict',
+  ): string | undefined {
+    const instance = this.createPolicy(policy)
+    try {
+      return this.wasm.verify_cms_detached_with_certificate(content, signature, instance)
+    } finally {
+      instance.free()
+    }
+  }
+
+  /**
+   * @desc encrypt document to RSA recipients as CMS EnvelopedData (CBC) or AuthEnvelopedData (GCM)
+   * @param {Uint8Array} content - document for encrypt
+   * @param {Array} certificates - PEM or hex DER X.509 certificates of recipients
+   * @param {string} contentEncryption - AES-128-CBC, AES-256-CBC, AES-128-GCM, AES-256-GCM ...
+   * @param {string} oaepHash - RSAES-OAEP hash, RSAES-PKCS1-v1_5 is used when omitted
+   * @param {string|RSAPolicyOptions} policy - checked against every recipient key, strict by default
+   * @returns {string} - hex encoded DER envelope
+   * @example
+   * const envelope = rsaInstance.encryptCms(document, [certificatePem], 'AES-256-GCM', 'SHA-256')
+   */
+  encryptCms(
+    content: Uint8Array,
+    certificates: string[],
+    contentEncryption: string = 'AES-256-CBC',
+    oaepHash?: string,
+    policy: 'strict' | 'legacy' | RSAPolicyOptions = 'strict',
+  ): string {
+    const builder: RSAEnvelopeBuilderInterface = new this.wasm.RSAEnvelopeBuilder(contentEncryption)
+    const instance = this.createPolicy(policy)
+    try {
+      builder.set_policy(instance)
+      certificates.forEach((certificate) => builder.add_recipient(certificate, oaepHash))
+      return builder.encrypt(content, OS_RANDOM)
+    } finally {
+      instance.free()
+      builder.free()
+    }
+  }
+
+  /**
+   * @desc decrypt CMS EnvelopedData / AuthEnvelopedData with private keys
+   * @param {string} data - PEM or hex DER envelope
+   * @param {string} certificate - PEM or hex DER X.509 certificate of the private keys
+   * @returns {Uint8Array} - decrypted document
+   * @example
+   * const document = rsaInstance.decryptCms(envelope, certificatePem)
+   */
+  decryptCms(data: string, certificate: string): Uint8Array {
+    return this.privateInstance.decrypt_cms(data, certificate)
+  }
+
+  /**
+   * @desc encrypt payload to ssh-rsa recipients in age v1 format
+   * @param {Uint8Array} payload - data for encrypt
+   * @param {Array} recipients - OpenSSH public key lines, e.g. content of id_rsa.pub
+   * @param {boolean} armor - return ASCII armored file instead of binary
+   * @param {string|RSAPolicyOptions} policy - checked against every recipient key, strict by default
+   * @returns {Uint8Array|string} - age file
+   * @example
+   * const file = rsaInstance.encryptAge(secret, ['ssh-rsa AAAAB3NzaC1yc2E... user@host'])
+   */
+  encryptAge(
+    payload: Uint8Array,
+    recipients: string[],
+    armor: boolean = false,
+    policy: 'strict' | 'legacy' | RSAPolicyOptions = 'strict',
+  ): Uint8Array | string {
+    const encryptor: RSAAgeEncryptorInterface = new this.wasm.RSAAgeEncryptor()
+    const instance = this.createPolicy(policy)
+    try {
+      encryptor.set_policy(instance)
+      recipients.forEach((recipient) => encryptor.add_recipient(recipient))
+      return armor ? encryptor.encrypt_armor(payload, OS_RANDOM) : encryptor.encrypt(payload, OS_RANDOM)
+    } finally {
+      instance.free()
+      encryptor.free()
+    }
+  }
+
+  /**
+   * @desc decrypt age v1 file with ssh-rsa stanza for private keys
+   * @param {Uint8Array|string} data - binary or ASCII armored age file
+   * @returns {Uint8Array} - decrypted payload
+   * @example
+   * const secret = rsaInstance.decryptAge(file)
+   */
+  decryptAge(data: Uint8Array | string): Uint8Array {
+    const file = typeof data === 'string' ? new TextEncoder().encode(data) : data
+    return this.privateInstance.decrypt_age(file)
+  }
+
+  /**
+   * @desc sign RFC 8785 canonical form of JSON document with private keys
+   * @param {Object|string} json - document for sign
+   * @param {string} embedField - when set, returns the canonical document with the signature in this field
+   * @returns {string} - hex signature or signed canonical document
+   * @example
+   * const signature = rsaInstance.signJson({ id: 7, amount: 1.5 })
+   * const signedDocument = rsaInstance.signJson({ id: 7, amount: 1.5 }, 'signature')
+   */
+  signJson(json: object | string, embedField?: string): string {
+    const document = typeof json === 'string' ? json : JSON.stringify(json)
+    return embedField
+      ? this.privateInstance.sign_json_embedded(document, embedField)
+      : this.privateInstance.sign_json(document)
+  }
+
+  /**
+   * @desc verify signature of RFC 8785 canonical form of JSON document with public keys
+   * @param {Object|string} json - signed document
+   * @param {string} signature - hex signature, ignored when embedField is set
+   * @param {string} embedField - field of the document holding the signature
+   * @returns {boolean} - result verify
+   * @example
+   * const verify = rsaInstance.verifyJson({ amount: 1.5, id: 7 }, signature)
+   * const verifyEmbedded = rsaInstance.verifyJson(signedDocument, undefined, 'signature')
+   */
+  verifyJson(json: object | string, signature?: string, embedField?: string): boolean {
+    const document = typeof json === 'string' ? json : JSON.stringify(json)
+    return embedField
+      ? this.publicInstance.verify_json_embedded(document, embedField)
+      : this.publicInstance.verify_json(document, signature)
+  }
+
+  /**
+   * @desc create COSE_Sign1 message (RFC 9052, RFC 8230) with private keys
+   * @param {Uint8Array} payload - data for sign
+   * @param {string} algorithm - PS256, PS384, PS512, RS256, RS384 or RS512
+   * @param {Uint8Array} kid - key identifier for the unprotected header
+   * @param {Uint8Array} externalAad - externally supplied data bound to the signature
+   * @returns {string} - hex tagged COSE_Sign1 message
+   * @example
+   * const message = rsaInstance.signCose(new TextEncoder().encode('hello'), 'PS256')
+   */
+  signCose(
+    payload: Uint8Array,
+    algorithm: string = 'PS256',
+    kid?: Uint8Array,
+    externalAad: Uint8Array = new Uint8Array(),
+  ): string {
+    return this.privateInstance.sign_cose(payload, algorithm, externalAad, kid, OS_RANDOM)
+  }
+
+  /**
+   * @desc verify COSE_Sign1 message with public keys
+   * @param {string} data - hex COSE_Sign1 message
+   * @param {Uint8Array} externalAad - externally supplied data bound to the signature
+   * @param {Uint8Array} detachedPayload - payload of a message without embedded content
+   * @returns {boolean} - result verify
+   * @example
+   * const verify = rsaInstance.verifyCose(message)
+   */
+  verifyCose(data: string, externalAad: Uint8Array = new Uint8Array(), detachedPayload?: Uint8Array): boolean {
+    return this.publicInstance.verify_cose(data, externalAad, detachedPayload)
+  }
+
+  /**
+   * @desc create ASCII-armored detached OpenPGP signature with private keys
+   * @param {Uint8Array} data - data for sign
+   * @param {number} keyCreationTime - creation time (unix seconds) used when the key was exported
+   * @param {string} hash - SHA-256, SHA-384 or SHA-512
+   * @returns {string} - armored signature, e.g. content of an .asc file
+   * @example
+   * const signature = rsaInstance.signOpenPgpDetached(artifact, keyCreationTime)
+   */
+  signOpenPgpDetached(data: Uint8Array, keyCreationTime: number, hash: string = 'SHA-256'): string {
+    return this.privateInstance.sign_openpgp_detached(data, hash, keyCreationTime, Math.floor(Date.now() / 1000))
+  }
+
+  /**
+   * @desc verify ASCII-armored detached OpenPGP signature with public keys
+   * @param {Uint8Array} data - signed data
+   * @param {string} signature - armored signature
+   * @returns {boolean} - result verify
+   * @example
+   * const verify = rsaInstance.verifyOpenPgpDetached(artifact, signature)
+   */
+  verifyOpenPgpDetached(data: Uint8Array, signature: string): boolean {
+    return this.publicInstance.verify_openpgp_detached(data, signature)
+  }
+
+  /**
+   * @desc create PASETO v1.public token with private keys
+   * @param {Object|string} message - token payload
+   * @param {string} footer - unencrypted footer, e.g. key id
+   * @returns {string} - token
+   * @example
+   * const token = rsaInstance.encodePaseto({ sub: 'partner', exp: '2030-01-01T00:00:00+00:00' }, '{"kid":"partner"}')
+   */
+  encodePaseto(message: object | string, footer: string = ''): string {
+    const payload = typeof message === 'string' ? message : JSON.stringify(message)
+    return this.privateInstance.encode_paseto_v1(payload, footer, OS_RANDOM)
+  }
+
+  /**
+   * @desc verify PASETO v1.public token with public keys, throws on invalid token
+   * @param {string} token - token
+   * @param {string} footer - expected footer
+   * @returns {string} - token payload
+   * @example
+   * const payload = JSON.parse(rsaInstance.decodePaseto(token, '{"kid":"partner"}'))
+   */
+  decodePaseto(token: string, footer: string = ''): string {
+    return this.publicInstance.decode_paseto_v1(token, footer)
+  }
+
+  /**
+   * @desc verify WebAuthn assertion of RS256 credential
+   * @param {Uint8Array} credentialPublicKey - COSE_Key stored at registration
+   * @param {Uint8Array} authenticatorData - response.authenticatorData
+   * @param {Uint8Array} clientDataJSON - response.clientDataJSON
+   * @param {Uint8Array} signature - response.signature
+   * @param {string|RSAPolicyOptions} policy - checked against the credential key, strict by default
+   * @returns {WebAuthnAssertion} - result verify, authenticator flags, sign counter and client data;
+   * challenge, origin, rpIdHash and signCount are to be checked by the caller
+   * @example
+   * const { verified, signCount } = rsaInstance.verifyWebAuthnAssertion(credentialPublicKey, authenticatorData, clientDataJSON, signature)
+   */
+  verifyWebAuthnAssertion(
+    credentialPublicKey: Uint8Array,
+    authenticatorData: Uint8Array,
+    clientDataJSON: Uint8Array,
+    signature: Uint8Array,
+    policy: 'strict' | 'legacy' | RSAPolicyOptions = 'strict',
+  ): WebAuthnAssertion {
+    const instance = this.createPolicy(policy)
+    try {
+      return JSON.parse(this.wasm.verify_webauthn_assertion(
+        credentialPublicKey,
+        authenticatorData,
+        clientDataJSON,
+        signature,
+        instance,
+      ))
+    } finally {
+      instance.free()
+    }
+  }
+
+  /**
+   * @desc create HTTP Message Signature (RFC 9421) headers with private keys
+   * @param {HttpRequest} request - method, absolute target uri and headers
+   * @param {Array} components - covered components, e.g. ['@method', '@authority', 'content-digest']
+   * @param {HttpSignatureParameters} parameters - created, expires, nonce, keyid, tag
+   * @param {string} algorithm - rsa-pss-sha512 or rsa-v1_5-sha256
+   * @param {string} label - signature label
+   * @returns {HttpSignatureHeaders} - Signature-Input and Signature header values
+   * @example
+   * const headers = rsaInstance.signHttpMessage(request, ['@method', '@path'], { keyid: 'client' })
+   */
+  signHttpMessage(
+    request: HttpRequest,
+    components: string[],
+    parameters: HttpSignatureParameters = { created: Math.floor(Date.now() / 1000) },
+    algorithm: string = 'rsa-pss-sha512',
+    label: string = 'sig1',
+  ): HttpSignatureHeaders {
+    const message: HttpMessageInterface = new this.wasm.HttpMessage(
+      request.method,
+      request.targetUri,
+      JSON.stringify(request.headers),
+    )
+    try {
+      return JSON.parse(this.privateInstance.sign_http_message(
+        message,
+        label,
+        JSON.stringify(components),
+        JSON.stringify(parameters),
+        algorithm,
+        OS_RANDOM,
+      ))
+    } finally {
+      message.free()
+    }
+  }
+
+  /**
+   * @desc verify HTTP Message Signature (RFC 9421) headers with public keys
+   * @param {HttpRequest} request - method, absolute target uri and headers
+   * @param {HttpSignatureHeaders} headers - Signature-Input and Signature header values
+   * @param {string} algorithm - rsa-pss-sha512 or rsa-v1_5-sha256
+   * @param {string} label - signature label
+   * @param {number} maxAge - maximum age of the signature in seconds, unlimited when omitted
+   * @returns {boolean} - result verify
+   * @example
+   * const verify = rsaInstance.verifyHttpMessage(request, headers)
+   */
+  verifyHttpMessage(
+    request: HttpRequest,
+    headers: HttpSignatureHeaders,
+    algorithm: string = 'rsa-pss-sha512',
+    label: string = 'sig1',
+    maxAge?: number,
+  ): boolean {
+    const message: HttpMessageInterface = new this.wasm.HttpMessage(
+      request.method,
+      request.targetUri,
+      JSON.stringify(request.headers),
+    )
+    try {
+      return this.publicInstance.verify_http_message(
+        message,
+        headers['signature-input'],
+        headers.signature,
+        label,
+        algorithm,
+        Math.floor(Date.now() / 1000),
+        maxAge,
+      )
+    } finally {
+      message.free()
+    }
+  }
+
   /**
    * @desc encryption message with public keys
    * @param {string} message - data for encrypt
@@ -300,8 +1134,7 @@ export default class RSA implements RSAInterface {
       throw new Error('message is not define')
     }
 
-    const randomSeed = randomBytes(32).toString('hex')
-    return this.publicInstance.encrypt(message, randomSeed)
+    return this.publicInstance.encrypt_seedless(message)
   }
 
   /**
@@ -328,6 +1161,38 @@ export default class RSA implements RSAInterface {
     return this.privateInstance.decrypt(encryptedMessage)
   }
 
+  /**
+   * @desc wrap raw symmetric key with public keys (RSA-OAEP)
+   * @param {string} key - raw AES key in hex (16, 24 or 32 bytes)
+   * @param {string} hash - OAEP hash algorithm
+   * @returns {string} - wrapped key in hex
+   * @example
+   * const wrappedKey = rsaInstance.wrapKey('000102030405060708090a0b0c0d0e0f', 'SHA-256')
+   */
+  wrapKey(key: string, hash: string = 'SHA-256'): string {
+    if (!key) {
+      throw new Error('key is not define')
+    }
+
+    return this.publicInstance.wrap_key(key, hash, OS_RANDOM)
+  }
+
+  /**
+   * @desc unwrap raw symmetric key with private keys (RSA-OAEP)
+   * @param {string} wrappedKey - wrapped key in hex
+   * @param {string} hash - OAEP hash algorithm
+   * @returns {string} - raw AES key in hex
+   * @example
+   * const key = rsaInstance.unwrapKey(wrappedKey, 'SHA-256')
+   */
+  unwrapKey(wrappedKey: string, hash: string = 'SHA-256'): string {
+    if (!wrappedKey) {
+      throw new Error('wrapped key is not define')
+    }
+
+    return this.privateInstance.unwrap_key(wrappedKey, hash)
+  }
+
   /**
    * @desc generate private key in PEM format
    * @returns {string} - key
@@ -369,4 +1234,63 @@ export default class RSA implements RSAInterface {
   publicKeyToPEM(): string {
     return this.publicInstance.to_pkcs8_pem()
   }
+
+  /**
+   * @desc generate public key as OpenSSH ssh-rsa line, e.g. for age recipients
+   * @param {string} comment - trailing comment
+   * @returns {string} - key
+   * @example
+   * const sshPublicKey = rsaInstance.publicKeyToSSH('user@host')
+   */
+  publicKeyToSSH(comment: string = ''): string {
+    return this.privateInstance.to_ssh_public_key(comment)
+  }
+
+  /**
+   * @desc generate ASCII-armored OpenPGP v4 secret key with self-signed user id, not protected by a passphrase
+   * @param {string} userId - user id, e.g. 'Release <release@example.com>'
+   * @param {number} creationTime - key creation time (unix seconds), part of the key fingerprint
+   * @returns {string} - armored private key block
+   * @example
+   * const privatePgp = rsaInstance.privateKeyToOpenPgp('Release <release@example.com>', keyCreationTime)
+   */
+  privateKeyToOpenPgp(userId: string, creationTime: number): string {
+    return this.privateInstance.to_openpgp_secret_key(userId, creationTime)
+  }
+
+  /**
+   * @desc generate ASCII-armored OpenPGP v4 public key with self-signed user id
+   * @param {string} userId - user id, e.g. 'Release <release@example.com>'
+   * @param {number} creationTime - key creation time (unix seconds), part of the key fingerprint
+   * @returns {string} - armored public key block
+   * @example
+   * const publicPgp = rsaInstance.publicKeyToOpenPgp('Release <release@example.com>', keyCreationTime)
+   */
+  publicKeyToOpenPgp(userId: string, creationTime: number): string {
+    return this.privateInstance.to_openpgp_public_key(userId, creationTime)
+  }
+
+  /**
+   * @desc generate private key as COSE_Key (RFC 8230)
+   * @param {Uint8Array} kid - key identifier
+   * @param {string} algorithm - algorithm the key is restricted to
+   * @returns {string} - hex CBOR key
+   * @example
+   * const privateCose = rsaInstance.privateKeyToCose(undefined, 'PS256')
+   */
+  privateKeyToCose(kid?: Uint8Array, algorithm?: string): string {
+    return this.privateInstance.to_cose_key(kid, algorithm)
+  }
+
+  /**
+   * @desc generate public key as COSE_Key (RFC 8230)
+   * @param {Uint8Array} kid - key identifier
+   * @param {string} algorithm - algorithm the key is restricted to
+   * @returns {string} - hex CBOR key
+   * @example
+   * const publicCose = rsaInstance.publicKeyToCose(undefined, 'PS256')
+   */
+  publicKeyToCose(kid?: Uint8Array, algorithm?: string): string {
+    return this.publicInstance.to_cose_key(kid, algorithm)
+  }
 }
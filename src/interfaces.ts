@@ -1,12 +1,56 @@
 export interface RSAPrivateKeyInterface {
   free(): void
   generate(bits: number, random: string): void
+  generate_seedless(bits: number): void
+  generate_parallel?(bits: number, random: string): void
+  generate_with(bits: number, publicExponent: string, primeCount: number, random: string): void
+  generate_fips(bits: number, method: string, random: string): string
+  recover_from(n: string, d: string, e: string): void
+  get_crt_components(): string
+  validate(): string
+  from_crt_components(components: string): void
+  derive_from_passphrase(bits: number, passphrase: string, salt: string, version: number): void
+  derive_from_mnemonic(bits: number, mnemonic: string, passphrase: string, version: number): void
   sign_message(message: string): string
+  sign_message_pss(message: string, hash: string, randomSeed: string): string
+  blind_sign(blindedMessage: string): string
   decrypt(encryptMessage: string): string
+  unwrap_key(wrappedKey: string, hash: string): string
+  unwrap_key_rsa_aes(wrappedKey: string, hash: string): string
+  sign_cms_detached(content: Uint8Array, certificate: string, signingTime: number): string
+  sign_cms_detached_pem(content: Uint8Array, certificate: string, signingTime: number): string
+  decrypt_cms(data: string, certificate: string): Uint8Array
+  decrypt_age(data: Uint8Array): Uint8Array
+  to_ssh_public_key(comment: string): string
+  sign_json(json: string): string
+  sign_json_embedded(json: string, field: string): string
+  sign_openpgp_detached(data: Uint8Array, hash: string, keyCreationTime: number, signingTime: number): string
+  to_openpgp_public_key(userId: string, creationTime: number): string
+  to_openpgp_secret_key(userId: string, creationTime: number): string
+  encode_paseto_v1(message: string, footer: string, randomSeed: string): string
+  sign_cose(
+    payload: Uint8Array,
+    algorithm: string,
+    externalAad: Uint8Array,
+    kid: Uint8Array | undefined,
+    randomSeed: string,
+  ): string
+  sign_http_message(
+    message: HttpMessageInterface,
+    label: string,
+    components: string,
+    parameters: string,
+    algorithm: string,
+    randomSeed: string,
+  ): string
   get_primes(): string
+  get_primes_hex(): string
   generate_from(n: string, d: string, e: string, primes: string): void
   to_pkcs8_pem(): string
   from_pkcs8_pem(data: string): void
+  to_cose_key(kid?: Uint8Array, algorithm?: string): string
+  from_cose_key(data: string): void
+  set_policy(policy: RSAPolicyInterface): void
   get_e(): string
   get_d(): string
   get_n(): string
@@ -15,14 +59,147 @@ export interface RSAPrivateKeyInterface {
 export interface RSAPublicKeyInterface {
   free(): void
   create(n: string, e: string): void
+  analyze(): string
   verify_message(message: string, signature: string): boolean
+  verify_message_pss(message: string, signature: string, hash: string): boolean
+  blind(message: string, variant: string, randomSeed: string): RSABlindingResultInterface
+  finalize(message: string, blindSignature: string, blinding: RSABlindingResultInterface): string
+  verify_blind_signature(message: string, signature: string, messagePrefix: string, variant: string): boolean
   encrypt(message: string, randomSeed: string): string
+  encrypt_seedless(message: string): string
+  wrap_key(key: string, hash: string, randomSeed: string): string
+  wrap_key_rsa_aes(key: string, aesKeyBits: number, hash: string, randomSeed: string): string
+  verify_cms_detached(content: Uint8Array, signature: string): boolean
+  verify_json(json: string, signature: string): boolean
+  verify_json_embedded(json: string, field: string): boolean
+  verify_openpgp_detached(data: Uint8Array, signature: string): boolean
+  openpgp_fingerprint(creationTime: number): string
+  decode_paseto_v1(token: string, footer: string): string
+  verify_cose(data: string, externalAad: Uint8Array, detachedPayload?: Uint8Array): boolean
+  verify_http_message(
+    message: HttpMessageInterface,
+    signatureInput: string,
+    signature: string,
+    label: string,
+    algorithm: string,
+    now: number,
+    maxAge?: number,
+  ): boolean
   to_pkcs8_pem(): string
   from_pkcs8_pem(data: string): void
+  to_cose_key(kid?: Uint8Array, algorithm?: string): string
+  from_cose_key(data: string): void
+  set_policy(policy: RSAPolicyInterface): void
   get_e(): string
   get_n(): string
 }
 
+export interface RSAPolicyInterface {
+  free(): void
+  to_json(): string
+}
+
+export interface RSABlindingResultInterface {
+  free(): void
+  get_blinded_message(): string
+  get_inv(): string
+  get_message_prefix(): string
+}
+
+export interface RSASignerInterface {
+  free(): void
+  update(chunk: Uint8Array): void
+  update_str(chunk: string): void
+  finalize(): string
+}
+
+export interface RSAVerifierInterface {
+  free(): void
+  update(chunk: Uint8Array): void
+  update_str(chunk: string): void
+  finalize(signature: string): boolean
+}
+
+export interface RSAEnvelopeBuilderInterface {
+  free(): void
+  set_policy(policy: RSAPolicyInterface): void
+  add_recipient(certificate: string, oaepHash?: string): void
+  encrypt(content: Uint8Array, randomSeed: string): string
+  encrypt_pem(content: Uint8Array, randomSeed: string): string
+}
+
+export interface RSABatchGcdInterface {
+  free(): void
+  add_key(key: RSAPublicKeyInterface): void
+  find_shared_factors(): string
+}
+
+export interface RSAAgeEncryptorInterface {
+  free(): void
+  set_policy(policy: RSAPolicyInterface): void
+  add_recipient(sshPublicKey: string): void
+  encrypt(payload: Uint8Array, randomSeed: string): Uint8Array
+  encrypt_armor(payload: Uint8Array, randomSeed: string): string
+}
+
+export interface RSAKeyGeneratorInterface {
+  free(): void
+  step(maxCandidates: number): boolean
+  candidates_tested(): number
+  primes_found(): number
+  is_done(): boolean
+  cancel(): void
+  finish(privateKey: RSAPrivateKeyInterface): void
+}
+
+export interface RSAKeyGenerationProgress {
+  candidatesTested: number
+  primesFound: number
+}
+
+export interface RSAKeyGeneration {
+  promise: Promise<RSAPrivate>
+  cancel(): void
+}
+
+export interface HttpMessageInterface {
+  free(): void
+}
+
+export interface HttpRequest {
+  method: string
+  targetUri: string
+  headers: { [name: string]: string | string[] }
+}
+
+export interface HttpSignatureParameters {
+  created?: number
+  expires?: number
+  nonce?: string
+  keyid?: string
+  tag?: string
+}
+
+export interface HttpSignatureHeaders {
+  'signature-input': string
+  signature: string
+}
+
+export interface WebAuthnAssertion {
+  verified: boolean
+  userPresent: boolean
+  userVerified: boolean
+  backupEligible: boolean
+  backupState: boolean
+  attestedCredentialData: boolean
+  extensionData: boolean
+  signCount: number
+  rpIdHash: string
+  type: string
+  challenge: string
+  origin: string
+}
+
 export interface RSAPublic {
   n: string
   e: string
@@ -33,24 +210,192 @@ export interface RSAPrivate extends RSAPublic {
   primes: string[]
 }
 
+export interface RSAFipsKeyGenerationReport {
+  standard: string
+  method: string
+  primes: string
+  randomBitGenerator: string
+  entropySource: string
+  bits: number
+  publicExponent: string
+  privateExponent: string
+  millerRabinRounds: number
+  candidatesTested: number
+}
+
+export interface RSAFipsPrivate extends RSAPrivate {
+  report: RSAFipsKeyGenerationReport
+}
+
+export interface RSAOtherPrimeInfo {
+  r: string
+  d: string
+  t: string
+}
+
+export interface RSAPrivateCrt {
+  kty: string
+  n: string
+  e: string
+  d: string
+  p: string
+  q: string
+  dp: string
+  dq: string
+  qi: string
+  oth?: RSAOtherPrimeInfo[]
+}
+
+export interface RSAKeyValidationReport {
+  valid: boolean
+  checks: { [check: string]: boolean }
+  failed: string[]
+}
+
+export interface RSAPublicKeyAnalysis {
+  bits: number
+  exponent: string
+  exponentEven: boolean
+  exponentTooSmall: boolean
+  exponentTooLarge: boolean
+  smallFactor: number | null
+  perfectPower: boolean
+  closePrimes: boolean
+  rocaFingerprint: boolean
+  weak: boolean
+  weaknesses: string[]
+}
+
+export interface RSASharedFactorKey {
+  index: number
+  p: string
+  q: string
+}
+
+export interface RSASharedFactors {
+  groups: { factor: string; keys: RSASharedFactorKey[] }[]
+  duplicates: number[][]
+}
+
+export interface RSAPolicyOptions {
+  minModulusBits?: number
+  exponents?: string[] | null
+  paddings?: string[]
+  hashes?: string[]
+  sha1Verification?: boolean
+}
+
+export interface RSAKeyGenerationOptions {
+  publicExponent?: string
+  primeCount?: number
+}
+
 export interface RSAInterface {
-  generateRSAPrivate(bits: number): RSAPrivate
+  setRSAPolicy(policy: 'strict' | 'legacy' | RSAPolicyOptions): RSAPolicyOptions
+
+  generateRSAPrivate(bits: number, options?: RSAKeyGenerationOptions): RSAPrivate
+  generateRSAPrivateFips(bits: number, method?: string): RSAFipsPrivate
+  deriveRSAPrivateFromPassphrase(bits: number, passphrase: string, salt: string, version?: number): RSAPrivate
+  deriveRSAPrivateFromMnemonic(bits: number, mnemonic: string, passphrase?: string, version?: number): RSAPrivate
+  generateRSAPrivateParallel(bits: number, threads: number): Promise<RSAPrivate>
+  generateRSAPrivateAsync(
+    bits: number,
+    onProgress?: (progress: RSAKeyGenerationProgress) => void,
+    candidatesPerStep?: number,
+  ): RSAKeyGeneration
   generateRSAPrivateFrom(n: string, d: string, e: string, primes: string[]): RSAPrivate
+  recoverRSAPrivateFrom(n: string, d: string, e: string): RSAPrivate
   createRSAPrivateFromPEM(key: string): RSAPrivate
+  createRSAPrivateFromCrt(components: RSAPrivateCrt): RSAPrivate
+  getRSAPrivateCrt(): RSAPrivateCrt
+  validateRSAPrivate(): RSAKeyValidationReport
 
   createRSAPublic(n: string, e: string): RSAPublic
+  analyzeRSAPublic(n: string, e: string): RSAPublicKeyAnalysis
+  findSharedFactors(keys: RSAPublic[]): RSASharedFactors
   createRSAPublicFromPEM(key: string): RSAPublic
 
   getRSAPrivate(): RSAPrivate
   getRSAPublic(): RSAPublic
   getPrivatePrimes(): string[]
+  getPrivatePrimesHex(): string[]
 
   privateKeyToPEM(): string
   publicKeyToPEM(): string
+  publicKeyToSSH(comment?: string): string
+  privateKeyToOpenPgp(userId: string, creationTime: number): string
+  publicKeyToOpenPgp(userId: string, creationTime: number): string
+  privateKeyToCose(kid?: Uint8Array, algorithm?: string): string
+  publicKeyToCose(kid?: Uint8Array, algorithm?: string): string
 
   publicEncrypt(message): String
   privateDecrypt(encryptedMessage): String
 
+  wrapKey(key: string, hash?: string): string
+  unwrapKey(wrappedKey: string, hash?: string): string
+
   signMessage(message: string): string
   verify(message: string, signature: string): boolean
+
+  signCmsDetached(content: Uint8Array, certificate: string, pem?: boolean): string
+  verifyCmsDetached(content: Uint8Array, signature: string): boolean
+  verifyCmsDetachedWithCertificate(
+    content: Uint8Array,
+    signature: string,
+    policy?: 'strict' | 'legacy' | RSAPolicyOptions,
+  ): string | undefined
+  encryptCms(
+    content: Uint8Array,
+    certificates: string[],
+    contentEncryption?: string,
+    oaepHash?: string,
+    policy?: 'strict' | 'legacy' | RSAPolicyOptions,
+  ): string
+  decryptCms(data: string, certificate: string): Uint8Array
+  encryptAge(
+    payload: Uint8Array,
+    recipients: string[],
+    armor?: boolean,
+    policy?: 'strict' | 'legacy' | RSAPolicyOptions,
+  ): Uint8Array | string
+  decryptAge(data: Uint8Array | string): Uint8Array
+
+  signJson(json: object | string, embedField?: string): string
+  verifyJson(json: object | string, signature?: string, embedField?: string): boolean
+
+  signCose(payload: Uint8Array, algorithm?: string, kid?: Uint8Array, externalAad?: Uint8Array): string
+  verifyCose(data: string, externalAad?: Uint8Array, detachedPayload?: Uint8Array): boolean
+  signOpenPgpDetached(data: Uint8Array, keyCreationTime: number, hash?: string): string
+  verifyOpenPgpDetached(data: Uint8Array, signature: string): boolean
+  encodePaseto(message: object | string, footer?: string): string
+  decodePaseto(token: string, footer?: string): string
+  verifyWebAuthnAssertion(
+    credentialPublicKey: Uint8Array,
+    authenticatorData: Uint8Array,
+    clientDataJSON: Uint8Array,
+    signature: Uint8Array,
+    policy?: 'strict' | 'legacy' | RSAPolicyOptions,
+  ): WebAuthnAssertion
+
+  signHttpMessage(
+    request: HttpRequest,
+    components: string[],
+    parameters?: HttpSignatureParameters,
+    algorithm?: string,
+    label?: string,
+  ): HttpSignatureHeaders
+  verifyHttpMessage(
+    request: HttpRequest,
+    headers: HttpSignatureHeaders,
+    algorithm?: string,
+    label?: string,
+    maxAge?: number,
+  ): boolean
+
+  blindMessage(message: string, variant?: string): RSABlindingResultInterface
+  blindSign(blindedMessage: string): string
+  finalizeBlindSignature(message: string, blindSignature: string, blinding: RSABlindingResultInterface): string
+  verifyBlindSignature(message: string, signature: string, messagePrefix: string, variant?: string): boolean
+  createSigner(hash?: string): RSASignerInterface
+  createVerifier(hash?: string): RSAVerifierInterface
 }
@@ -0,0 +1,420 @@
+use super::*;
+use chacha20poly1305::{ ChaCha20Poly1305, Key, Nonce };
+use chacha20poly1305::aead::{ Aead, KeyInit };
+use hkdf::Hkdf;
+use hmac::{ Hmac, Mac };
+use hashing::HashAlgorithm;
+use private_keys::RSAPrivateKeyPair;
+use policy::{ Padding, RSAPolicy };
+
+const VERSION_LINE: &str = "age-encryption.org/v1";
+const SSH_RSA_LABEL: &[u8] = b"age-encryption.org/v1/ssh-rsa";
+const ARMOR_LABEL: &str = "AGE ENCRYPTED FILE";
+
+const FILE_KEY_SIZE: usize = 16;
+const PAYLOAD_NONCE_SIZE: usize = 16;
+const CHUNK_SIZE: usize = 64 * 1024;
+const TAG_SIZE: usize = 16;
+const COLUMNS: usize = 64;
+
+fn encode_base64(data: &[u8]) -> String {
+    base64::encode_config(data, base64::STANDARD_NO_PAD)
+}
+
+/// Canonical unpadded base64 as required by the age header format.
+fn decode_base64(data: &str) -> Vec<u8> {
+    let decoded = base64::decode_config(data, base64::STANDARD_NO_PAD).expect("invalid age header encoding");
+    if encode_base64(&decoded) != data {
+        panic!("invalid age header encoding");
+    }
+
+    decoded
+}
+
+fn ssh_string(data: &[u8], output: &mut Vec<u8>) {
+    output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    output.extend_from_slice(data);
+}
+
+fn ssh_mpint(value: &BigUint, output: &mut Vec<u8>) {
+    let mut bytes = value.to_bytes_be();
+    if bytes[0] & 0x80 != 0 {
+        bytes.insert(0, 0);
+    }
+    ssh_string(&bytes, output);
+}
+
+/// `ssh-rsa` public key in SSH wire format, RFC 4253 6.6.
+fn ssh_public_key(key: &impl PublicKeyParts) -> Vec<u8> {
+    let mut output = vec![];
+    ssh_string(b"ssh-rsa", &mut output);
+    ssh_mpint(key.e(), &mut output);
+    ssh_mpint(key.n(), &mut output);
+    output
+}
+
+fn read_ssh_string<'a>(data: &'a [u8], position: &mut usize) -> &'a [u8] {
+    if data.len() - *position < 4 {
+        panic!("invalid ssh public key");
+    }
+    let mut len = [0u8; 4];
+    len.copy_from_slice(&data[*position..*position + 4]);
+    let len = u32::from_be_bytes(len) as usize;
+    *position += 4;
+    if data.len() - *position < len {
+        panic!("invalid ssh public key");
+    }
+
+    let bytes = &data[*position..*position + len];
+    *position += len;
+    bytes
+}
+
+/// Parses an `ssh-rsa AAAA... comment` line as found in `authorized_keys` or `id_rsa.pub`.
+fn parse_ssh_rsa(line: &str) -> RsaPublicKey {
+    let mut fields = line.split_whitespace();
+    if fields.next() != Some("ssh-rsa") {
+        panic!("recipient is not an ssh-rsa public key");
+    }
+    let data = base64::decode(fields.next().expect("invalid ssh public key")).expect("invalid ssh public key");
+
+    let mut position = 0;
+    if read_ssh_string(&data, &mut position) != b"ssh-rsa" {
+        panic!("invalid ssh public key");
+    }
+    let e = BigUint::from_bytes_be(read_ssh_string(&data, &mut position));
+    let n = BigUint::from_bytes_be(read_ssh_string(&data, &mut position));
+    if position != data.len() {
+        panic!("invalid ssh public key");
+    }
+
+    RsaPublicKey::new(n, e).expect("invalid create public instance")
+}
+
+/// Stanza tag: the first four bytes of SHA-256 over the SSH public key.
+fn recipient_tag(key: &impl PublicKeyParts) -> String {
+    encode_base64(&HashAlgorithm::Sha256.digest(&ssh_public_key(key))[..4])
+}
+
+fn hkdf_sha256(ikm: &[u8], salt: &[u8], info: &[u8]) -> [u8; 32] {
+    let mut okm = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(salt), ikm).expand(info, &mut okm).expect("hkdf error");
+    okm
+}
+
+fn header_mac(file_key: &[u8]) -> Hmac<Sha256> {
+    <Hmac<Sha256> as Mac>::new_from_slice(&hkdf_sha256(file_key, &[], b"header")).expect("hmac error")
+}
+
+/// STREAM nonce: 11 byte big-endian chunk counter followed by the last chunk flag.
+fn chunk_nonce(counter: u64, last: bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[3..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+pub fn encrypt<R: RngCore>(rng: &mut R, recipients: &[RsaPublicKey], payload: &[u8]) -> Vec<u8> {
+    if recipients.is_empty() {
+        panic!("no age recipients");
+    }
+
+    let mut file_key = [0u8; FILE_KEY_SIZE];
+    rng.fill_bytes(&mut file_key);
+
+    let mut header = format!("{}\n", VERSION_LINE);
+    for recipient in recipients {
+        let wrapped = padding::oaep_encrypt(rng, recipient, HashAlgorithm::Sha256, SSH_RSA_LABEL, &file_key);
+        header.push_str(&format!("-> ssh-rsa {}\n", recipient_tag(recipient)));
+        let body = encode_base64(&wrapped);
+        for line in body.as_bytes().chunks(COLUMNS) {
+            header.push_str(std::str::from_utf8(line).unwrap());
+            header.push('\n');
+        }
+        // The final body line is always shorter than a full line, even when empty.
+        if body.len() % COLUMNS == 0 {
+            header.push('\n');
+        }
+    }
+    header.push_str("---");
+
+    let mut mac = header_mac(&file_key);
+    mac.update(header.as_bytes());
+    header.push_str(&format!(" {}\n", encode_base64(&mac.finalize().into_bytes())));
+
+    let mut nonce = [0u8; PAYLOAD_NONCE_SIZE];
+    rng.fill_bytes(&mut nonce);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&hkdf_sha256(&file_key, &nonce, b"payload")));
+
+    let mut output = header.into_bytes();
+    output.extend_from_slice(&nonce);
+    let chunks: Vec<&[u8]> = if payload.is_empty() { vec![payload] } else { payload.chunks(CHUNK_SIZE).collect() };
+    for (counter, chunk) in chunks.iter().enumerate() {
+        let nonce = chunk_nonce(counter as u64, counter == chunks.len() - 1);
+        output.extend(cipher.encrypt(Nonce::from_slice(&nonce), *chunk).expect("encryption error"));
+    }
+
+    output
+}
+
+fn read_line<'a>(data: &'a [u8], position: &mut usize) -> &'a str {
+    let end = data[*position..].iter().position(|byte| *byte == b'\n').expect("invalid age header");
+    let line = std::str::from_utf8(&data[*position..*position + end]).expect("invalid age header");
+    *position += end + 1;
+    line
+}
+
+pub fn decrypt(key: &RsaPrivateKey, data: &[u8]) -> Vec<u8> {
+    let mut position = 0;
+    if read_line(data, &mut position) != VERSION_LINE {
+        panic!("unsupported age version");
+    }
+
+    let tag = recipient_tag(key);
+    let mut file_key = None;
+    let mac_line = loop {
+        let line_start = position;
+        let line = read_line(data, &mut position);
+        if let Some(mac) = line.strip_prefix("--- ") {
+            break (line_start, mac);
+        }
+
+        let arguments: Vec<&str> = line.strip_prefix("-> ").expect("invalid age header").split(' ').collect();
+        let mut body = String::new();
+        loop {
+            let body_line = read_line(data, &mut position);
+            body.push_str(body_line);
+            if body_line.len() < COLUMNS {
+                break;
+            }
+        }
+
+        if file_key.is_none() && arguments == ["ssh-rsa", tag.as_str()] {
+            let unwrapped = padding::oaep_decrypt(key, HashAlgorithm::Sha256, SSH_RSA_LABEL, &decode_base64(&body));
+            if unwrapped.len() != FILE_KEY_SIZE {
+                panic!("invalid age file key");
+            }
+            file_key = Some(unwrapped);
+        }
+    };
+    let file_key = file_key.expect("no age recipient stanza for this key");
+
+    let (mac_start, mac_value) = mac_line;
+    let mut mac = header_mac(&file_key);
+    mac.update(&data[..mac_start + 3]);
+    mac.verify_slice(&decode_base64(mac_value)).expect("invalid age header mac");
+
+    let payload = &data[position..];
+    if payload.len() < PAYLOAD_NONCE_SIZE + TAG_SIZE {
+        panic!("invalid age payload");
+    }
+    let (nonce, ciphertext) = payload.split_at(PAYLOAD_NONCE_SIZE);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&hkdf_sha256(&file_key, nonce, b"payload")));
+
+    let chunks: Vec<&[u8]> = ciphertext.chunks(CHUNK_SIZE + TAG_SIZE).collect();
+    let mut plaintext = vec![];
+    for (counter, chunk) in chunks.iter().enumerate() {
+        let last = counter == chunks.len() - 1;
+        let decrypted = cipher.decrypt(Nonce::from_slice(&chunk_nonce(counter as u64, last)), *chunk).expect("invalid age payload");
+        if last && decrypted.is_empty() && counter > 0 {
+            panic!("invalid age payload");
+        }
+        plaintext.extend(decrypted);
+    }
+
+    plaintext
+}
+
+/// Collects `ssh-rsa` recipients and produces age v1 files.
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct RSAAgeEncryptor {
+    recipients: Vec<RsaPublicKey>,
+    policy: RSAPolicy
+}
+
+#[wasm_bindgen]
+impl RSAAgeEncryptor {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        utils::set_panic_hook();
+        RSAAgeEncryptor::default()
+    }
+
+    /// Replaces the strict default policy, checked by every following `add_recipient`.
+    pub fn set_policy(&mut self, policy: &RSAPolicy) {
+        self.policy = policy.clone();
+    }
+
+    /// Adds a recipient given as an OpenSSH `ssh-rsa` public key line.
+    /// Panics unless the policy allows the key with RSAES-OAEP and SHA-256.
+    pub fn add_recipient(&mut self, ssh_public_key: &str) {
+        utils::set_panic_hook();
+        let key = parse_ssh_rsa(ssh_public_key);
+        self.policy.check_operation(&key, Padding::Oaep, Some(HashAlgorithm::Sha256));
+        self.recipients.push(key);
+    }
+
+    pub fn encrypt(&self, payload: &[u8], random_seed: &str) -> Vec<u8> {
+        utils::set_panic_hook();
+        let mut rng = utils::seeded_rng(random_seed);
+        encrypt(&mut rng, &self.recipients, payload)
+    }
+
+    /// Same as `encrypt` in the ASCII armored form (`age --armor`).
+    pub fn encrypt_armor(&self, payload: &[u8], random_seed: &str) -> String {
+        utils::set_panic_hook();
+        let mut rng = utils::seeded_rng(random_seed);
+        der::to_pem(ARMOR_LABEL, &encrypt(&mut rng, &self.recipients, payload))
+    }
+}
+
+#[wasm_bindgen]
+impl RSAPrivateKeyPair {
+    /// Decrypts a binary or ASCII armored age file with an `ssh-rsa` stanza for this key.
+    pub fn decrypt_age(&self, data: &[u8]) -> Vec<u8> {
+        utils::set_panic_hook();
+        let armor = format!("-----BEGIN {}-----", ARMOR_LABEL);
+        let key = self.decrypting_instance(Padding::Oaep, Some(HashAlgorithm::Sha256));
+        match std::str::from_utf8(data) {
+            Ok(text) if text.trim_start().starts_with(&armor) => decrypt(key, &der::from_pem_or_hex(text)),
+            _ => decrypt(key, data)
+        }
+    }
+
+    /// Public key as an OpenSSH `ssh-rsa` line, usable as an age recipient.
+    pub fn to_ssh_public_key(&self, comment: &str) -> String {
+        utils::set_panic_hook();
+        let line = format!("ssh-rsa {}", base64::encode(ssh_public_key(self.instance())));
+        if comment.is_empty() { line } else { format!("{} {}", line, comment) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fixtures::legacy_private_key_pair;
+    use fixtures::PRIVATE_KEY;
+
+    const SEED: &str = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+    const OTHER_SEED: &str = "a0b1c2d3e4f5061728394a5b6c7d8e9fa0b1c2d3e4f5061728394a5b6c7d8e9f";
+
+    fn private_key_pair() -> RSAPrivateKeyPair {
+        let mut private_instance = RSAPrivateKeyPair::new();
+        private_instance.from_pkcs8_pem(PRIVATE_KEY);
+        private_instance
+    }
+
+    fn encryptor(recipients: &[&RSAPrivateKeyPair]) -> RSAAgeEncryptor {
+        encryptor_with_policy(&RSAPolicy::strict(), recipients)
+    }
+
+    fn encryptor_with_policy(policy: &RSAPolicy, recipients: &[&RSAPrivateKeyPair]) -> RSAAgeEncryptor {
+        let mut encryptor = RSAAgeEncryptor::new();
+        encryptor.set_policy(policy);
+        for recipient in recipients {
+            encryptor.add_recipient(&recipient.to_ssh_public_key("user@host"));
+        }
+        encryptor
+    }
+
+    #[test]
+    fn ssh_public_key_round_trip() {
+        let private_instance = private_key_pair();
+        let line = private_instance.to_ssh_public_key("user@host");
+        assert!(line.starts_with("ssh-rsa AAAAB3NzaC1yc2E"));
+        assert!(line.ends_with(" user@host"));
+        assert_eq!(parse_ssh_rsa(&line), private_instance.instance().to_public_key());
+    }
+
+    #[test]
+    fn header_format() {
+        let private_instance = private_key_pair();
+        let file = encryptor(&[&private_instance]).encrypt(b"secret", SEED);
+        let text = String::from_utf8_lossy(&file);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "age-encryption.org/v1");
+        assert_eq!(lines[1], format!("-> ssh-rsa {}", recipient_tag(private_instance.instance())));
+        assert!(lines[2..7].iter().all(|line| line.len() == COLUMNS));
+        assert_eq!(lines[7].len(), 342 - 5 * COLUMNS);
+        assert!(lines[8].starts_with("--- "));
+    }
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        let private_instance = private_key_pair();
+        let encryptor = encryptor(&[&private_instance]);
+        for len in [0usize, 1, CHUNK_SIZE - 1, CHUNK_SIZE, CHUNK_SIZE + 1, 3 * CHUNK_SIZE].iter() {
+            let payload: Vec<u8> = (0..*len).map(|index| index as u8).collect();
+            let file = encryptor.encrypt(&payload, SEED);
+            assert_eq!(private_instance.decrypt_age(&file), payload, "{}", len);
+        }
+    }
+
+    #[test]
+    fn decrypt_reference_file() {
+        // encrypted by the age 0.11 crate to the ssh-rsa recipient of PRIVATE_KEY
+        let file = include_bytes!("testdata/age_ssh_rsa.age");
+        assert_eq!(private_key_pair().decrypt_age(file), b"encrypted by the age reference implementation\n".to_vec());
+    }
+
+    #[test]
+    fn encrypt_to_multiple_recipients() {
+        let private_instance = private_key_pair();
+        let mut other_instance = legacy_private_key_pair();
+        other_instance.generate(1024, OTHER_SEED);
+
+        let file = encryptor_with_policy(&RSAPolicy::legacy(), &[&other_instance, &private_instance]).encrypt_armor(b"shared secret", SEED);
+        assert!(file.starts_with("-----BEGIN AGE ENCRYPTED FILE-----\n"));
+        assert_eq!(private_instance.decrypt_age(file.as_bytes()), b"shared secret".to_vec());
+        assert_eq!(other_instance.decrypt_age(file.as_bytes()), b"shared secret".to_vec());
+    }
+
+    #[test]
+    #[should_panic(expected = "no age recipient stanza for this key")]
+    fn decrypt_without_recipient() {
+        let private_instance = private_key_pair();
+        let mut other_instance = legacy_private_key_pair();
+        other_instance.generate(1024, OTHER_SEED);
+        let file = encryptor_with_policy(&RSAPolicy::legacy(), &[&other_instance]).encrypt(b"secret", SEED);
+        private_instance.decrypt_age(&file);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid age header mac")]
+    fn decrypt_modified_header() {
+        let private_instance = private_key_pair();
+        let mut other_instance = legacy_private_key_pair();
+        other_instance.generate(1024, OTHER_SEED);
+        let file = encryptor_with_policy(&RSAPolicy::legacy(), &[&private_instance, &other_instance]).encrypt(b"secret", SEED);
+
+        // Drop the second stanza, keeping the first one intact.
+        let text = String::from_utf8_lossy(&file).to_string();
+        let second = text.match_indices("-> ").nth(1).unwrap().0;
+        let footer = text.find("--- ").unwrap();
+        let modified = [&file[..second], &file[footer..]].concat();
+        private_instance.decrypt_age(&modified);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid age payload")]
+    fn decrypt_truncated_payload() {
+        let private_instance = private_key_pair();
+        let file = encryptor(&[&private_instance]).encrypt(&vec![7u8; 2 * CHUNK_SIZE], SEED);
+        private_instance.decrypt_age(&file[..file.len() - CHUNK_SIZE - TAG_SIZE]);
+    }
+
+    #[test]
+    #[should_panic(expected = "recipient is not an ssh-rsa public key")]
+    fn add_ed25519_recipient() {
+        RSAAgeEncryptor::new().add_recipient("ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl");
+    }
+
+    #[test]
+    #[should_panic(expected = "key size 1024 is below the policy minimum of 2048 bits")]
+    fn strict_policy_rejects_small_recipient() {
+        let mut other_instance = legacy_private_key_pair();
+        other_instance.generate(1024, OTHER_SEED);
+        encryptor(&[&other_instance]);
+    }
+}
@@ -0,0 +1,214 @@
+use super::*;
+use num_traits::{ Num, One, Pow, ToPrimitive, Zero };
+use public_keys::RSAPublicKeyPair;
+
+const MIN_MODULUS_BITS: usize = 2048;
+const MIN_EXPONENT: u32 = 65537;
+const MAX_EXPONENT_BITS: usize = 256;
+const TRIAL_DIVISION_LIMIT: u32 = 1 << 16;
+const FERMAT_ITERATIONS: usize = 1000;
+
+/// Primes of the ROCA fingerprint test from the paper's detection tool.
+const ROCA_PRIMES: [u32; 38] = [
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89,
+    97, 101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167
+];
+
+fn smallest_factor(n: &BigUint) -> Option<u32> {
+    keygen::small_primes(TRIAL_DIVISION_LIMIT).into_iter().find(|prime| (n % *prime).is_zero() && n != &BigUint::from(*prime))
+}
+
+/// `n = m^k` for some `k > 1`. Roots below the trial division limit are left to `smallest_factor`.
+fn is_perfect_power(n: &BigUint) -> bool {
+    let max_exponent = n.bits() / 16;
+    keygen::small_primes(max_exponent as u32 + 1).into_iter().any(|exponent| {
+        let root = n.nth_root(exponent);
+        &root.pow(exponent) == n
+    })
+}
+
+/// Fermat factorization for `FERMAT_ITERATIONS` steps, which succeeds when `|p - q|` is
+/// below about `2^(nlen/4)`.
+fn has_close_primes(n: &BigUint) -> bool {
+    let mut a = n.sqrt();
+    if &(&a * &a) < n {
+        a += 1u32;
+    }
+
+    (0..FERMAT_ITERATIONS).any(|_| {
+        let b_squared = &a * &a - n;
+        let b = b_squared.sqrt();
+        a += 1u32;
+        &b * &b == b_squared
+    })
+}
+
+/// ROCA (CVE-2017-15361) fingerprint: moduli from the affected Infineon library are, modulo
+/// each of `ROCA_PRIMES`, in the subgroup generated by 65537.
+fn has_roca_fingerprint(n: &BigUint) -> bool {
+    ROCA_PRIMES.iter().all(|prime| {
+        let residue = (n % *prime).to_u32().unwrap();
+        let generator = 65537 % prime;
+        let mut power = 1;
+        loop {
+            if power == residue {
+                return true;
+            }
+            power = power * generator % prime;
+            if power == 1 {
+                return false;
+            }
+        }
+    })
+}
+
+/// Strength report for a public key. `weaknesses` names every failed check, `weak` is set when there is any.
+pub fn analyze(n: &BigUint, e: &BigUint) -> serde_json::Value {
+    let small_factor = smallest_factor(n);
+    let checks = [
+        ("modulusSize", n.bits() < MIN_MODULUS_BITS),
+        ("exponentEven", (e % 2u32).is_zero()),
+        ("exponentTooSmall", e < &BigUint::from(MIN_EXPONENT)),
+        ("exponentTooLarge", e.bits() > MAX_EXPONENT_BITS || e >= n),
+        ("smallFactor", small_factor.is_some()),
+        ("perfectPower", is_perfect_power(n)),
+        ("closePrimes", small_factor.is_none() && has_close_primes(n)),
+        ("rocaFingerprint", has_roca_fingerprint(n))
+    ];
+    let weaknesses: Vec<&str> = checks.iter().filter(|(_, weak)| *weak).map(|(name, _)| *name).collect();
+
+    let mut report = serde_json::json!({
+        "bits": n.bits(),
+        "exponent": e.to_str_radix(16),
+        "smallFactor": small_factor,
+        "weak": !weaknesses.is_empty(),
+        "weaknesses": weaknesses
+    });
+    for (name, weak) in checks.iter().filter(|(name, _)| !["modulusSize", "smallFactor"].contains(name)) {
+        report[*name] = serde_json::Value::Bool(*weak);
+    }
+
+    report
+}
+
+/// Analyzes hex `n` and `e` before creating a key from them, e.g. to reject weak keys of users.
+/// Returns JSON with `bits`, `exponent`, `exponentEven`, `exponentTooSmall`, `exponentTooLarge`,
+/// `smallFactor` (the smallest prime factor below 2^16 or null), `perfectPower`, `closePrimes`,
+/// `rocaFingerprint`, `weak` and the names of the `weaknesses`.
+#[wasm_bindgen]
+pub fn analyze_public_key(n: &str, e: &str) -> String {
+    utils::set_panic_hook();
+    let n = BigUint::from_str_radix(n, 16).expect("invalid n");
+    let e = BigUint::from_str_radix(e, 16).expect("invalid e");
+    if n <= BigUint::one() {
+        panic!("invalid n");
+    }
+
+    analyze(&n, &e).to_string()
+}
+
+#[wasm_bindgen]
+impl RSAPublicKeyPair {
+    /// `analyze_public_key` for this key.
+    pub fn analyze(&self) -> String {
+        utils::set_panic_hook();
+        analyze(self.instance().n(), self.instance().e()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::Value;
+    use fixtures::PRIVATE_KEY;
+
+    fn report(n: &BigUint, e: u32) -> Value {
+        serde_json::from_str(&analyze_public_key(&n.to_str_radix(16), &format!("{:x}", e))).unwrap()
+    }
+
+    fn modulus() -> BigUint {
+        let mut private_instance = private_keys::RSAPrivateKeyPair::new();
+        private_instance.from_pkcs8_pem(PRIVATE_KEY);
+        private_instance.instance().n().clone()
+    }
+
+    fn next_prime(mut value: BigUint) -> BigUint {
+        value |= BigUint::one();
+        while !num_bigint_dig::prime::probably_prime(&value, 20) {
+            value += 2u32;
+        }
+        value
+    }
+
+    #[test]
+    fn analyze_strong_key() {
+        let mut public_instance = RSAPublicKeyPair::new();
+        public_instance.set_instance(RsaPublicKey::new(modulus(), BigUint::from(65537u32)).unwrap());
+        let report: Value = serde_json::from_str(&public_instance.analyze()).unwrap();
+        assert_eq!(report["bits"], 2048);
+        assert_eq!(report["exponent"], "10001");
+        assert_eq!(report["smallFactor"], Value::Null);
+        assert_eq!(report["rocaFingerprint"], false);
+        assert_eq!(report["weak"], false);
+        assert_eq!(report["weaknesses"].as_array().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn analyze_weak_exponents() {
+        assert_eq!(report(&modulus(), 3)["weaknesses"], serde_json::json!(["exponentTooSmall"]));
+        assert_eq!(report(&modulus(), 65538)["weaknesses"], serde_json::json!(["exponentEven"]));
+
+        let large_exponent = format!("1{}1", "0".repeat(64));
+        let report: Value = serde_json::from_str(&analyze_public_key(&modulus().to_str_radix(16), &large_exponent)).unwrap();
+        assert_eq!(report["exponentTooLarge"], true);
+    }
+
+    #[test]
+    fn analyze_small_factor() {
+        let n = modulus() * 65521u32;
+        let report = report(&n, 65537);
+        assert_eq!(report["smallFactor"], 65521);
+        assert_eq!(report["weaknesses"], serde_json::json!(["smallFactor"]));
+
+        assert_eq!(self::report(&(modulus() << 1), 65537)["smallFactor"], 2);
+    }
+
+    #[test]
+    fn analyze_perfect_power() {
+        let prime = next_prime(BigUint::one() << 682);
+        let report = report(&prime.pow(3u32), 65537);
+        assert_eq!(report["perfectPower"], true);
+        assert!(report["weak"].as_bool().unwrap());
+    }
+
+    #[test]
+    fn analyze_close_primes() {
+        let p = next_prime((BigUint::one() << 1023) + (BigUint::one() << 1022));
+        let q = next_prime(&p + (BigUint::one() << 400));
+        let report = report(&(&p * &q), 65537);
+        assert_eq!(report["closePrimes"], true);
+        assert_eq!(report["weaknesses"], serde_json::json!(["closePrimes"]));
+
+        assert_eq!(self::report(&(&p * &next_prime(&p + (BigUint::one() << 900))), 65537)["closePrimes"], false);
+    }
+
+    #[test]
+    fn analyze_roca_fingerprint() {
+        // Primes of the affected library are k * M + (65537^a mod M), with M the primorial of
+        // ROCA_PRIMES and 2, so the modulus is a power of 65537 modulo each of them.
+        let primorial = ROCA_PRIMES.iter().fold(BigUint::from(2u32), |product, prime| product * *prime);
+        let generator = BigUint::from(65537u32);
+        let mut rng = utils::seeded_rng("c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7");
+        let mut roca_prime = || loop {
+            let candidate = BigUint::from(rng.next_u64()) * &primorial + generator.modpow(&BigUint::from(rng.next_u32()), &primorial);
+            if num_bigint_dig::prime::probably_prime(&candidate, 20) {
+                return candidate;
+            }
+        };
+
+        let n = roca_prime() * roca_prime();
+        assert!(has_roca_fingerprint(&n));
+        assert!(report(&n, 65537)["weaknesses"].as_array().unwrap().contains(&Value::from("rocaFingerprint")));
+        assert!(!has_roca_fingerprint(&modulus()));
+    }
+}
@@ -0,0 +1,218 @@
+use super::*;
+use num_integer::Integer;
+use num_traits::One;
+use public_keys::RSAPublicKeyPair;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+/// Levels of the product tree, the moduli first and their product last.
+fn product_tree(moduli: &[BigUint]) -> Vec<Vec<BigUint>> {
+    let mut levels = vec![moduli.to_vec()];
+    while levels.last().unwrap().len() > 1 {
+        let level = levels.last().unwrap().chunks(2).map(|pair| pair.iter().product()).collect();
+        levels.push(level);
+    }
+    levels
+}
+
+/// `gcd(n_i, (n_1 * ... * n_k) / n_i)` of every modulus, with the product reduced modulo `n_i²`
+/// down the remainder tree (Bernstein, "How to find smooth parts of integers").
+pub fn batch_gcd(moduli: &[BigUint]) -> Vec<BigUint> {
+    if moduli.is_empty() {
+        return vec![];
+    }
+
+    let levels = product_tree(moduli);
+    let mut remainders = levels.last().unwrap().clone();
+    for level in levels.iter().rev().skip(1) {
+        remainders = level.iter().enumerate().map(|(index, node)| &remainders[index / 2] % (node * node)).collect();
+    }
+
+    remainders.iter().zip(moduli).map(|(remainder, n)| (remainder / n).gcd(n)).collect()
+}
+
+/// Factors of every modulus with a prime shared with another one, or `None` when it shares none.
+/// A modulus whose both primes are shared has `n` as batch gcd and is split by pairwise gcds with
+/// the other compromised moduli. `moduli` must not contain duplicates.
+fn shared_factors(moduli: &[BigUint]) -> Vec<Option<(BigUint, BigUint)>> {
+    let one = BigUint::one();
+    let gcds = batch_gcd(moduli);
+    let compromised: Vec<usize> = (0..moduli.len()).filter(|index| gcds[*index] != one).collect();
+
+    (0..moduli.len()).map(|index| {
+        let n = &moduli[index];
+        let factor = if gcds[index] == one {
+            return None;
+        } else if &gcds[index] != n {
+            gcds[index].clone()
+        } else {
+            compromised.iter()
+                .filter(|other| **other != index)
+                .map(|other| n.gcd(&moduli[*other]))
+                .find(|gcd| gcd != &one && gcd != n)?
+        };
+        Some((n / &factor, factor))
+    }).collect()
+}
+
+/// Collects public keys and finds the ones sharing a prime ("Mining your Ps and Qs"), with
+/// product and remainder trees instead of pairwise gcds.
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct RSABatchGcd {
+    moduli: Vec<BigUint>
+}
+
+#[wasm_bindgen]
+impl RSABatchGcd {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        utils::set_panic_hook();
+        RSABatchGcd { moduli: vec![] }
+    }
+
+    /// Adds a key, keys are numbered in the order they are added.
+    pub fn add_key(&mut self, key: &RSAPublicKeyPair) {
+        utils::set_panic_hook();
+        self.moduli.push(key.instance().n().clone());
+    }
+
+    /// Returns JSON with `groups` of keys sharing a prime, as the shared `factor` and the `keys` with
+    /// their `index`, the shared prime `p` and the cofactor `q`, and `duplicates`, groups of indexes
+    /// of keys with the same modulus. Keys in either are compromised.
+    pub fn find_shared_factors(&self) -> String {
+        utils::set_panic_hook();
+        let mut positions: HashMap<&BigUint, usize> = HashMap::new();
+        let mut unique: Vec<(&BigUint, Vec<usize>)> = vec![];
+        for (index, n) in self.moduli.iter().enumerate() {
+            match positions.entry(n) {
+                Entry::Occupied(position) => unique[*position.get()].1.push(index),
+                Entry::Vacant(position) => {
+                    position.insert(unique.len());
+                    unique.push((n, vec![index]));
+                }
+            }
+        }
+
+        let moduli: Vec<BigUint> = unique.iter().map(|(n, _)| (*n).clone()).collect();
+        let factored: Vec<(&Vec<usize>, BigUint, BigUint)> = unique.iter().zip(shared_factors(&moduli))
+            .filter_map(|((_, indexes), factors)| factors.map(|(p, q)| (indexes, p, q)))
+            .collect();
+
+        // A prime is shared when it divides more than one of the distinct moduli.
+        let mut occurrences: HashMap<&BigUint, usize> = HashMap::new();
+        for (_, p, q) in &factored {
+            *occurrences.entry(p).or_default() += 1;
+            if p != q {
+                *occurrences.entry(q).or_default() += 1;
+            }
+        }
+
+        let mut group_positions: HashMap<&BigUint, usize> = HashMap::new();
+        let mut groups: Vec<(&BigUint, Vec<serde_json::Value>)> = vec![];
+        for (indexes, p, q) in &factored {
+            for (factor, cofactor) in [(p, q), (q, p)] {
+                if occurrences[factor] < 2 || (factor == q && p == q) {
+                    continue;
+                }
+
+                let keys = indexes.iter().map(|index| serde_json::json!({
+                    "index": index,
+                    "p": factor.to_str_radix(16),
+                    "q": cofactor.to_str_radix(16)
+                }));
+                match group_positions.entry(factor) {
+                    Entry::Occupied(position) => groups[*position.get()].1.extend(keys),
+                    Entry::Vacant(position) => {
+                        position.insert(groups.len());
+                        groups.push((factor, keys.collect()));
+                    }
+                }
+            }
+        }
+
+        let groups: Vec<serde_json::Value> = groups.into_iter()
+            .map(|(factor, keys)| serde_json::json!({ "factor": factor.to_str_radix(16), "keys": keys }))
+            .collect();
+        let duplicates: Vec<&Vec<usize>> = unique.iter().map(|(_, indexes)| indexes).filter(|indexes| indexes.len() > 1).collect();
+
+        serde_json::json!({ "groups": groups, "duplicates": duplicates }).to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fixtures::legacy_public_key_pair;
+    use serde_json::Value;
+
+    const SEED: &str = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+
+    fn primes(count: usize) -> Vec<BigUint> {
+        let mut rng = utils::seeded_rng(SEED);
+        (0..count).map(|_| loop {
+            let mut bytes = [0u8; 64];
+            rng.fill_bytes(&mut bytes);
+            bytes[0] |= 0xc0;
+            bytes[63] |= 1;
+            let candidate = BigUint::from_bytes_be(&bytes);
+            if num_bigint_dig::prime::probably_prime(&candidate, 20) {
+                break candidate;
+            }
+        }).collect()
+    }
+
+    fn public_key(n: &BigUint) -> RSAPublicKeyPair {
+        let mut public_instance = legacy_public_key_pair();
+        public_instance.set_instance(RsaPublicKey::new(n.clone(), BigUint::from(65537u32)).unwrap());
+        public_instance
+    }
+
+    fn keys(group: &Value) -> Vec<u64> {
+        group["keys"].as_array().unwrap().iter().map(|key| key["index"].as_u64().unwrap()).collect()
+    }
+
+    #[test]
+    fn batch_gcd_of_moduli() {
+        let primes = primes(4);
+        let moduli = vec![&primes[0] * &primes[1], &primes[0] * &primes[2], &primes[2] * &primes[3]];
+        assert_eq!(batch_gcd(&moduli), vec![primes[0].clone(), moduli[1].clone(), primes[2].clone()]);
+        assert_eq!(batch_gcd(&moduli[..1]), vec![BigUint::one()]);
+        assert!(batch_gcd(&[]).is_empty());
+    }
+
+    #[test]
+    fn find_shared_factors() {
+        let primes = primes(6);
+        let moduli = [
+            &primes[0] * &primes[1],
+            &primes[0] * &primes[2],
+            &primes[3] * &primes[4],
+            &primes[1] * &primes[2],
+            &primes[3] * &primes[4],
+            &primes[5] * &primes[0]
+        ];
+        let mut batch = RSABatchGcd::new();
+        moduli.iter().for_each(|n| batch.add_key(&public_key(n)));
+        let result: Value = serde_json::from_str(&batch.find_shared_factors()).unwrap();
+
+        let groups = result["groups"].as_array().unwrap();
+        let group = |prime: &BigUint| groups.iter().find(|group| group["factor"] == prime.to_str_radix(16)).unwrap();
+        assert_eq!(groups.len(), 3);
+        assert_eq!(keys(group(&primes[0])), vec![0, 1, 5]);
+        assert_eq!(group(&primes[0])["keys"][2]["q"], primes[5].to_str_radix(16));
+        assert_eq!(keys(group(&primes[1])), vec![0, 3]);
+        assert_eq!(keys(group(&primes[2])), vec![1, 3]);
+        assert_eq!(result["duplicates"], serde_json::json!([[2, 4]]));
+    }
+
+    #[test]
+    fn find_no_shared_factors() {
+        let primes = primes(4);
+        let mut batch = RSABatchGcd::new();
+        batch.add_key(&public_key(&(&primes[0] * &primes[1])));
+        batch.add_key(&public_key(&(&primes[2] * &primes[3])));
+        assert_eq!(batch.find_shared_factors(), r#"{"duplicates":[],"groups":[]}"#);
+        assert_eq!(RSABatchGcd::new().find_shared_factors(), r#"{"duplicates":[],"groups":[]}"#);
+    }
+}
@@ -0,0 +1,325 @@
+use super::*;
+use num_bigint_dig::RandBigInt;
+use hashing::HashAlgorithm;
+use primitives::{ i2osp, os2ip, mod_inverse, rsa_private, rsa_public };
+use public_keys::RSAPublicKeyPair;
+use private_keys::RSAPrivateKeyPair;
+use policy::Padding;
+
+const MESSAGE_PREFIX_LENGTH: usize = 32;
+
+/// RSABSSA variant from RFC 9474 section 5: salt length and message preparation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlindVariant {
+    salt_length: usize,
+    randomized: bool
+}
+
+impl BlindVariant {
+    pub fn from_name(name: &str) -> BlindVariant {
+        match name {
+            "RSABSSA-SHA384-PSS-Randomized" => BlindVariant { salt_length: 48, randomized: true },
+            "RSABSSA-SHA384-PSSZERO-Randomized" => BlindVariant { salt_length: 0, randomized: true },
+            "RSABSSA-SHA384-PSS-Deterministic" => BlindVariant { salt_length: 48, randomized: false },
+            "RSABSSA-SHA384-PSSZERO-Deterministic" => BlindVariant { salt_length: 0, randomized: false },
+            _ => panic!("unsupported blind signature variant {}", name)
+        }
+    }
+
+    fn prepare<R: RngCore>(&self, rng: &mut R) -> Vec<u8> {
+        let mut message_prefix = vec![];
+        if self.randomized {
+            message_prefix.resize(MESSAGE_PREFIX_LENGTH, 0);
+            rng.fill_bytes(&mut message_prefix);
+        }
+
+        message_prefix
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct RSABlindingResult {
+    blinded_message: String,
+    inv: String,
+    message_prefix: String,
+    variant: BlindVariant
+}
+
+#[wasm_bindgen]
+impl RSABlindingResult {
+    pub fn get_blinded_message(&self) -> String {
+        self.blinded_message.to_string()
+    }
+
+    pub fn get_inv(&self) -> String {
+        self.inv.to_string()
+    }
+
+    pub fn get_message_prefix(&self) -> String {
+        self.message_prefix.to_string()
+    }
+}
+
+fn prefixed_message(message_prefix: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut input = message_prefix.to_vec();
+    input.extend_from_slice(message);
+    input
+}
+
+/// Blind from RFC 9474 section 4.2 with an explicit salt and blinding factor.
+pub fn blind_with(
+    key: &RsaPublicKey,
+    input_message: &[u8],
+    salt: &[u8],
+    r: &BigUint
+) -> (Vec<u8>, Vec<u8>) {
+    let k = key.size();
+    let m_hash = HashAlgorithm::Sha384.digest(input_message);
+    let encoded_message = padding::emsa_pss_encode(HashAlgorithm::Sha384, &m_hash, key.n().bits() - 1, salt);
+
+    let m = os2ip(&encoded_message);
+    if mod_inverse(&m, key.n()).is_none() {
+        panic!("invalid input");
+    }
+
+    let inv = mod_inverse(r, key.n()).expect("invalid blind");
+    let x = rsa_public(key, r);
+    let z = (m * x) % key.n();
+
+    (i2osp(&z, k), i2osp(&inv, k))
+}
+
+/// Finalize from RFC 9474 section 4.4.
+pub fn finalize_with(
+    key: &RsaPublicKey,
+    input_message: &[u8],
+    blind_signature: &[u8],
+    inv: &[u8],
+    salt_length: usize
+) -> Vec<u8> {
+    let k = key.size();
+    if blind_signature.len() != k || inv.len() != k {
+        panic!("unexpected input size");
+    }
+
+    let z = os2ip(blind_signature);
+    let s = (z * os2ip(inv)) % key.n();
+    let signature = i2osp(&s, k);
+
+    let m_hash = HashAlgorithm::Sha384.digest(input_message);
+    if !padding::pss_verify(key, HashAlgorithm::Sha384, &m_hash, &signature, Some(salt_length)) {
+        panic!("invalid signature");
+    }
+
+    signature
+}
+
+#[wasm_bindgen]
+impl RSAPublicKeyPair {
+    pub fn blind(&self, message: &str, variant: &str, random_seed: &str) -> RSABlindingResult {
+        utils::set_panic_hook();
+        let variant = BlindVariant::from_name(variant);
+        let mut rng = utils::seeded_rng(random_seed);
+        let instance = self.verifying_instance(Padding::Pss, HashAlgorithm::Sha384);
+
+        let message_prefix = variant.prepare(&mut rng);
+        let mut salt = vec![0u8; variant.salt_length];
+        rng.fill_bytes(&mut salt);
+        let r = rng.gen_biguint_range(&BigUint::from(1u32), instance.n());
+
+        let (blinded_message, inv) = blind_with(
+            instance,
+            &prefixed_message(&message_prefix, message.as_bytes()),
+            &salt,
+            &r
+        );
+
+        RSABlindingResult {
+            blinded_message: hex::encode(&blinded_message),
+            inv: hex::encode(&inv),
+            message_prefix: hex::encode(&message_prefix),
+            variant
+        }
+    }
+
+    pub fn finalize(&self, message: &str, blind_signature: &str, blinding: &RSABlindingResult) -> String {
+        utils::set_panic_hook();
+        let message_prefix = hex::decode(&blinding.message_prefix).expect("invalid decode message prefix");
+        let signature = finalize_with(
+            self.verifying_instance(Padding::Pss, HashAlgorithm::Sha384),
+            &prefixed_message(&message_prefix, message.as_bytes()),
+            &hex::decode(blind_signature).expect("invalid decode blind signature"),
+            &hex::decode(&blinding.inv).expect("invalid decode inv"),
+            blinding.variant.salt_length
+        );
+
+        hex::encode(&signature)
+    }
+
+    pub fn verify_blind_signature(
+        &self,
+        message: &str,
+        signature: &str,
+        message_prefix: &str,
+        variant: &str
+    ) -> bool {
+        utils::set_panic_hook();
+        let variant = BlindVariant::from_name(variant);
+        let message_prefix = hex::decode(message_prefix).expect("invalid decode message prefix");
+        if message_prefix.len() != if variant.randomized { MESSAGE_PREFIX_LENGTH } else { 0 } {
+            return false;
+        }
+
+        let m_hash = HashAlgorithm::Sha384.digest(&prefixed_message(&message_prefix, message.as_bytes()));
+        padding::pss_verify(
+            self.verifying_instance(Padding::Pss, HashAlgorithm::Sha384),
+            HashAlgorithm::Sha384,
+            &m_hash,
+            &hex::decode(signature).expect("invalid decode signature"),
+            Some(variant.salt_length)
+        )
+    }
+}
+
+#[wasm_bindgen]
+impl RSAPrivateKeyPair {
+    /// BlindSign from RFC 9474 section 4.3.
+    pub fn blind_sign(&self, blinded_message: &str) -> String {
+        utils::set_panic_hook();
+        let instance = self.signing_instance(Padding::Pss, HashAlgorithm::Sha384);
+        let decode_blinded_message = hex::decode(blinded_message).expect("invalid decode blinded message");
+        if decode_blinded_message.len() != instance.size() {
+            panic!("unexpected input size");
+        }
+
+        let m = os2ip(&decode_blinded_message);
+        if &m >= instance.n() {
+            panic!("invalid message length");
+        }
+
+        let s = rsa_private(instance, &m);
+        if rsa_public(instance, &s) != m {
+            panic!("signing failure");
+        }
+
+        hex::encode(&i2osp(&s, instance.size()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fixtures::key_pairs;
+
+    const VARIANTS: [&str; 4] = [
+        "RSABSSA-SHA384-PSS-Randomized",
+        "RSABSSA-SHA384-PSSZERO-Randomized",
+        "RSABSSA-SHA384-PSS-Deterministic",
+        "RSABSSA-SHA384-PSSZERO-Deterministic"
+    ];
+
+    #[test]
+    fn blind_sign_and_finalize_all_variants() {
+        let seed = "0e1a6b8c1d4f5e6d7c8b9a0f1e2d3c4b5a69788796a5b4c3d2e1f0a1b2c3d4e5";
+        let message = "voucher";
+        let (private_instance, public_instance) = key_pairs();
+        for variant in VARIANTS.iter() {
+            let blinding = public_instance.blind(message, variant, seed);
+            let blind_signature = private_instance.blind_sign(&blinding.get_blinded_message());
+            let signature = public_instance.finalize(message, &blind_signature, &blinding);
+            assert!(public_instance.verify_blind_signature(
+                message,
+                &signature,
+                &blinding.get_message_prefix(),
+                variant
+            ));
+            assert!(!public_instance.verify_blind_signature(
+                "other voucher",
+                &signature,
+                &blinding.get_message_prefix(),
+                variant
+            ));
+        }
+    }
+
+    #[test]
+    fn deterministic_variant_verifies_as_pss_signature() {
+        let seed = "0e1a6b8c1d4f5e6d7c8b9a0f1e2d3c4b5a69788796a5b4c3d2e1f0a1b2c3d4e5";
+        let message = "voucher";
+        let (private_instance, public_instance) = key_pairs();
+        let blinding = public_instance.blind(message, "RSABSSA-SHA384-PSS-Deterministic", seed);
+        let blind_signature = private_instance.blind_sign(&blinding.get_blinded_message());
+        let signature = public_instance.finalize(message, &blind_signature, &blinding);
+        assert_eq!(blinding.get_message_prefix(), "");
+        assert!(public_instance.verify_message_pss(message, &signature, "SHA-384"));
+    }
+
+    #[test]
+    fn blinded_message_hides_message() {
+        let message = "voucher";
+        let (_, public_instance) = key_pairs();
+        let first = public_instance.blind(
+            message,
+            "RSABSSA-SHA384-PSS-Deterministic",
+            "0e1a6b8c1d4f5e6d7c8b9a0f1e2d3c4b5a69788796a5b4c3d2e1f0a1b2c3d4e5"
+        );
+        let second = public_instance.blind(
+            message,
+            "RSABSSA-SHA384-PSS-Deterministic",
+            "1e1a6b8c1d4f5e6d7c8b9a0f1e2d3c4b5a69788796a5b4c3d2e1f0a1b2c3d4e5"
+        );
+        assert_ne!(first.get_blinded_message(), second.get_blinded_message());
+    }
+
+    /// The RSABSSA-SHA384-PSS-Deterministic and RSABSSA-SHA384-PSSZERO-Deterministic vectors of
+    /// RFC 9474 Appendix A.
+    #[test]
+    fn blind_signature_vectors() {
+        let vectors: serde_json::Value = serde_json::from_str(include_str!("testdata/blind_signatures.json")).unwrap();
+        for vector in vectors.as_array().unwrap() {
+            let field = |name: &str| hex::decode(vector[name].as_str().unwrap()).unwrap();
+            let number = |name: &str| BigUint::from_bytes_be(&field(name));
+            let name = vector["name"].as_str().unwrap();
+            let variant = BlindVariant::from_name(name);
+
+            let key = RsaPrivateKey::from_components(number("n"), number("e"), number("d"), vec![number("p"), number("q")]);
+            let mut private_instance = private_keys::RSAPrivateKeyPair::new();
+            private_instance.set_instance(key);
+            let mut public_instance = public_keys::RSAPublicKeyPair::new();
+            public_instance.create(&private_instance.get_n(), &private_instance.get_e());
+
+            let input_message = prefixed_message(&field("msg_prefix"), &field("msg"));
+            assert_eq!(input_message, field("input_msg"), "{}", name);
+            let m_hash = HashAlgorithm::Sha384.digest(&input_message);
+            let encoded_message = padding::emsa_pss_encode(HashAlgorithm::Sha384, &m_hash, number("n").bits() - 1, &field("salt"));
+            assert_eq!(encoded_message, field("encoded_msg"), "{}", name);
+            let r = mod_inverse(&number("inv"), public_instance.instance().n()).unwrap();
+            let (blinded_message, inv) = blind_with(public_instance.instance(), &input_message, &field("salt"), &r);
+            assert_eq!(blinded_message, field("blinded_msg"), "{}", name);
+            assert_eq!(inv, field("inv"), "{}", name);
+
+            let blind_signature = private_instance.blind_sign(vector["blinded_msg"].as_str().unwrap());
+            assert_eq!(blind_signature, vector["blind_sig"].as_str().unwrap(), "{}", name);
+            let signature = finalize_with(public_instance.instance(), &input_message, &field("blind_sig"), &inv, variant.salt_length);
+            assert_eq!(signature, field("sig"), "{}", name);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn finalize_with_invalid_blind_signature() {
+        let seed = "0e1a6b8c1d4f5e6d7c8b9a0f1e2d3c4b5a69788796a5b4c3d2e1f0a1b2c3d4e5";
+        let (_, public_instance) = key_pairs();
+        let blinding = public_instance.blind("voucher", "RSABSSA-SHA384-PSS-Randomized", seed);
+        public_instance.finalize("voucher", &blinding.get_blinded_message(), &blinding);
+    }
+
+    #[test]
+    #[should_panic]
+    fn blind_with_unknown_variant() {
+        let seed = "0e1a6b8c1d4f5e6d7c8b9a0f1e2d3c4b5a69788796a5b4c3d2e1f0a1b2c3d4e5";
+        let (_, public_instance) = key_pairs();
+        public_instance.blind("voucher", "RSABSSA-SHA256-PSS-Randomized", seed);
+    }
+}
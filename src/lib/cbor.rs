@@ -0,0 +1,294 @@
+/// CBOR data item from RFC 8949, limited to what COSE and WebAuthn structures use.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Unsigned(u64),
+    /// Negative integer `-1 - n`.
+    Negative(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Tag(u64, Box<Value>),
+    Bool(bool),
+    Null,
+    Undefined,
+    Float(f64)
+}
+
+impl Value {
+    pub fn integer(value: i64) -> Value {
+        if value < 0 {
+            Value::Negative((-1 - value) as u64)
+        } else {
+            Value::Unsigned(value as u64)
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Unsigned(value) if *value <= i64::MAX as u64 => Some(*value as i64),
+            Value::Negative(value) if *value <= i64::MAX as u64 => Some(-1 - *value as i64),
+            _ => None
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(bytes) => Some(bytes),
+            _ => None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&[(Value, Value)]> {
+        match self {
+            Value::Map(entries) => Some(entries),
+            _ => None
+        }
+    }
+
+    /// Map lookup by integer label.
+    pub fn get(&self, label: i64) -> Option<&Value> {
+        self.as_map()?.iter().find(|(key, _)| key.as_integer() == Some(label)).map(|(_, value)| value)
+    }
+}
+
+fn encode_head(major: u8, value: u64, output: &mut Vec<u8>) {
+    let major = major << 5;
+    if value < 24 {
+        output.push(major | value as u8);
+    } else if value <= u8::MAX as u64 {
+        output.push(major | 24);
+        output.push(value as u8);
+    } else if value <= u16::MAX as u64 {
+        output.push(major | 25);
+        output.extend_from_slice(&(value as u16).to_be_bytes());
+    } else if value <= u32::MAX as u64 {
+        output.push(major | 26);
+        output.extend_from_slice(&(value as u32).to_be_bytes());
+    } else {
+        output.push(major | 27);
+        output.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+fn encode_into(value: &Value, output: &mut Vec<u8>) {
+    match value {
+        Value::Unsigned(value) => encode_head(0, *value, output),
+        Value::Negative(value) => encode_head(1, *value, output),
+        Value::Bytes(bytes) => {
+            encode_head(2, bytes.len() as u64, output);
+            output.extend_from_slice(bytes);
+        },
+        Value::Text(text) => {
+            encode_head(3, text.len() as u64, output);
+            output.extend_from_slice(text.as_bytes());
+        },
+        Value::Array(items) => {
+            encode_head(4, items.len() as u64, output);
+            for item in items {
+                encode_into(item, output);
+            }
+        },
+        Value::Map(entries) => {
+            // Core deterministic encoding (RFC 8949 4.2.1): keys sorted by their encoding.
+            let mut encoded: Vec<(Vec<u8>, Vec<u8>)> = entries.iter().map(|(key, value)| (encode(key), encode(value))).collect();
+            encoded.sort();
+            encode_head(5, encoded.len() as u64, output);
+            for (key, value) in encoded {
+                output.extend(key);
+                output.extend(value);
+            }
+        },
+        Value::Tag(tag, value) => {
+            encode_head(6, *tag, output);
+            encode_into(value, output);
+        },
+        Value::Bool(false) => output.push(0xf4),
+        Value::Bool(true) => output.push(0xf5),
+        Value::Null => output.push(0xf6),
+        Value::Undefined => output.push(0xf7),
+        Value::Float(value) => {
+            output.push(0xfb);
+            output.extend_from_slice(&value.to_bits().to_be_bytes());
+        }
+    }
+}
+
+pub fn encode(value: &Value) -> Vec<u8> {
+    let mut output = vec![];
+    encode_into(value, &mut output);
+    output
+}
+
+const MAX_DEPTH: usize = 16;
+
+struct Decoder<'a> {
+    input: &'a [u8],
+    position: usize
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> &'a [u8] {
+        if self.input.len() - self.position < len {
+            panic!("invalid cbor: truncated");
+        }
+
+        let bytes = &self.input[self.position..self.position + len];
+        self.position += len;
+        bytes
+    }
+
+    fn argument(&mut self, additional: u8) -> u64 {
+        match additional {
+            0..=23 => additional as u64,
+            24 => self.take(1)[0] as u64,
+            25 => self.take(2).iter().fold(0, |acc, byte| (acc << 8) | *byte as u64),
+            26 => self.take(4).iter().fold(0, |acc, byte| (acc << 8) | *byte as u64),
+            27 => self.take(8).iter().fold(0, |acc, byte| (acc << 8) | *byte as u64),
+            _ => panic!("invalid cbor: indefinite length items are not supported")
+        }
+    }
+
+    fn length(&mut self, additional: u8) -> usize {
+        let len = self.argument(additional);
+        if len > (self.input.len() - self.position) as u64 {
+            panic!("invalid cbor: length");
+        }
+
+        len as usize
+    }
+
+    fn value(&mut self, depth: usize) -> Value {
+        if depth > MAX_DEPTH {
+            panic!("invalid cbor: nesting too deep");
+        }
+
+        let initial = self.take(1)[0];
+        let (major, additional) = (initial >> 5, initial & 0x1f);
+        match major {
+            0 => Value::Unsigned(self.argument(additional)),
+            1 => Value::Negative(self.argument(additional)),
+            2 => {
+                let len = self.length(additional);
+                Value::Bytes(self.take(len).to_vec())
+            },
+            3 => {
+                let len = self.length(additional);
+                Value::Text(String::from_utf8(self.take(len).to_vec()).expect("invalid cbor: text"))
+            },
+            4 => {
+                let len = self.length(additional);
+                Value::Array((0..len).map(|_| self.value(depth + 1)).collect())
+            },
+            5 => {
+                let len = self.length(additional);
+                Value::Map((0..len).map(|_| (self.value(depth + 1), self.value(depth + 1))).collect())
+            },
+            6 => {
+                let tag = self.argument(additional);
+                Value::Tag(tag, Box::new(self.value(depth + 1)))
+            },
+            _ => match additional {
+                20 => Value::Bool(false),
+                21 => Value::Bool(true),
+                22 => Value::Null,
+                23 => Value::Undefined,
+                25 => {
+                    let bits = self.argument(additional) as u16;
+                    Value::Float(half_to_f64(bits))
+                },
+                26 => Value::Float(f32::from_bits(self.argument(additional) as u32) as f64),
+                27 => Value::Float(f64::from_bits(self.argument(additional))),
+                _ => panic!("invalid cbor: unsupported simple value")
+            }
+        }
+    }
+}
+
+fn half_to_f64(bits: u16) -> f64 {
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f64;
+    let value = match exponent {
+        0 => mantissa * 2f64.powi(-24),
+        31 => if mantissa == 0.0 { f64::INFINITY } else { f64::NAN },
+        _ => (mantissa + 1024.0) * 2f64.powi(exponent - 25)
+    };
+
+    if bits & 0x8000 != 0 { -value } else { value }
+}
+
+/// Decodes one data item and returns it with the number of bytes consumed.
+pub fn decode_prefix(input: &[u8]) -> (Value, usize) {
+    let mut decoder = Decoder { input, position: 0 };
+    let value = decoder.value(0);
+    (value, decoder.position)
+}
+
+pub fn decode(input: &[u8]) -> Value {
+    let (value, len) = decode_prefix(input);
+    if len != input.len() {
+        panic!("invalid cbor: trailing data");
+    }
+
+    value
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_rfc8949_examples() {
+        assert_eq!(hex::encode(encode(&Value::Unsigned(0))), "00");
+        assert_eq!(hex::encode(encode(&Value::Unsigned(24))), "1818");
+        assert_eq!(hex::encode(encode(&Value::Unsigned(1000000))), "1a000f4240");
+        assert_eq!(hex::encode(encode(&Value::integer(-1000))), "3903e7");
+        assert_eq!(hex::encode(encode(&Value::Text("IETF".to_string()))), "6449455446");
+        assert_eq!(hex::encode(encode(&Value::Bytes(vec![1, 2, 3, 4]))), "4401020304");
+        assert_eq!(
+            hex::encode(encode(&Value::Array(vec![Value::Unsigned(1), Value::Array(vec![Value::Unsigned(2), Value::Unsigned(3)])]))),
+            "8201820203"
+        );
+    }
+
+    #[test]
+    fn map_keys_are_sorted() {
+        let map = Value::Map(vec![
+            (Value::integer(-1), Value::Unsigned(1)),
+            (Value::integer(3), Value::Unsigned(2)),
+            (Value::integer(1), Value::Unsigned(3))
+        ]);
+        assert_eq!(hex::encode(encode(&map)), "a3010303022001");
+    }
+
+    #[test]
+    fn decode_round_trip() {
+        let value = Value::Tag(18, Box::new(Value::Array(vec![
+            Value::Bytes(vec![0xa1, 0x01, 0x38, 0x24]),
+            Value::Map(vec![(Value::integer(4), Value::Bytes(b"kid".to_vec()))]),
+            Value::Null,
+            Value::Bool(true)
+        ])));
+        assert_eq!(decode(&encode(&value)), value);
+    }
+
+    #[test]
+    fn decode_floats() {
+        assert_eq!(decode(&hex::decode("f93c00").unwrap()), Value::Float(1.0));
+        assert_eq!(decode(&hex::decode("fa47c35000").unwrap()), Value::Float(100000.0));
+        assert_eq!(decode(&hex::decode("fb3ff199999999999a").unwrap()), Value::Float(1.1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn decode_truncated() {
+        decode(&hex::decode("5a000f4240").unwrap());
+    }
+}
@@ -0,0 +1,379 @@
+use super::*;
+use rsa::PublicKey;
+use hashing::HashAlgorithm;
+use x509::Certificate;
+use public_keys::RSAPublicKeyPair;
+use private_keys::RSAPrivateKeyPair;
+use policy::{ Padding, RSAPolicy };
+
+pub const OID_DATA: &str = "1.2.840.113549.1.7.1";
+pub const OID_SIGNED_DATA: &str = "1.2.840.113549.1.7.2";
+pub const OID_CONTENT_TYPE: &str = "1.2.840.113549.1.9.3";
+pub const OID_MESSAGE_DIGEST: &str = "1.2.840.113549.1.9.4";
+pub const OID_SIGNING_TIME: &str = "1.2.840.113549.1.9.5";
+pub const OID_RSA_ENCRYPTION: &str = "1.2.840.113549.1.1.1";
+pub const OID_SHA1: &str = "1.3.14.3.2.26";
+pub const OID_SHA256: &str = "2.16.840.1.101.3.4.2.1";
+pub const OID_SHA384: &str = "2.16.840.1.101.3.4.2.2";
+pub const OID_SHA512: &str = "2.16.840.1.101.3.4.2.3";
+
+pub fn hash_oid(hash: HashAlgorithm) -> &'static str {
+    match hash {
+        HashAlgorithm::Sha1 => OID_SHA1,
+        HashAlgorithm::Sha256 => OID_SHA256,
+        HashAlgorithm::Sha384 => OID_SHA384,
+        HashAlgorithm::Sha512 => OID_SHA512
+    }
+}
+
+pub fn hash_from_oid(oid: &str) -> HashAlgorithm {
+    match oid {
+        OID_SHA1 => HashAlgorithm::Sha1,
+        OID_SHA256 => HashAlgorithm::Sha256,
+        OID_SHA384 => HashAlgorithm::Sha384,
+        OID_SHA512 => HashAlgorithm::Sha512,
+        _ => panic!("unsupported digest algorithm {}", oid)
+    }
+}
+
+/// PKCS#1 v1.5 signature algorithm OIDs (sha*WithRSAEncryption) accepted next to rsaEncryption.
+fn is_pkcs1v15_signature_oid(oid: &str) -> bool {
+    matches!(
+        oid,
+        OID_RSA_ENCRYPTION
+            | "1.2.840.113549.1.1.5"
+            | "1.2.840.113549.1.1.11"
+            | "1.2.840.113549.1.1.12"
+            | "1.2.840.113549.1.1.13"
+    )
+}
+
+fn attribute(oid: &str, value: Vec<u8>) -> Vec<u8> {
+    der::sequence(&[der::oid(oid), der::set_of(&[value])])
+}
+
+/// Detached SignedData from RFC 5652 section 5 with content-type, message-digest
+/// and signing-time signed attributes.
+pub fn sign_detached(
+    key: &RsaPrivateKey,
+    certificate: &Certificate,
+    content: &[u8],
+    signing_time: u64
+) -> Vec<u8> {
+    if certificate.public_key.n() != key.n() || certificate.public_key.e() != key.e() {
+        panic!("certificate does not match private key");
+    }
+
+    let hash = HashAlgorithm::Sha256;
+    let digest_algorithm = der::algorithm(hash_oid(hash), Some(der::null()));
+    let signed_attributes = der::sorted(&[
+        attribute(OID_CONTENT_TYPE, der::oid(OID_DATA)),
+        attribute(OID_SIGNING_TIME, der::time(signing_time)),
+        attribute(OID_MESSAGE_DIGEST, der::octet_string(&hash.digest(content)))
+    ]).concat();
+
+    let signature = key.sign(
+        PaddingScheme::new_pkcs1v15_sign(Some(hash.pkcs1v15_hash())),
+        &hash.digest(&der::encode(der::TAG_SET, &signed_attributes))
+    ).expect("sign error");
+
+    let signer_info = der::sequence(&[
+        der::small_integer(1),
+        certificate.issuer_and_serial_number(),
+        digest_algorithm.clone(),
+        der::context(0, true, &signed_attributes),
+        der::algorithm(OID_RSA_ENCRYPTION, Some(der::null())),
+        der::octet_string(&signature)
+    ]);
+
+    let signed_data = der::sequence(&[
+        der::small_integer(1),
+        der::set_of(&[digest_algorithm]),
+        der::sequence(&[der::oid(OID_DATA)]),
+        der::context(0, true, &certificate.raw),
+        der::set_of(&[signer_info])
+    ]);
+
+    der::sequence(&[der::oid(OID_SIGNED_DATA), der::context(0, true, &signed_data)])
+}
+
+fn find_attribute<'a>(attributes: &[der::DerObject<'a>], oid: &str) -> Option<der::DerObject<'a>> {
+    attributes.iter().find_map(|attribute| {
+        let fields = attribute.children();
+        if fields.len() == 2 && fields[0].as_oid() == oid {
+            let values = fields[1].expect_tag(der::TAG_SET).children();
+            if values.len() != 1 {
+                panic!("attribute {} must have a single value", oid);
+            }
+
+            return Some(values[0]);
+        }
+
+        None
+    })
+}
+
+/// `policy` is checked once the hash is known. The content-type attribute must match
+/// `content_type`, the eContentType of the SignedData.
+fn verify_signer_info(signer_info: &der::DerObject, content: &[u8], content_type: &str, public_key: &RsaPublicKey, policy: &RSAPolicy) -> bool {
+    let fields = signer_info.expect_tag(der::TAG_SEQUENCE).children();
+    if fields.len() < 5 {
+        panic!("invalid signer info");
+    }
+
+    let hash = hash_from_oid(&fields[2].children()[0].as_oid());
+    policy.check_verification(public_key, Padding::Pkcs1v15Sign, hash);
+    let (signed_attributes, rest) = if fields[3].tag == 0xa0 {
+        (Some(fields[3]), &fields[4..])
+    } else {
+        (None, &fields[3..])
+    };
+
+    if rest.len() < 2 || !is_pkcs1v15_signature_oid(&rest[0].children()[0].as_oid()) {
+        return false;
+    }
+    let signature = rest[1].expect_tag(der::TAG_OCTET_STRING).content;
+
+    let signed_digest = match signed_attributes {
+        Some(signed_attributes) => {
+            let attributes = signed_attributes.children();
+            let signed_content_type = find_attribute(&attributes, OID_CONTENT_TYPE);
+            let message_digest = find_attribute(&attributes, OID_MESSAGE_DIGEST);
+            match (signed_content_type, message_digest) {
+                (Some(signed_content_type), Some(message_digest)) => {
+                    if signed_content_type.as_oid() != content_type {
+                        return false;
+                    }
+                    if message_digest.expect_tag(der::TAG_OCTET_STRING).content != hash.digest(content).as_slice() {
+                        return false;
+                    }
+                },
+                _ => return false
+            }
+
+            hash.digest(&der::encode(der::TAG_SET, signed_attributes.content))
+        },
+        None => hash.digest(content)
+    };
+
+    public_key.verify(
+        PaddingScheme::new_pkcs1v15_sign(Some(hash.pkcs1v15_hash())),
+        &signed_digest,
+        signature
+    ).is_ok()
+}
+
+/// Returns the SignedData fields: eContentType, embedded certificates and signer infos.
+fn parse_signed_data(data: &[u8]) -> (String, Vec<Certificate>, Vec<Vec<u8>>) {
+    let content_info = der::parse_single(data);
+    let fields = content_info.expect_tag(der::TAG_SEQUENCE).children();
+    if fields.len() != 2 || fields[0].as_oid() != OID_SIGNED_DATA {
+        panic!("not a cms signed data");
+    }
+
+    let signed_data = fields[1].expect_tag(0xa0).children()[0];
+    let signed_data_fields = signed_data.expect_tag(der::TAG_SEQUENCE).children();
+    if signed_data_fields.len() < 4 {
+        panic!("invalid signed data");
+    }
+    let content_type = signed_data_fields[2].expect_tag(der::TAG_SEQUENCE).children()[0].as_oid();
+    let mut certificates = vec![];
+    let mut signer_infos = vec![];
+    for field in signed_data_fields.iter().skip(3) {
+        match field.tag {
+            0xa0 => {
+                for certificate in field.children() {
+                    if certificate.tag == der::TAG_SEQUENCE {
+                        certificates.push(Certificate::from_der(certificate.raw));
+                    }
+                }
+            },
+            der::TAG_SET => {
+                for signer_info in field.children() {
+                    signer_infos.push(signer_info.raw.to_vec());
+                }
+            },
+            _ => {}
+        }
+    }
+
+    (content_type, certificates, signer_infos)
+}
+
+/// Verifies with the key and policy of `public_key`.
+pub fn verify_detached(data: &[u8], content: &[u8], public_key: &RSAPublicKeyPair) -> bool {
+    let (content_type, _, signer_infos) = parse_signed_data(data);
+    if signer_infos.is_empty() {
+        return false;
+    }
+
+    signer_infos.iter().all(|signer_info| {
+        let signer_info = der::parse_single(signer_info);
+        verify_signer_info(&signer_info, content, &content_type, public_key.instance(), public_key.policy())
+    })
+}
+
+/// Verifies with the embedded certificates and returns the certificate of each signer,
+/// `None` if a signer is missing its certificate or fails to verify. `policy` is checked
+/// against every signer key. The certificates are not validated, the caller has to decide
+/// whether to trust them.
+pub fn verify_detached_signers(data: &[u8], content: &[u8], policy: &RSAPolicy) -> Option<Vec<Certificate>> {
+    let (content_type, certificates, signer_infos) = parse_signed_data(data);
+    if signer_infos.is_empty() {
+        return None;
+    }
+
+    signer_infos.iter().map(|signer_info| {
+        let signer_info = der::parse_single(signer_info);
+        let sid = signer_info.children()[1];
+        let certificate = certificates.iter().find(|certificate| certificate.matches(&sid))?;
+        if verify_signer_info(&signer_info, content, &content_type, &certificate.public_key, policy) {
+            Some(certificate.clone())
+        } else {
+            None
+        }
+    }).collect()
+}
+
+#[wasm_bindgen]
+impl RSAPrivateKeyPair {
+    /// Detached CMS SignedData (.p7s) as hex encoded DER. `signing_time` is unix time in seconds.
+    pub fn sign_cms_detached(&self, content: &[u8], certificate: &str, signing_time: u32) -> String {
+        utils::set_panic_hook();
+        let certificate = Certificate::from_pem_or_hex(certificate);
+        let key = self.signing_instance(Padding::Pkcs1v15Sign, HashAlgorithm::Sha256);
+        hex::encode(sign_detached(key, &certificate, content, signing_time as u64))
+    }
+
+    pub fn sign_cms_detached_pem(&self, content: &[u8], certificate: &str, signing_time: u32) -> String {
+        utils::set_panic_hook();
+        let certificate = Certificate::from_pem_or_hex(certificate);
+        let key = self.signing_instance(Padding::Pkcs1v15Sign, HashAlgorithm::Sha256);
+        der::to_pem("PKCS7", &sign_detached(key, &certificate, content, signing_time as u64))
+    }
+}
+
+#[wasm_bindgen]
+impl RSAPublicKeyPair {
+    pub fn verify_cms_detached(&self, content: &[u8], signature: &str) -> bool {
+        utils::set_panic_hook();
+        verify_detached(&der::from_pem_or_hex(signature), content, self)
+    }
+}
+
+/// Verifies detached CMS SignedData against the certificates embedded in it and returns
+/// the signer certificates as PEM, `undefined` if verification fails, as `verify_detached_signers`.
+/// Panics unless `policy` allows every signer key.
+#[wasm_bindgen]
+pub fn verify_cms_detached_with_certificate(content: &[u8], signature: &str, policy: &RSAPolicy) -> Option<String> {
+    utils::set_panic_hook();
+    verify_detached_signers(&der::from_pem_or_hex(signature), content, policy).map(|certificates| {
+        certificates.iter().map(|certificate| der::to_pem("CERTIFICATE", &certificate.raw)).collect()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fixtures::legacy_private_key_pair;
+    use fixtures::legacy_public_key_pair;
+    use fixtures::key_pairs;
+    use fixtures::CERTIFICATE;
+
+    #[test]
+    fn sign_and_verify_detached() {
+        let content = b"document contents";
+        let (private_instance, public_instance) = key_pairs();
+        let signature = private_instance.sign_cms_detached(content, CERTIFICATE, 1700000000);
+        assert!(public_instance.verify_cms_detached(content, &signature));
+        assert_eq!(verify_cms_detached_with_certificate(content, &signature, &RSAPolicy::default()), Some(der::to_pem("CERTIFICATE", &der::from_pem_or_hex(CERTIFICATE))));
+        assert!(!public_instance.verify_cms_detached(b"other contents", &signature));
+        assert_eq!(verify_cms_detached_with_certificate(b"other contents", &signature, &RSAPolicy::default()), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "key size 2048 is below the policy minimum of 3072 bits")]
+    fn verify_with_certificate_below_policy() {
+        let content = b"document contents";
+        let (private_instance, _) = key_pairs();
+        let signature = private_instance.sign_cms_detached(content, CERTIFICATE, 1700000000);
+        verify_cms_detached_with_certificate(content, &signature, &RSAPolicy::from_json(r#"{"minModulusBits":3072}"#));
+    }
+
+    #[test]
+    fn sign_and_verify_detached_pem() {
+        let content = b"document contents";
+        let (private_instance, public_instance) = key_pairs();
+        let signature = private_instance.sign_cms_detached_pem(content, CERTIFICATE, 1700000000);
+        assert!(signature.starts_with("-----BEGIN PKCS7-----\n"));
+        assert!(public_instance.verify_cms_detached(content, &signature));
+        assert!(verify_cms_detached_with_certificate(content, &signature, &RSAPolicy::default()).is_some());
+    }
+
+    #[test]
+    fn signed_attributes() {
+        let content = b"document contents";
+        let (private_instance, _) = key_pairs();
+        let signature = hex::decode(private_instance.sign_cms_detached(content, CERTIFICATE, 1700000000)).unwrap();
+        let (content_type, certificates, signer_infos) = parse_signed_data(&signature);
+        assert_eq!(content_type, OID_DATA);
+        assert_eq!(certificates.len(), 1);
+        assert_eq!(signer_infos.len(), 1);
+
+        let signer_info = der::parse_single(&signer_infos[0]);
+        let attributes = signer_info.children()[3].children();
+        let signing_time = find_attribute(&attributes, OID_SIGNING_TIME).unwrap();
+        assert_eq!(signing_time.content, b"231114221320Z");
+        let message_digest = find_attribute(&attributes, OID_MESSAGE_DIGEST).unwrap();
+        assert_eq!(message_digest.content, HashAlgorithm::Sha256.digest(content).as_slice());
+    }
+
+    #[test]
+    fn content_type_mismatch() {
+        let content = b"document contents";
+        let (private_instance, public_instance) = key_pairs();
+        let signature = private_instance.sign_cms_detached(content, CERTIFICATE, 1700000000);
+        // eContentType id-signedData instead of the signed id-data
+        let signature = signature.replacen("300b06092a864886f70d010701", "300b06092a864886f70d010702", 1);
+        assert!(!public_instance.verify_cms_detached(content, &signature));
+        assert_eq!(verify_cms_detached_with_certificate(content, &signature, &RSAPolicy::default()), None);
+    }
+
+    #[test]
+    fn verify_openssl_signature() {
+        // openssl cms -sign -binary -md sha256 -signer CERTIFICATE -inkey PRIVATE_KEY -outform DER
+        let signature = hex::encode(include_bytes!("testdata/cms_openssl.p7s"));
+        let (_, public_instance) = key_pairs();
+        assert!(public_instance.verify_cms_detached(b"document contents", &signature));
+        assert!(verify_cms_detached_with_certificate(b"document contents", &signature, &RSAPolicy::default()).is_some());
+        assert!(!public_instance.verify_cms_detached(b"other contents", &signature));
+    }
+
+    #[test]
+    fn verify_with_other_key() {
+        let content = b"document contents";
+        let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+        let (private_instance, _) = key_pairs();
+        let mut other_private_instance = legacy_private_key_pair();
+        let mut other_public_instance = legacy_public_key_pair();
+        other_private_instance.generate(1024, seed);
+        other_public_instance.create(&other_private_instance.get_n(), &other_private_instance.get_e());
+        let signature = private_instance.sign_cms_detached(content, CERTIFICATE, 1700000000);
+        assert!(!other_public_instance.verify_cms_detached(content, &signature));
+    }
+
+    #[test]
+    #[should_panic]
+    fn sign_with_foreign_certificate() {
+        let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+        let mut private_instance = legacy_private_key_pair();
+        private_instance.generate(1024, seed);
+        private_instance.sign_cms_detached(b"document contents", CERTIFICATE, 1700000000);
+    }
+
+    #[test]
+    #[should_panic]
+    fn verify_invalid_signature() {
+        verify_cms_detached_with_certificate(b"document contents", "3003020101", &RSAPolicy::default());
+    }
+}
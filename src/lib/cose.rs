@@ -0,0 +1,475 @@
+use super::*;
+use rsa::PublicKey;
+use cbor::Value;
+use hashing::HashAlgorithm;
+use primitives::{ crt_coefficients, crt_exponents };
+use public_keys::RSAPublicKeyPair;
+use private_keys::RSAPrivateKeyPair;
+use policy::Padding;
+
+const HEADER_ALG: i64 = 1;
+const HEADER_KID: i64 = 4;
+const COSE_SIGN1_TAG: u64 = 18;
+
+const KEY_KTY: i64 = 1;
+const KEY_KID: i64 = 2;
+const KEY_ALG: i64 = 3;
+const KTY_RSA: i64 = 3;
+const RSA_N: i64 = -1;
+const RSA_E: i64 = -2;
+const RSA_D: i64 = -3;
+const RSA_P: i64 = -4;
+const RSA_Q: i64 = -5;
+const RSA_DP: i64 = -6;
+const RSA_DQ: i64 = -7;
+const RSA_QINV: i64 = -8;
+const RSA_OTHER: i64 = -9;
+const RSA_R_I: i64 = -10;
+const RSA_D_I: i64 = -11;
+const RSA_T_I: i64 = -12;
+
+/// RSA algorithms registered for COSE by RFC 8230 (PS*) and RFC 8812 (RS*).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoseAlgorithm {
+    Ps256,
+    Ps384,
+    Ps512,
+    Rs256,
+    Rs384,
+    Rs512
+}
+
+impl CoseAlgorithm {
+    pub fn from_name(name: &str) -> CoseAlgorithm {
+        match name.to_uppercase().as_str() {
+            "PS256" => CoseAlgorithm::Ps256,
+            "PS384" => CoseAlgorithm::Ps384,
+            "PS512" => CoseAlgorithm::Ps512,
+            "RS256" => CoseAlgorithm::Rs256,
+            "RS384" => CoseAlgorithm::Rs384,
+            "RS512" => CoseAlgorithm::Rs512,
+            _ => panic!("unsupported cose algorithm {}", name)
+        }
+    }
+
+    pub fn from_id(id: i64) -> Option<CoseAlgorithm> {
+        match id {
+            -37 => Some(CoseAlgorithm::Ps256),
+            -38 => Some(CoseAlgorithm::Ps384),
+            -39 => Some(CoseAlgorithm::Ps512),
+            -257 => Some(CoseAlgorithm::Rs256),
+            -258 => Some(CoseAlgorithm::Rs384),
+            -259 => Some(CoseAlgorithm::Rs512),
+            _ => None
+        }
+    }
+
+    pub fn id(&self) -> i64 {
+        match self {
+            CoseAlgorithm::Ps256 => -37,
+            CoseAlgorithm::Ps384 => -38,
+            CoseAlgorithm::Ps512 => -39,
+            CoseAlgorithm::Rs256 => -257,
+            CoseAlgorithm::Rs384 => -258,
+            CoseAlgorithm::Rs512 => -259
+        }
+    }
+
+    pub fn hash(&self) -> HashAlgorithm {
+        match self {
+            CoseAlgorithm::Ps256 | CoseAlgorithm::Rs256 => HashAlgorithm::Sha256,
+            CoseAlgorithm::Ps384 | CoseAlgorithm::Rs384 => HashAlgorithm::Sha384,
+            CoseAlgorithm::Ps512 | CoseAlgorithm::Rs512 => HashAlgorithm::Sha512
+        }
+    }
+
+    fn is_pss(&self) -> bool {
+        matches!(self, CoseAlgorithm::Ps256 | CoseAlgorithm::Ps384 | CoseAlgorithm::Ps512)
+    }
+
+    pub fn padding(&self) -> Padding {
+        if self.is_pss() { Padding::Pss } else { Padding::Pkcs1v15Sign }
+    }
+
+    pub fn sign<R: RngCore>(&self, rng: &mut R, key: &RsaPrivateKey, data: &[u8]) -> Vec<u8> {
+        let hash = self.hash();
+        if self.is_pss() {
+            let mut salt = vec![0u8; hash.size()];
+            rng.fill_bytes(&mut salt);
+            return padding::pss_sign(key, hash, &hash.digest(data), &salt);
+        }
+
+        key.sign(PaddingScheme::new_pkcs1v15_sign(Some(hash.pkcs1v15_hash())), &hash.digest(data)).expect("sign error")
+    }
+
+    pub fn verify(&self, key: &RsaPublicKey, data: &[u8], signature: &[u8]) -> bool {
+        let hash = self.hash();
+        if self.is_pss() {
+            return padding::pss_verify(key, hash, &hash.digest(data), signature, Some(hash.size()));
+        }
+
+        key.verify(PaddingScheme::new_pkcs1v15_sign(Some(hash.pkcs1v15_hash())), &hash.digest(data), signature).is_ok()
+    }
+}
+
+fn sig_structure(protected: &[u8], external_aad: &[u8], payload: &[u8]) -> Vec<u8> {
+    cbor::encode(&Value::Array(vec![
+        Value::Text("Signature1".to_string()),
+        Value::Bytes(protected.to_vec()),
+        Value::Bytes(external_aad.to_vec()),
+        Value::Bytes(payload.to_vec())
+    ]))
+}
+
+/// COSE_Sign1 from RFC 9052 section 4.2 with the algorithm in the protected header.
+pub fn sign1<R: RngCore>(
+    rng: &mut R,
+    key: &RsaPrivateKey,
+    algorithm: CoseAlgorithm,
+    payload: &[u8],
+    external_aad: &[u8],
+    kid: Option<Vec<u8>>
+) -> Vec<u8> {
+    let protected = cbor::encode(&Value::Map(vec![(Value::integer(HEADER_ALG), Value::integer(algorithm.id()))]));
+    let unprotected = match kid {
+        Some(kid) => vec![(Value::integer(HEADER_KID), Value::Bytes(kid))],
+        None => vec![]
+    };
+    let signature = algorithm.sign(rng, key, &sig_structure(&protected, external_aad, payload));
+
+    cbor::encode(&Value::Tag(COSE_SIGN1_TAG, Box::new(Value::Array(vec![
+        Value::Bytes(protected),
+        Value::Map(unprotected),
+        Value::Bytes(payload.to_vec()),
+        Value::Bytes(signature)
+    ]))))
+}
+
+/// Fields of a decoded COSE_Sign1: protected header bytes, the protected map, payload and signature.
+struct Sign1 {
+    protected: Vec<u8>,
+    protected_header: Value,
+    payload: Option<Vec<u8>>,
+    signature: Vec<u8>
+}
+
+fn decode_sign1(data: &[u8]) -> Sign1 {
+    let message = match cbor::decode(data) {
+        Value::Tag(COSE_SIGN1_TAG, message) => *message,
+        Value::Tag(tag, _) => panic!("unexpected cose tag {}", tag),
+        message => message
+    };
+
+    match message.as_array() {
+        Some([Value::Bytes(protected), Value::Map(_), payload, Value::Bytes(signature)]) => {
+            let protected_header = if protected.is_empty() {
+                Value::Map(vec![])
+            } else {
+                cbor::decode(protected)
+            };
+
+            Sign1 {
+                protected: protected.clone(),
+                protected_header,
+                payload: match payload {
+                    Value::Bytes(payload) => Some(payload.clone()),
+                    Value::Null => None,
+                    _ => panic!("invalid cose payload")
+                },
+                signature: signature.clone()
+            }
+        },
+        _ => panic!("invalid cose_sign1 structure")
+    }
+}
+
+pub fn verify1(key: &RSAPublicKeyPair, data: &[u8], external_aad: &[u8], detached_payload: Option<&[u8]>) -> bool {
+    let message = decode_sign1(data);
+    let algorithm = match message.protected_header.get(HEADER_ALG).and_then(Value::as_integer).and_then(CoseAlgorithm::from_id) {
+        Some(algorithm) => algorithm,
+        None => return false
+    };
+    let payload = match (&message.payload, detached_payload) {
+        (Some(payload), None) => payload.as_slice(),
+        (None, Some(payload)) => payload,
+        _ => return false
+    };
+
+    let key = key.verifying_instance(algorithm.padding(), algorithm.hash());
+    algorithm.verify(key, &sig_structure(&message.protected, external_aad, payload), &message.signature)
+}
+
+fn integer_bytes(value: &BigUint) -> Value {
+    Value::Bytes(value.to_bytes_be())
+}
+
+fn key_integer(key: &Value, label: i64) -> Option<BigUint> {
+    key.get(label).map(|value| BigUint::from_bytes_be(value.as_bytes().expect("cose key parameters must be byte strings")))
+}
+
+fn key_header(kid: Option<Vec<u8>>, algorithm: Option<String>) -> Vec<(Value, Value)> {
+    let mut entries = vec![(Value::integer(KEY_KTY), Value::integer(KTY_RSA))];
+    if let Some(kid) = kid {
+        entries.push((Value::integer(KEY_KID), Value::Bytes(kid)));
+    }
+    if let Some(algorithm) = algorithm {
+        entries.push((Value::integer(KEY_ALG), Value::integer(CoseAlgorithm::from_name(&algorithm).id())));
+    }
+
+    entries
+}
+
+/// COSE_Key with kty 3 from RFC 8230 section 4.
+pub fn public_key_to_cose(key: &RsaPublicKey, kid: Option<Vec<u8>>, algorithm: Option<String>) -> Vec<u8> {
+    let mut entries = key_header(kid, algorithm);
+    entries.push((Value::integer(RSA_N), integer_bytes(key.n())));
+    entries.push((Value::integer(RSA_E), integer_bytes(key.e())));
+    cbor::encode(&Value::Map(entries))
+}
+
+pub fn private_key_to_cose(key: &RsaPrivateKey, kid: Option<Vec<u8>>, algorithm: Option<String>) -> Vec<u8> {
+    let primes = key.primes();
+    let exponents = crt_exponents(key);
+    let coefficients = crt_coefficients(key);
+
+    let mut entries = key_header(kid, algorithm);
+    entries.push((Value::integer(RSA_N), integer_bytes(key.n())));
+    entries.push((Value::integer(RSA_E), integer_bytes(key.e())));
+    entries.push((Value::integer(RSA_D), integer_bytes(key.d())));
+    entries.push((Value::integer(RSA_P), integer_bytes(&primes[0])));
+    entries.push((Value::integer(RSA_Q), integer_bytes(&primes[1])));
+    entries.push((Value::integer(RSA_DP), integer_bytes(&exponents[0])));
+    entries.push((Value::integer(RSA_DQ), integer_bytes(&exponents[1])));
+    entries.push((Value::integer(RSA_QINV), integer_bytes(&coefficients[0])));
+    if primes.len() > 2 {
+        let other_primes = (2..primes.len()).map(|index| Value::Map(vec![
+            (Value::integer(RSA_R_I), integer_bytes(&primes[index])),
+            (Value::integer(RSA_D_I), integer_bytes(&exponents[index])),
+            (Value::integer(RSA_T_I), integer_bytes(&coefficients[index - 1]))
+        ])).collect();
+        entries.push((Value::integer(RSA_OTHER), Value::Array(other_primes)));
+    }
+
+    cbor::encode(&Value::Map(entries))
+}
+
+fn decode_key(data: &[u8]) -> Value {
+    let key = cbor::decode(data);
+    if key.get(KEY_KTY).and_then(Value::as_integer) != Some(KTY_RSA) {
+        panic!("cose key is not an rsa key");
+    }
+
+    key
+}
+
+pub fn public_key_from_cose(data: &[u8]) -> RsaPublicKey {
+    let key = decode_key(data);
+    RsaPublicKey::new(
+        key_integer(&key, RSA_N).expect("missing n"),
+        key_integer(&key, RSA_E).expect("missing e")
+    ).expect("invalid create public instance")
+}
+
+/// The `alg` parameter of a COSE_Key, when present.
+pub fn key_algorithm(data: &[u8]) -> Option<i64> {
+    decode_key(data).get(KEY_ALG).map(|value| value.as_integer().expect("invalid cose key algorithm"))
+}
+
+pub fn private_key_from_cose(data: &[u8]) -> RsaPrivateKey {
+    let key = decode_key(data);
+    let mut primes = vec![key_integer(&key, RSA_P).expect("missing p"), key_integer(&key, RSA_Q).expect("missing q")];
+    if let Some(other_primes) = key.get(RSA_OTHER) {
+        for other_prime in other_primes.as_array().expect("invalid other primes") {
+            primes.push(key_integer(other_prime, RSA_R_I).expect("missing r_i"));
+        }
+    }
+
+    let private_key = RsaPrivateKey::from_components(
+        key_integer(&key, RSA_N).expect("missing n"),
+        key_integer(&key, RSA_E).expect("missing e"),
+        key_integer(&key, RSA_D).expect("missing d"),
+        primes
+    );
+    private_key.validate().expect("invalid private key");
+
+    let exponents = crt_exponents(&private_key);
+    let coefficients = crt_coefficients(&private_key);
+    let consistent = key_integer(&key, RSA_DP).as_ref() == Some(&exponents[0])
+        && key_integer(&key, RSA_DQ).as_ref() == Some(&exponents[1])
+        && key_integer(&key, RSA_QINV).as_ref() == Some(&coefficients[0]);
+    if !consistent {
+        panic!("inconsistent crt parameters");
+    }
+
+    private_key
+}
+
+#[wasm_bindgen]
+impl RSAPrivateKeyPair {
+    /// COSE_Sign1 (hex encoded, tagged) with PS256, PS384, PS512, RS256, RS384 or RS512.
+    pub fn sign_cose(
+        &self,
+        payload: &[u8],
+        algorithm: &str,
+        external_aad: &[u8],
+        kid: Option<Vec<u8>>,
+        random_seed: &str
+    ) -> String {
+        utils::set_panic_hook();
+        let mut rng = utils::seeded_rng(random_seed);
+        let algorithm = CoseAlgorithm::from_name(algorithm);
+        let key = self.signing_instance(algorithm.padding(), algorithm.hash());
+        hex::encode(sign1(&mut rng, key, algorithm, payload, external_aad, kid))
+    }
+
+    pub fn to_cose_key(&self, kid: Option<Vec<u8>>, algorithm: Option<String>) -> String {
+        utils::set_panic_hook();
+        hex::encode(private_key_to_cose(self.instance(), kid, algorithm))
+    }
+
+    pub fn from_cose_key(&mut self, data: &str) {
+        utils::set_panic_hook();
+        let decode_data = hex::decode(data).expect("invalid decode cose key");
+        self.set_instance(private_key_from_cose(&decode_data));
+    }
+}
+
+#[wasm_bindgen]
+impl RSAPublicKeyPair {
+    /// Verifies a hex encoded COSE_Sign1; `detached_payload` is required when the payload is nil.
+    pub fn verify_cose(&self, data: &str, external_aad: &[u8], detached_payload: Option<Vec<u8>>) -> bool {
+        utils::set_panic_hook();
+        let decode_data = hex::decode(data).expect("invalid decode cose message");
+        verify1(self, &decode_data, external_aad, detached_payload.as_deref())
+    }
+
+    pub fn to_cose_key(&self, kid: Option<Vec<u8>>, algorithm: Option<String>) -> String {
+        utils::set_panic_hook();
+        hex::encode(public_key_to_cose(self.instance(), kid, algorithm))
+    }
+
+    pub fn from_cose_key(&mut self, data: &str) {
+        utils::set_panic_hook();
+        let decode_data = hex::decode(data).expect("invalid decode cose key");
+        self.set_instance(public_key_from_cose(&decode_data));
+    }
+}
+
+/// Payload of a hex encoded COSE_Sign1, empty when detached. Does not verify the signature.
+#[wasm_bindgen]
+pub fn cose_sign1_payload(data: &str) -> Vec<u8> {
+    utils::set_panic_hook();
+    let decode_data = hex::decode(data).expect("invalid decode cose message");
+    decode_sign1(&decode_data).payload.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fixtures::key_pairs;
+
+    const SEED: &str = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+
+    #[test]
+    fn sign_and_verify() {
+        let (private_instance, public_instance) = key_pairs();
+        for algorithm in ["PS256", "PS384", "PS512", "RS256", "RS384", "RS512"].iter() {
+            let message = private_instance.sign_cose(b"device token", algorithm, b"aad", Some(b"kid".to_vec()), SEED);
+            assert!(public_instance.verify_cose(&message, b"aad", None), "{}", algorithm);
+            assert!(!public_instance.verify_cose(&message, b"other aad", None), "{}", algorithm);
+            assert_eq!(cose_sign1_payload(&message), b"device token".to_vec());
+        }
+    }
+
+    #[test]
+    fn sign1_structure() {
+        let (private_instance, _) = key_pairs();
+        let message = hex::decode(private_instance.sign_cose(b"payload", "PS256", &[], None, SEED)).unwrap();
+        // tag 18, array of 4, protected {1: -37}, empty unprotected map, payload
+        assert_eq!(hex::encode(&message[..9]), "d28444a1013824a047");
+        assert_eq!(&message[9..16], b"payload");
+    }
+
+    #[test]
+    fn rs256_is_deterministic() {
+        let (private_instance, _) = key_pairs();
+        let other_seed = "0000000000000000000000000000000000000000000000000000000000000000";
+        assert_eq!(
+            private_instance.sign_cose(b"payload", "RS256", &[], None, SEED),
+            private_instance.sign_cose(b"payload", "RS256", &[], None, other_seed)
+        );
+    }
+
+    #[test]
+    fn verify_with_unknown_algorithm() {
+        let (private_instance, public_instance) = key_pairs();
+        let message = hex::decode(private_instance.sign_cose(b"payload", "PS256", &[], None, SEED)).unwrap();
+        let mut tampered = message.clone();
+        tampered[6] = 0x64; // alg -101
+        assert!(!public_instance.verify_cose(&hex::encode(tampered), &[], None));
+    }
+
+    #[test]
+    fn public_cose_key_round_trip() {
+        let (_, public_instance) = key_pairs();
+        let cose_key = public_instance.to_cose_key(Some(b"kid".to_vec()), Some("RS256".to_string()));
+        let decoded = cbor::decode(&hex::decode(&cose_key).unwrap());
+        assert_eq!(decoded.get(KEY_KTY), Some(&Value::Unsigned(3)));
+        assert_eq!(decoded.get(KEY_ALG).and_then(Value::as_integer), Some(-257));
+        assert_eq!(decoded.get(RSA_E), Some(&Value::Bytes(vec![1, 0, 1])));
+
+        let mut imported = RSAPublicKeyPair::new();
+        imported.from_cose_key(&cose_key);
+        assert_eq!(imported.get_n(), public_instance.get_n());
+        assert_eq!(imported.get_e(), public_instance.get_e());
+    }
+
+    #[test]
+    fn private_cose_key_round_trip() {
+        let (private_instance, public_instance) = key_pairs();
+        let mut imported = RSAPrivateKeyPair::new();
+        imported.from_cose_key(&private_instance.to_cose_key(None, None));
+        assert_eq!(imported.get_d(), private_instance.get_d());
+        assert_eq!(imported.get_primes(), private_instance.get_primes());
+
+        let message = imported.sign_cose(b"payload", "PS512", &[], None, SEED);
+        assert!(public_instance.verify_cose(&message, &[], None));
+    }
+
+    #[test]
+    fn detached_payload() {
+        let (private_instance, public_instance) = key_pairs();
+        let message = hex::decode(private_instance.sign_cose(b"payload", "PS256", &[], None, SEED)).unwrap();
+        let mut parts = match cbor::decode(&message) {
+            Value::Tag(_, message) => message.as_array().unwrap().to_vec(),
+            _ => panic!("expected tagged message")
+        };
+        parts[2] = Value::Null;
+        let detached = hex::encode(cbor::encode(&Value::Array(parts)));
+        assert!(public_instance.verify_cose(&detached, &[], Some(b"payload".to_vec())));
+        assert!(!public_instance.verify_cose(&detached, &[], None));
+    }
+
+    #[test]
+    #[should_panic]
+    fn public_key_from_non_rsa_cose_key() {
+        // kty 2 (EC2)
+        let mut public_instance = RSAPublicKeyPair::new();
+        public_instance.from_cose_key("a10102");
+    }
+
+    #[test]
+    #[should_panic]
+    fn private_key_with_inconsistent_crt() {
+        let (private_instance, _) = key_pairs();
+        let key = cbor::decode(&hex::decode(private_instance.to_cose_key(None, None)).unwrap());
+        let entries = key.as_map().unwrap().iter().map(|(label, value)| {
+            if label.as_integer() == Some(RSA_DP) {
+                (label.clone(), Value::Bytes(vec![1]))
+            } else {
+                (label.clone(), value.clone())
+            }
+        }).collect();
+        RSAPrivateKeyPair::new().from_cose_key(&hex::encode(cbor::encode(&Value::Map(entries))));
+    }
+}
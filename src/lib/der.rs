@@ -0,0 +1,306 @@
+use super::*;
+
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_NULL: u8 = 0x05;
+pub const TAG_OID: u8 = 0x06;
+pub const TAG_UTC_TIME: u8 = 0x17;
+pub const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub const TAG_SEQUENCE: u8 = 0x30;
+pub const TAG_SET: u8 = 0x31;
+
+/// Parsed DER element borrowing from the input buffer.
+#[derive(Debug, Clone, Copy)]
+pub struct DerObject<'a> {
+    pub tag: u8,
+    pub content: &'a [u8],
+    pub raw: &'a [u8]
+}
+
+impl<'a> DerObject<'a> {
+    pub fn children(&self) -> Vec<DerObject<'a>> {
+        parse_all(self.content)
+    }
+
+    pub fn expect_tag(&self, tag: u8) -> &DerObject<'a> {
+        if self.tag != tag {
+            panic!("unexpected der tag {:#04x}, expected {:#04x}", self.tag, tag);
+        }
+
+        self
+    }
+
+    pub fn as_oid(&self) -> String {
+        decode_oid(self.expect_tag(TAG_OID).content)
+    }
+
+    pub fn as_biguint(&self) -> BigUint {
+        BigUint::from_bytes_be(self.expect_tag(TAG_INTEGER).content)
+    }
+}
+
+pub fn encode(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut result = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        result.push(len as u8);
+    } else {
+        let len_bytes: Vec<u8> = len.to_be_bytes().iter().cloned().skip_while(|byte| *byte == 0).collect();
+        result.push(0x80 | len_bytes.len() as u8);
+        result.extend(len_bytes);
+    }
+
+    result.extend_from_slice(content);
+    result
+}
+
+pub fn sequence(items: &[Vec<u8>]) -> Vec<u8> {
+    encode(TAG_SEQUENCE, &items.concat())
+}
+
+/// DER SET OF: elements are sorted by their encodings (X.690 11.6).
+pub fn set_of(items: &[Vec<u8>]) -> Vec<u8> {
+    encode(TAG_SET, &sorted(items).concat())
+}
+
+pub fn sorted(items: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    let mut items = items.to_vec();
+    items.sort();
+    items
+}
+
+pub fn context(number: u8, constructed: bool, content: &[u8]) -> Vec<u8> {
+    let tag = 0x80 | if constructed { 0x20 } else { 0 } | number;
+    encode(tag, content)
+}
+
+pub fn integer(value: &BigUint) -> Vec<u8> {
+    let mut bytes = value.to_bytes_be();
+    if bytes[0] & 0x80 != 0 {
+        bytes.insert(0, 0);
+    }
+
+    encode(TAG_INTEGER, &bytes)
+}
+
+pub fn small_integer(value: u32) -> Vec<u8> {
+    integer(&BigUint::from(value))
+}
+
+pub fn octet_string(content: &[u8]) -> Vec<u8> {
+    encode(TAG_OCTET_STRING, content)
+}
+
+pub fn null() -> Vec<u8> {
+    encode(TAG_NULL, &[])
+}
+
+pub fn oid(value: &str) -> Vec<u8> {
+    let arcs: Vec<u64> = value.split('.').map(|arc| arc.parse().expect("invalid oid")).collect();
+    if arcs.len() < 2 {
+        panic!("invalid oid {}", value);
+    }
+
+    let mut content = vec![];
+    for arc in std::iter::once(arcs[0] * 40 + arcs[1]).chain(arcs[2..].iter().cloned()) {
+        let mut encoded = vec![(arc & 0x7f) as u8];
+        let mut rest = arc >> 7;
+        while rest > 0 {
+            encoded.push(0x80 | (rest & 0x7f) as u8);
+            rest >>= 7;
+        }
+        encoded.reverse();
+        content.extend(encoded);
+    }
+
+    encode(TAG_OID, &content)
+}
+
+pub fn decode_oid(content: &[u8]) -> String {
+    let mut arcs: Vec<u64> = vec![];
+    let mut value: u64 = 0;
+    for byte in content {
+        value = (value << 7) | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            if arcs.is_empty() {
+                let first = std::cmp::min(value / 40, 2);
+                arcs.push(first);
+                arcs.push(value - first * 40);
+            } else {
+                arcs.push(value);
+            }
+            value = 0;
+        }
+    }
+
+    arcs.iter().map(|arc| arc.to_string()).collect::<Vec<String>>().join(".")
+}
+
+/// AlgorithmIdentifier with optional parameters.
+pub fn algorithm(algorithm_oid: &str, parameters: Option<Vec<u8>>) -> Vec<u8> {
+    match parameters {
+        Some(parameters) => sequence(&[oid(algorithm_oid), parameters]),
+        None => sequence(&[oid(algorithm_oid)])
+    }
+}
+
+/// UTCTime (YYMMDDHHMMSSZ) for dates from 1950 to 2049, GeneralizedTime otherwise.
+pub fn time(unix_seconds: u64) -> Vec<u8> {
+    let days = (unix_seconds / 86400) as i64;
+    let seconds = unix_seconds % 86400;
+
+    // civil_from_days, http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    let clock = format!(
+        "{:02}{:02}{:02}{:02}{:02}Z",
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+
+    if (1950..2050).contains(&year) {
+        encode(TAG_UTC_TIME, format!("{:02}{}", year % 100, clock).as_bytes())
+    } else {
+        encode(TAG_GENERALIZED_TIME, format!("{:04}{}", year, clock).as_bytes())
+    }
+}
+
+pub fn parse(data: &[u8]) -> (DerObject<'_>, &[u8]) {
+    if data.len() < 2 {
+        panic!("invalid der: truncated header");
+    }
+
+    let tag = data[0];
+    let (len, header_len) = if data[1] < 0x80 {
+        (data[1] as usize, 2)
+    } else {
+        let len_len = (data[1] & 0x7f) as usize;
+        if len_len == 0 || len_len > 4 || data.len() < 2 + len_len {
+            panic!("invalid der: length");
+        }
+
+        let len = data[2..2 + len_len].iter().fold(0usize, |acc, byte| (acc << 8) | *byte as usize);
+        (len, 2 + len_len)
+    };
+
+    if data.len() < header_len + len {
+        panic!("invalid der: truncated content");
+    }
+
+    let object = DerObject {
+        tag,
+        content: &data[header_len..header_len + len],
+        raw: &data[..header_len + len]
+    };
+
+    (object, &data[header_len + len..])
+}
+
+pub fn parse_single(data: &[u8]) -> DerObject<'_> {
+    let (object, rest) = parse(data);
+    if !rest.is_empty() {
+        panic!("invalid der: trailing data");
+    }
+
+    object
+}
+
+pub fn parse_all(mut data: &[u8]) -> Vec<DerObject<'_>> {
+    let mut objects = vec![];
+    while !data.is_empty() {
+        let (object, rest) = parse(data);
+        objects.push(object);
+        data = rest;
+    }
+
+    objects
+}
+
+pub fn to_pem(label: &str, der: &[u8]) -> String {
+    let encoded = base64::encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).unwrap());
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem
+}
+
+/// Accepts PEM (any label) or hex encoded DER.
+pub fn from_pem_or_hex(data: &str) -> Vec<u8> {
+    let data = data.trim();
+    if data.starts_with("-----BEGIN") {
+        let body: String = data
+            .lines()
+            .filter(|line| !line.starts_with("-----"))
+            .map(|line| line.trim())
+            .collect();
+
+        return base64::decode(&body).expect("invalid pem");
+    }
+
+    hex::decode(data).expect("invalid decode der")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_and_decode_oid() {
+        let encoded = oid("1.2.840.113549.1.7.2");
+        assert_eq!(hex::encode(&encoded), "06092a864886f70d010702");
+        assert_eq!(parse_single(&encoded).as_oid(), "1.2.840.113549.1.7.2");
+    }
+
+    #[test]
+    fn encode_long_length() {
+        let encoded = octet_string(&[0u8; 300]);
+        assert_eq!(&encoded[..4], &[0x04, 0x82, 0x01, 0x2c]);
+        assert_eq!(parse_single(&encoded).content.len(), 300);
+    }
+
+    #[test]
+    fn encode_integer_with_high_bit() {
+        assert_eq!(hex::encode(small_integer(128)), "02020080");
+        assert_eq!(hex::encode(small_integer(1)), "020101");
+    }
+
+    #[test]
+    fn encode_time() {
+        assert_eq!(time(0), encode(TAG_UTC_TIME, b"700101000000Z"));
+        assert_eq!(time(1700000000), encode(TAG_UTC_TIME, b"231114221320Z"));
+        assert_eq!(time(2524608000), encode(TAG_GENERALIZED_TIME, b"20500101000000Z"));
+    }
+
+    #[test]
+    fn set_of_is_sorted() {
+        let encoded = set_of(&[small_integer(2), small_integer(1)]);
+        assert_eq!(hex::encode(&encoded), "3106020101020102");
+    }
+
+    #[test]
+    fn pem_round_trip() {
+        let der = sequence(&[null()]);
+        assert_eq!(from_pem_or_hex(&to_pem("TEST", &der)), der);
+        assert_eq!(from_pem_or_hex(&hex::encode(&der)), der);
+    }
+
+    #[test]
+    #[should_panic]
+    fn parse_truncated() {
+        parse(&[0x30, 0x05, 0x00]);
+    }
+}
@@ -0,0 +1,240 @@
+use super::*;
+use chacha20::ChaCha20;
+use chacha20::cipher::{ KeyIvInit, StreamCipher };
+use hkdf::Hkdf;
+use hmac::{ Hmac, Mac };
+use hmac::digest::KeyInit;
+use num_bigint_dig::prime::probably_prime;
+use num_traits::One;
+use primitives::mod_inverse;
+use private_keys::RSAPrivateKeyPair;
+use sha2::Sha512;
+
+pub const LATEST_VERSION: u32 = 1;
+const PASSPHRASE_ITERATIONS: u32 = 600_000;
+const MNEMONIC_ITERATIONS: u32 = 2048;
+const DRBG_SALT: &[u8] = b"rsa-lib key derivation v1";
+const MILLER_RABIN_ROUNDS: usize = 20;
+const PUBLIC_EXPONENT: u32 = 65537;
+
+/// PBKDF2 from RFC 8018 5.2 with HMAC as the PRF.
+fn pbkdf2<M: Mac + KeyInit + Clone>(password: &[u8], salt: &[u8], iterations: u32, output: &mut [u8]) {
+    let prf = <M as Mac>::new_from_slice(password).expect("hmac error");
+    for (index, chunk) in output.chunks_mut(M::output_size()).enumerate() {
+        let mut mac = prf.clone();
+        mac.update(salt);
+        mac.update(&(index as u32 + 1).to_be_bytes());
+        let mut u = mac.finalize().into_bytes();
+        let mut block = u.clone();
+
+        for _ in 1..iterations {
+            let mut mac = prf.clone();
+            mac.update(&u);
+            u = mac.finalize().into_bytes();
+            block.iter_mut().zip(u.iter()).for_each(|(block, u)| *block ^= u);
+        }
+
+        chunk.copy_from_slice(&block[..chunk.len()]);
+    }
+}
+
+/// Seed of a BIP-39 mnemonic. The words and the passphrase are expected in NFKD form.
+fn mnemonic_seed(mnemonic: &str, passphrase: &str) -> [u8; 64] {
+    let words = mnemonic.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let mut seed = [0u8; 64];
+    pbkdf2::<Hmac<Sha512>>(words.as_bytes(), format!("mnemonic{}", passphrase).as_bytes(), MNEMONIC_ITERATIONS, &mut seed);
+    seed
+}
+
+/// ChaCha20 keystream (RFC 8439, zero nonce, counter from 0) as a random number generator.
+/// Unlike `StdRng` its output is fixed by the algorithm, not by the `rand` version.
+pub struct ChaCha20Drbg {
+    cipher: ChaCha20
+}
+
+impl ChaCha20Drbg {
+    pub fn new(key: &[u8; 32]) -> Self {
+        ChaCha20Drbg { cipher: ChaCha20::new(key.into(), &[0u8; 12].into()) }
+    }
+}
+
+impl RngCore for ChaCha20Drbg {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.iter_mut().for_each(|byte| *byte = 0);
+        self.cipher.apply_keystream(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+fn check_parameters(bits: usize, version: u32) {
+    if version != LATEST_VERSION {
+        panic!("unsupported derivation version {}", version);
+    }
+    if bits < 1024 || bits % 16 != 0 {
+        panic!("key size must be a multiple of 16 and at least 1024");
+    }
+}
+
+/// Next prime of the version 1 search: `bits / 8` bytes of the stream read big-endian, with the two
+/// top bits and the lowest bit set, until one is a probable prime with `gcd(p - 1, e) = 1`.
+fn next_prime(drbg: &mut ChaCha20Drbg, bits: usize, e: &BigUint) -> BigUint {
+    let mut bytes = vec![0u8; bits / 8];
+    loop {
+        drbg.fill_bytes(&mut bytes);
+        bytes[0] |= 0xc0;
+        *bytes.last_mut().unwrap() |= 1;
+
+        let candidate = BigUint::from_bytes_be(&bytes);
+        if mod_inverse(e, &(&candidate - BigUint::one())).is_some() && probably_prime(&candidate, MILLER_RABIN_ROUNDS) {
+            return candidate;
+        }
+    }
+}
+
+/// Version 1 of the derivation from input keying material `ikm`:
+///
+/// 1. DRBG key = HKDF-SHA256(ikm, salt = "rsa-lib key derivation v1", info = bits as u32 big-endian), 32 bytes.
+/// 2. The primes are drawn from `ChaCha20Drbg` with that key by `next_prime`, `p` first,
+///    then the first `q` different from `p`.
+/// 3. n = pq, e = 65537 and d = e^-1 mod (p - 1)(q - 1).
+///
+/// Any change to this output breaks the keys of existing users, add a new version instead.
+pub fn derive_key(ikm: &[u8], bits: usize, version: u32) -> RsaPrivateKey {
+    check_parameters(bits, version);
+
+    let mut drbg_key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(DRBG_SALT), ikm).expand(&(bits as u32).to_be_bytes(), &mut drbg_key).expect("hkdf error");
+    let mut drbg = ChaCha20Drbg::new(&drbg_key);
+
+    let e = BigUint::from(PUBLIC_EXPONENT);
+    let p = next_prime(&mut drbg, bits / 2, &e);
+    let q = loop {
+        let q = next_prime(&mut drbg, bits / 2, &e);
+        if q != p {
+            break q;
+        }
+    };
+
+    keygen::assemble_key(e, vec![p, q])
+}
+
+#[wasm_bindgen]
+impl RSAPrivateKeyPair {
+    /// Derives the same key for the same inputs and `version`, on every platform and release.
+    /// The input keying material is PBKDF2-HMAC-SHA256(passphrase, salt, 600000 iterations), 32 bytes,
+    /// see `derive_key` for the rest. `salt` should be unique per user, e.g. an account id.
+    pub fn derive_from_passphrase(&mut self, bits: usize, passphrase: &str, salt: &str, version: u32) {
+        utils::set_panic_hook();
+        check_parameters(bits, version);
+        self.policy().check_key_size(bits);
+        if salt.is_empty() {
+            panic!("salt must not be empty");
+        }
+
+        let mut ikm = [0u8; 32];
+        pbkdf2::<Hmac<Sha256>>(passphrase.as_bytes(), salt.as_bytes(), PASSPHRASE_ITERATIONS, &mut ikm);
+        self.set_instance(derive_key(&ikm, bits, version));
+    }
+
+    /// `derive_from_passphrase` with the BIP-39 seed of `mnemonic` and `passphrase` as input keying
+    /// material. Words are separated by single spaces; the checksum is not checked.
+    pub fn derive_from_mnemonic(&mut self, bits: usize, mnemonic: &str, passphrase: &str, version: u32) {
+        utils::set_panic_hook();
+        self.policy().check_key_size(bits);
+        self.set_instance(derive_key(&mnemonic_seed(mnemonic, passphrase), bits, version));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fixtures::legacy_private_key_pair;
+    use hashing::HashAlgorithm;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn pbkdf2_vectors() {
+        let mut output = [0u8; 32];
+        pbkdf2::<Hmac<Sha256>>(b"password", b"salt", 1, &mut output);
+        assert_eq!(hex::encode(output), "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b");
+
+        let mut output = [0u8; 40];
+        pbkdf2::<Hmac<Sha256>>(b"passwordPASSWORDpassword", b"saltSALTsaltSALTsaltSALTsaltSALTsalt", 4096, &mut output);
+        assert_eq!(hex::encode(output), "348c89dbcbd32b2f32d814b8116e84cf2b17347ebc1800181c4e2a1fb8dd53e1c635518c7dac47e9");
+    }
+
+    #[test]
+    fn bip39_seed() {
+        assert_eq!(
+            hex::encode(&mnemonic_seed(MNEMONIC, "TREZOR")[..]),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+        assert_eq!(mnemonic_seed(&format!(" {}\n", MNEMONIC.replace(' ', "  ")), ""), mnemonic_seed(MNEMONIC, ""));
+    }
+
+    #[test]
+    fn drbg_stream() {
+        // RFC 8439 2.3.2 block function with counter 0 is the keystream of the all-zero key.
+        let mut drbg = ChaCha20Drbg::new(&[0u8; 32]);
+        let mut first = [0u8; 16];
+        drbg.fill_bytes(&mut first);
+        assert_eq!(hex::encode(first), "76b8e0ada0f13d90405d6ae55386bd28");
+        assert_eq!(drbg.next_u32(), u32::from_le_bytes([0xbd, 0xd2, 0x19, 0xb8]));
+    }
+
+    // The frozen vectors were reproduced with an independent implementation. They must never change.
+    #[test]
+    fn frozen_mnemonic_vector() {
+        let mut private_instance = legacy_private_key_pair();
+        private_instance.derive_from_mnemonic(1024, MNEMONIC, "", 1);
+        assert_eq!(
+            private_instance.get_n(),
+            "f26c3f0ee8c987bee7115fa8574e503589ab9f094bbcde27be17ce400d99cb8b617976daf9d2e24f52ae4a1eecf148a06689afb848945c17e3d940f57627b285\
+             da75042ccb0abf44b89ff7c507a15302e4e6dbf1b26a0f5f8b91e540f74b09b817e24d193fd3e5b1644f56c019cc6d3c2d8991fb1435778ea6b8dda391c802e9"
+        );
+        assert_eq!(private_instance.get_e(), "10001");
+        assert!(private_instance.instance().validate().is_ok());
+
+        let mut other_instance = legacy_private_key_pair();
+        other_instance.derive_from_mnemonic(1024, MNEMONIC, "TREZOR", 1);
+        assert_ne!(other_instance.get_n(), private_instance.get_n());
+    }
+
+    #[test]
+    fn frozen_passphrase_vector() {
+        let mut private_instance = RSAPrivateKeyPair::new();
+        private_instance.derive_from_passphrase(2048, "correct horse battery staple", "user@example.com", 1);
+        let n = private_instance.instance().n().to_bytes_be();
+        assert_eq!(hex::encode(HashAlgorithm::Sha256.digest(&n)), "79cdd0d63eece13c0787cc6cf4f05fd89a862a7504210a9bded116cec44c5c6c");
+        assert_eq!(private_instance.instance().primes()[0].bits(), 1024);
+    }
+
+    #[test]
+    #[should_panic(expected = "unsupported derivation version 2")]
+    fn unknown_version() {
+        RSAPrivateKeyPair::new().derive_from_mnemonic(2048, MNEMONIC, "", 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "salt must not be empty")]
+    fn empty_salt() {
+        RSAPrivateKeyPair::new().derive_from_passphrase(2048, "passphrase", "", 1);
+    }
+}
@@ -0,0 +1,169 @@
+use hmac::{ Hmac, Mac };
+use rand::{ CryptoRng, Error, RngCore };
+use rand::rngs::OsRng;
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+use super::utils;
+
+const OUTLEN: usize = 32;
+/// Bytes of entropy input and nonce drawn from the platform, for a security strength of 256 bits.
+const ENTROPY_LEN: usize = 32;
+const NONCE_LEN: usize = 16;
+/// Limits from SP 800-90A Table 2 for HMAC_DRBG.
+const MAX_REQUEST_BYTES: usize = 1 << 16;
+const RESEED_INTERVAL: u64 = 1 << 48;
+
+/// HMAC_DRBG with SHA-256 from NIST SP 800-90A Rev. 1, section 10.1.2.
+pub struct HmacDrbg {
+    key: [u8; OUTLEN],
+    value: [u8; OUTLEN],
+    reseed_counter: u64
+}
+
+impl HmacDrbg {
+    /// Instantiate from `entropy || nonce || personalization`, section 10.1.2.3.
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> HmacDrbg {
+        let mut drbg = HmacDrbg { key: [0; OUTLEN], value: [1; OUTLEN], reseed_counter: 1 };
+        drbg.update(&[entropy, nonce, personalization]);
+        drbg
+    }
+
+    /// Instantiated from the platform CSPRNG.
+    pub fn from_os_rng(personalization: &[u8]) -> HmacDrbg {
+        let mut input = [0u8; ENTROPY_LEN + NONCE_LEN];
+        OsRng.fill_bytes(&mut input);
+        let drbg = HmacDrbg::new(&input[..ENTROPY_LEN], &input[ENTROPY_LEN..], personalization);
+        input.zeroize();
+        drbg
+    }
+
+    /// Instantiated from a hex `random_seed` used as entropy input, or `from_os_rng` for `OS_RANDOM`.
+    pub fn from_seed(random_seed: &str, personalization: &[u8]) -> HmacDrbg {
+        if random_seed == utils::OS_RANDOM {
+            return HmacDrbg::from_os_rng(personalization);
+        }
+        if !utils::caller_seeds_allowed() {
+            panic!("caller supplied random seeds are forbidden, pass \"{}\" instead", utils::OS_RANDOM);
+        }
+
+        let mut entropy = hex::decode(random_seed).expect("invalid decode");
+        if entropy.len() != ENTROPY_LEN {
+            panic!("random seed must be {} bytes", ENTROPY_LEN);
+        }
+        let drbg = HmacDrbg::new(&entropy, &[], personalization);
+        entropy.zeroize();
+        drbg
+    }
+
+    fn hmac(&self, parts: &[&[u8]]) -> [u8; OUTLEN] {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.key).expect("hmac error");
+        for part in parts {
+            mac.update(part);
+        }
+        mac.finalize().into_bytes().into()
+    }
+
+    /// HMAC_DRBG_Update, section 10.1.2.2. `provided_data` is the concatenation of `parts`.
+    fn update(&mut self, parts: &[&[u8]]) {
+        let provided = parts.iter().any(|part| !part.is_empty());
+        for round in 0..if provided { 2u8 } else { 1 } {
+            let mut input: Vec<&[u8]> = vec![&self.value, std::slice::from_ref(&round)];
+            input.extend_from_slice(parts);
+            self.key = self.hmac(&input);
+            self.value = self.hmac(&[&self.value]);
+        }
+    }
+
+    /// HMAC_DRBG_Generate, section 10.1.2.5.
+    pub fn generate(&mut self, output: &mut [u8], additional_input: &[u8]) {
+        if output.len() > MAX_REQUEST_BYTES {
+            panic!("at most {} bytes can be requested at once", MAX_REQUEST_BYTES);
+        }
+        if self.reseed_counter > RESEED_INTERVAL {
+            panic!("random generator has to be reseeded");
+        }
+
+        if !additional_input.is_empty() {
+            self.update(&[additional_input]);
+        }
+        for chunk in output.chunks_mut(OUTLEN) {
+            self.value = self.hmac(&[&self.value]);
+            chunk.copy_from_slice(&self.value[..chunk.len()]);
+        }
+        self.update(&[additional_input]);
+        self.reseed_counter += 1;
+    }
+}
+
+impl Drop for HmacDrbg {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.value.zeroize();
+    }
+}
+
+impl RngCore for HmacDrbg {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.generate(&mut bytes, &[]);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.generate(&mut bytes, &[]);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(MAX_REQUEST_BYTES) {
+            self.generate(chunk, &[]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for HmacDrbg {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::Value;
+
+    fn field(vector: &Value, name: &str) -> Vec<u8> {
+        hex::decode(vector[name].as_str().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn nist_vectors() {
+        let vectors: Value = serde_json::from_str(include_str!("testdata/hmac_drbg_sha256.json")).unwrap();
+        for vector in vectors.as_array().unwrap() {
+            let mut drbg = HmacDrbg::new(&field(vector, "entropy"), &field(vector, "nonce"), &field(vector, "personalization"));
+            let expected = field(vector, "expected");
+            let mut output = vec![0u8; expected.len()];
+            for additional in vector["additional"].as_array().unwrap() {
+                drbg.generate(&mut output, &hex::decode(additional.as_str().unwrap()).unwrap());
+            }
+            assert_eq!(hex::encode(output), hex::encode(expected));
+        }
+    }
+
+    #[test]
+    fn seeded_and_os_instances() {
+        let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+        assert_eq!(HmacDrbg::from_seed(seed, b"").next_u64(), HmacDrbg::from_seed(seed, b"").next_u64());
+        assert_ne!(HmacDrbg::from_seed(seed, b"").next_u64(), HmacDrbg::from_seed(seed, b"other").next_u64());
+        assert_ne!(HmacDrbg::from_os_rng(b"").next_u64(), HmacDrbg::from_os_rng(b"").next_u64());
+    }
+
+    #[test]
+    #[should_panic(expected = "random seed must be 32 bytes")]
+    fn short_seed() {
+        HmacDrbg::from_seed("c993abb9", b"");
+    }
+}
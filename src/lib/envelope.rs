@@ -0,0 +1,581 @@
+use super::*;
+use rsa::PublicKey;
+use aes_gcm::{ AesGcm, Nonce };
+use aes_gcm::aead::{ Aead, KeyInit, Payload, consts::U12 };
+use aes::{ Aes128, Aes192, Aes256 };
+use hashing::HashAlgorithm;
+use key_wrap::AesKey;
+use x509::Certificate;
+use cms::{ OID_DATA, OID_RSA_ENCRYPTION, hash_oid, hash_from_oid };
+use private_keys::RSAPrivateKeyPair;
+use policy::{ Padding, RSAPolicy };
+
+pub const OID_ENVELOPED_DATA: &str = "1.2.840.113549.1.7.3";
+pub const OID_AUTH_ENVELOPED_DATA: &str = "1.2.840.113549.1.9.16.1.23";
+pub const OID_RSAES_OAEP: &str = "1.2.840.113549.1.1.7";
+pub const OID_MGF1: &str = "1.2.840.113549.1.1.8";
+
+const AES_BLOCK_SIZE: usize = 16;
+const GCM_NONCE_SIZE: usize = 12;
+const GCM_TAG_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContentEncryption {
+    Cbc(usize),
+    Gcm(usize)
+}
+
+impl ContentEncryption {
+    pub fn from_name(name: &str) -> ContentEncryption {
+        match name.to_uppercase().as_str() {
+            "AES-128-CBC" => ContentEncryption::Cbc(16),
+            "AES-192-CBC" => ContentEncryption::Cbc(24),
+            "AES-256-CBC" => ContentEncryption::Cbc(32),
+            "AES-128-GCM" => ContentEncryption::Gcm(16),
+            "AES-192-GCM" => ContentEncryption::Gcm(24),
+            "AES-256-GCM" => ContentEncryption::Gcm(32),
+            _ => panic!("unsupported content encryption {}", name)
+        }
+    }
+
+    pub fn from_oid(oid: &str) -> ContentEncryption {
+        match oid {
+            "2.16.840.1.101.3.4.1.2" => ContentEncryption::Cbc(16),
+            "2.16.840.1.101.3.4.1.22" => ContentEncryption::Cbc(24),
+            "2.16.840.1.101.3.4.1.42" => ContentEncryption::Cbc(32),
+            "2.16.840.1.101.3.4.1.6" => ContentEncryption::Gcm(16),
+            "2.16.840.1.101.3.4.1.26" => ContentEncryption::Gcm(24),
+            "2.16.840.1.101.3.4.1.46" => ContentEncryption::Gcm(32),
+            _ => panic!("unsupported content encryption {}", oid)
+        }
+    }
+
+    pub fn oid(&self) -> &'static str {
+        match self {
+            ContentEncryption::Cbc(16) => "2.16.840.1.101.3.4.1.2",
+            ContentEncryption::Cbc(24) => "2.16.840.1.101.3.4.1.22",
+            ContentEncryption::Cbc(_) => "2.16.840.1.101.3.4.1.42",
+            ContentEncryption::Gcm(16) => "2.16.840.1.101.3.4.1.6",
+            ContentEncryption::Gcm(24) => "2.16.840.1.101.3.4.1.26",
+            ContentEncryption::Gcm(_) => "2.16.840.1.101.3.4.1.46"
+        }
+    }
+
+    pub fn key_len(&self) -> usize {
+        match self {
+            ContentEncryption::Cbc(len) | ContentEncryption::Gcm(len) => *len
+        }
+    }
+}
+
+fn cbc_encrypt(key: &[u8], iv: &[u8], content: &[u8]) -> Vec<u8> {
+    let cipher = AesKey::new(key);
+    let pad = AES_BLOCK_SIZE - content.len() % AES_BLOCK_SIZE;
+    let mut data = content.to_vec();
+    data.resize(content.len() + pad, pad as u8);
+
+    let mut previous = iv.to_vec();
+    for block in data.chunks_mut(AES_BLOCK_SIZE) {
+        for (byte, previous_byte) in block.iter_mut().zip(&previous) {
+            *byte ^= previous_byte;
+        }
+        cipher.encrypt_block(block);
+        previous = block.to_vec();
+    }
+
+    data
+}
+
+fn cbc_decrypt(key: &[u8], iv: &[u8], ciphertext: &[u8]) -> Option<Vec<u8>> {
+    if ciphertext.is_empty() || ciphertext.len() % AES_BLOCK_SIZE != 0 {
+        return None;
+    }
+
+    let cipher = AesKey::new(key);
+    let mut data = ciphertext.to_vec();
+    let mut previous = iv.to_vec();
+    for block in data.chunks_mut(AES_BLOCK_SIZE) {
+        let current = block.to_vec();
+        cipher.decrypt_block(block);
+        for (byte, previous_byte) in block.iter_mut().zip(&previous) {
+            *byte ^= previous_byte;
+        }
+        previous = current;
+    }
+
+    let pad = *data.last().unwrap() as usize;
+    if pad == 0 || pad > AES_BLOCK_SIZE || data[data.len() - pad..].iter().any(|byte| *byte as usize != pad) {
+        return None;
+    }
+
+    data.truncate(data.len() - pad);
+    Some(data)
+}
+
+fn gcm_encrypt(key: &[u8], nonce: &[u8], aad: &[u8], content: &[u8]) -> Vec<u8> {
+    let payload = Payload { msg: content, aad };
+    let nonce = Nonce::from_slice(nonce);
+    match key.len() {
+        16 => AesGcm::<Aes128, U12>::new_from_slice(key).unwrap().encrypt(nonce, payload),
+        24 => AesGcm::<Aes192, U12>::new_from_slice(key).unwrap().encrypt(nonce, payload),
+        32 => AesGcm::<Aes256, U12>::new_from_slice(key).unwrap().encrypt(nonce, payload),
+        len => panic!("invalid aes key length {}", len)
+    }.expect("encryption error")
+}
+
+fn gcm_decrypt(key: &[u8], nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Option<Vec<u8>> {
+    if nonce.len() != GCM_NONCE_SIZE {
+        panic!("unsupported gcm nonce length {}", nonce.len());
+    }
+
+    let payload = Payload { msg: ciphertext, aad };
+    let nonce = Nonce::from_slice(nonce);
+    match key.len() {
+        16 => AesGcm::<Aes128, U12>::new_from_slice(key).unwrap().decrypt(nonce, payload),
+        24 => AesGcm::<Aes192, U12>::new_from_slice(key).unwrap().decrypt(nonce, payload),
+        32 => AesGcm::<Aes256, U12>::new_from_slice(key).unwrap().decrypt(nonce, payload),
+        len => panic!("invalid aes key length {}", len)
+    }.ok()
+}
+
+/// RSAES-OAEP AlgorithmIdentifier from RFC 4055 section 4.1, SHA-1 parameters are left as defaults.
+fn oaep_algorithm(hash: HashAlgorithm) -> Vec<u8> {
+    if hash == HashAlgorithm::Sha1 {
+        return der::algorithm(OID_RSAES_OAEP, Some(der::sequence(&[])));
+    }
+
+    let hash_algorithm = der::algorithm(hash_oid(hash), Some(der::null()));
+    der::algorithm(OID_RSAES_OAEP, Some(der::sequence(&[
+        der::context(0, true, &hash_algorithm),
+        der::context(1, true, &der::algorithm(OID_MGF1, Some(hash_algorithm.clone())))
+    ])))
+}
+
+fn parse_oaep_parameters(parameters: Option<&der::DerObject>) -> HashAlgorithm {
+    let mut hash = HashAlgorithm::Sha1;
+    let mut mgf_hash = HashAlgorithm::Sha1;
+    if let Some(parameters) = parameters {
+        for field in parameters.expect_tag(der::TAG_SEQUENCE).children() {
+            let algorithm = field.children()[0];
+            match field.tag {
+                0xa0 => hash = hash_from_oid(&algorithm.children()[0].as_oid()),
+                0xa1 => {
+                    let mgf = algorithm.children();
+                    if mgf[0].as_oid() != OID_MGF1 {
+                        panic!("unsupported mask generation function");
+                    }
+                    mgf_hash = hash_from_oid(&mgf[1].children()[0].as_oid());
+                },
+                0xa2 => {
+                    let source = algorithm.children();
+                    if source.len() > 1 && !source[1].content.is_empty() {
+                        panic!("unsupported oaep label");
+                    }
+                },
+                _ => {}
+            }
+        }
+    }
+
+    if hash != mgf_hash {
+        panic!("unsupported oaep parameters");
+    }
+
+    hash
+}
+
+/// Recipient of an envelope; `oaep_hash` of `None` selects RSAES-PKCS1-v1_5 key transport.
+#[derive(Debug, Clone)]
+struct Recipient {
+    certificate: Certificate,
+    oaep_hash: Option<HashAlgorithm>
+}
+
+impl Recipient {
+    /// KeyTransRecipientInfo from RFC 5652 6.2.1.
+    fn key_trans_recipient_info<R: RngCore + CryptoRng>(&self, rng: &mut R, content_key: &[u8]) -> Vec<u8> {
+        let public_key = &self.certificate.public_key;
+        let (algorithm, encrypted_key) = match self.oaep_hash {
+            Some(hash) => (oaep_algorithm(hash), padding::oaep_encrypt(rng, public_key, hash, &[], content_key)),
+            None => (
+                der::algorithm(OID_RSA_ENCRYPTION, Some(der::null())),
+                public_key.encrypt(rng, PaddingScheme::new_pkcs1v15_encrypt(), content_key).expect("encrypt error")
+            )
+        };
+
+        der::sequence(&[
+            der::small_integer(0),
+            self.certificate.issuer_and_serial_number(),
+            algorithm,
+            der::octet_string(&encrypted_key)
+        ])
+    }
+}
+
+/// EnvelopedData (AES-CBC) or AuthEnvelopedData (AES-GCM, RFC 5083) wrapped into a ContentInfo.
+fn encrypt<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    recipients: &[Recipient],
+    content_encryption: ContentEncryption,
+    content: &[u8]
+) -> Vec<u8> {
+    if recipients.is_empty() {
+        panic!("no recipients");
+    }
+
+    let mut content_key = vec![0u8; content_encryption.key_len()];
+    rng.fill_bytes(&mut content_key);
+    let recipient_infos: Vec<Vec<u8>> = recipients
+        .iter()
+        .map(|recipient| recipient.key_trans_recipient_info(rng, &content_key))
+        .collect();
+
+    let (content_type, envelope) = match content_encryption {
+        ContentEncryption::Cbc(_) => {
+            let mut iv = vec![0u8; AES_BLOCK_SIZE];
+            rng.fill_bytes(&mut iv);
+            let encrypted_content_info = der::sequence(&[
+                der::oid(OID_DATA),
+                der::algorithm(content_encryption.oid(), Some(der::octet_string(&iv))),
+                der::context(0, false, &cbc_encrypt(&content_key, &iv, content))
+            ]);
+
+            (OID_ENVELOPED_DATA, der::sequence(&[
+                der::small_integer(0),
+                der::set_of(&recipient_infos),
+                encrypted_content_info
+            ]))
+        },
+        ContentEncryption::Gcm(_) => {
+            let mut nonce = vec![0u8; GCM_NONCE_SIZE];
+            rng.fill_bytes(&mut nonce);
+            let mut ciphertext = gcm_encrypt(&content_key, &nonce, &[], content);
+            let tag = ciphertext.split_off(ciphertext.len() - GCM_TAG_SIZE);
+            let parameters = der::sequence(&[der::octet_string(&nonce), der::small_integer(GCM_TAG_SIZE as u32)]);
+            let auth_encrypted_content_info = der::sequence(&[
+                der::oid(OID_DATA),
+                der::algorithm(content_encryption.oid(), Some(parameters)),
+                der::context(0, false, &ciphertext)
+            ]);
+
+            (OID_AUTH_ENVELOPED_DATA, der::sequence(&[
+                der::small_integer(0),
+                der::set_of(&recipient_infos),
+                auth_encrypted_content_info,
+                der::octet_string(&tag)
+            ]))
+        }
+    };
+
+    der::sequence(&[der::oid(content_type), der::context(0, true, &envelope)])
+}
+
+/// `None` if the encrypted key does not decrypt, the caller must not reveal this.
+fn decrypt_content_key(key: &RSAPrivateKeyPair, recipient_info: &der::DerObject) -> Option<Vec<u8>> {
+    let fields = recipient_info.children();
+    let algorithm = fields[2].children();
+    let encrypted_key = fields[3].expect_tag(der::TAG_OCTET_STRING).content;
+    match algorithm[0].as_oid().as_str() {
+        OID_RSA_ENCRYPTION => key.decrypting_instance(Padding::Pkcs1v15Encrypt, None)
+            .decrypt(PaddingScheme::new_pkcs1v15_encrypt(), encrypted_key)
+            .ok(),
+        OID_RSAES_OAEP => {
+            let hash = parse_oaep_parameters(algorithm.get(1));
+            padding::try_oaep_decrypt(key.decrypting_instance(Padding::Oaep, Some(hash)), hash, &[], encrypted_key)
+        },
+        oid => panic!("unsupported key encryption algorithm {}", oid)
+    }
+}
+
+/// Decrypts with the key and policy of `key`. A content key that fails to decrypt is replaced
+/// by a random one (implicit rejection, RFC 3218 section 2.3), so key transport and content
+/// decryption failures end in the same "decryption error".
+pub fn decrypt(key: &RSAPrivateKeyPair, certificate: &Certificate, data: &[u8]) -> Vec<u8> {
+    let content_info = der::parse_single(data);
+    let fields = content_info.expect_tag(der::TAG_SEQUENCE).children();
+    if fields.len() != 2 {
+        panic!("invalid cms content info");
+    }
+
+    let content_type = fields[0].as_oid();
+    let authenticated = match content_type.as_str() {
+        OID_ENVELOPED_DATA => false,
+        OID_AUTH_ENVELOPED_DATA => true,
+        _ => panic!("not a cms enveloped data")
+    };
+
+    let envelope = fields[1].expect_tag(0xa0).children()[0].expect_tag(der::TAG_SEQUENCE).children();
+    let mut rest = &envelope[1..];
+    if rest[0].tag == 0xa0 {
+        rest = &rest[1..];
+    }
+
+    let recipient_info = rest[0]
+        .expect_tag(der::TAG_SET)
+        .children()
+        .into_iter()
+        .find(|recipient_info| {
+            recipient_info.tag == der::TAG_SEQUENCE && certificate.matches(&recipient_info.children()[1])
+        })
+        .expect("no matching recipient");
+    let encrypted_content_info = rest[1].expect_tag(der::TAG_SEQUENCE).children();
+    let algorithm = encrypted_content_info[1].children();
+    let content_encryption = ContentEncryption::from_oid(&algorithm[0].as_oid());
+
+    let mut random_key = vec![0u8; content_encryption.key_len()];
+    utils::os_rng().fill_bytes(&mut random_key);
+    let content_key = decrypt_content_key(key, &recipient_info)
+        .filter(|content_key| content_key.len() == content_encryption.key_len())
+        .unwrap_or(random_key);
+
+    let ciphertext = match encrypted_content_info.get(2) {
+        Some(encrypted_content) if encrypted_content.tag == 0x80 => encrypted_content.content.to_vec(),
+        Some(encrypted_content) if encrypted_content.tag == 0xa0 => encrypted_content
+            .children()
+            .iter()
+            .flat_map(|chunk| chunk.expect_tag(der::TAG_OCTET_STRING).content.to_vec())
+            .collect(),
+        _ => panic!("missing encrypted content")
+    };
+
+    let content = match (content_encryption, authenticated) {
+        (ContentEncryption::Cbc(_), false) => {
+            cbc_decrypt(&content_key, algorithm[1].expect_tag(der::TAG_OCTET_STRING).content, &ciphertext)
+        },
+        (ContentEncryption::Gcm(_), true) => {
+            let parameters = algorithm[1].children();
+            let nonce = parameters[0].expect_tag(der::TAG_OCTET_STRING).content;
+            let (aad, mac) = match &rest[2..] {
+                [auth_attributes, mac, ..] if auth_attributes.tag == 0xa1 => {
+                    (der::encode(der::TAG_SET, auth_attributes.content), mac)
+                },
+                [mac, ..] => (vec![], mac),
+                _ => panic!("missing mac")
+            };
+
+            let mut ciphertext = ciphertext;
+            ciphertext.extend_from_slice(mac.expect_tag(der::TAG_OCTET_STRING).content);
+            gcm_decrypt(&content_key, nonce, &aad, &ciphertext)
+        },
+        _ => panic!("content encryption does not match content type")
+    };
+
+    content.expect("decryption error")
+}
+
+/// Collects recipients and produces CMS EnvelopedData / AuthEnvelopedData.
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct RSAEnvelopeBuilder {
+    content_encryption: ContentEncryption,
+    recipients: Vec<Recipient>,
+    policy: RSAPolicy
+}
+
+#[wasm_bindgen]
+impl RSAEnvelopeBuilder {
+    /// `content_encryption` is one of AES-128-CBC, AES-192-CBC, AES-256-CBC, AES-128-GCM, AES-192-GCM, AES-256-GCM.
+    #[wasm_bindgen(constructor)]
+    pub fn new(content_encryption: &str) -> Self {
+        utils::set_panic_hook();
+        RSAEnvelopeBuilder {
+            content_encryption: ContentEncryption::from_name(content_encryption),
+            recipients: vec![],
+            policy: RSAPolicy::default()
+        }
+    }
+
+    /// Replaces the strict default policy, checked by every following `add_recipient`.
+    pub fn set_policy(&mut self, policy: &RSAPolicy) {
+        self.policy = policy.clone();
+    }
+
+    /// Adds a recipient certificate. RSAES-OAEP with `oaep_hash` is used when given, RSAES-PKCS1-v1_5 otherwise.
+    /// Panics unless the policy allows the certificate key with that padding and hash.
+    pub fn add_recipient(&mut self, certificate: &str, oaep_hash: Option<String>) {
+        utils::set_panic_hook();
+        let certificate = Certificate::from_pem_or_hex(certificate);
+        let oaep_hash = oaep_hash.map(|hash| HashAlgorithm::from_name(&hash));
+        let padding = if oaep_hash.is_some() { Padding::Oaep } else { Padding::Pkcs1v15Encrypt };
+        self.policy.check_operation(&certificate.public_key, padding, oaep_hash);
+        self.recipients.push(Recipient { certificate, oaep_hash });
+    }
+
+    pub fn encrypt(&self, content: &[u8], random_seed: &str) -> String {
+        utils::set_panic_hook();
+        let mut rng = utils::seeded_rng(random_seed);
+        hex::encode(encrypt(&mut rng, &self.recipients, self.content_encryption, content))
+    }
+
+    pub fn encrypt_pem(&self, content: &[u8], random_seed: &str) -> String {
+        utils::set_panic_hook();
+        let mut rng = utils::seeded_rng(random_seed);
+        der::to_pem("CMS", &encrypt(&mut rng, &self.recipients, self.content_encryption, content))
+    }
+}
+
+#[wasm_bindgen]
+impl RSAPrivateKeyPair {
+    /// Decrypts CMS EnvelopedData / AuthEnvelopedData (PEM or hex DER) for the recipient `certificate`.
+    pub fn decrypt_cms(&self, data: &str, certificate: &str) -> Vec<u8> {
+        utils::set_panic_hook();
+        let certificate = Certificate::from_pem_or_hex(certificate);
+        decrypt(self, &certificate, &der::from_pem_or_hex(data))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fixtures::legacy_private_key_pair;
+    use fixtures::CERTIFICATE;
+    use fixtures::PRIVATE_KEY;
+
+    const SEED: &str = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+
+    fn private_key_pair() -> RSAPrivateKeyPair {
+        let mut private_instance = legacy_private_key_pair();
+        private_instance.from_pkcs8_pem(PRIVATE_KEY);
+        private_instance
+    }
+
+    fn legacy_builder(content_encryption: &str) -> RSAEnvelopeBuilder {
+        let mut builder = RSAEnvelopeBuilder::new(content_encryption);
+        builder.set_policy(&RSAPolicy::legacy());
+        builder
+    }
+
+    fn round_trip(content_encryption: &str, oaep_hash: Option<&str>) {
+        let content = b"confidential document contents";
+        let mut builder = legacy_builder(content_encryption);
+        builder.add_recipient(CERTIFICATE, oaep_hash.map(String::from));
+        let envelope = builder.encrypt(content, SEED);
+        assert_eq!(private_key_pair().decrypt_cms(&envelope, CERTIFICATE), content.to_vec());
+    }
+
+    #[test]
+    fn cbc_with_pkcs1v15() {
+        round_trip("AES-128-CBC", None);
+        round_trip("AES-256-CBC", None);
+    }
+
+    #[test]
+    fn cbc_with_oaep() {
+        round_trip("AES-192-CBC", Some("SHA-1"));
+        round_trip("AES-256-CBC", Some("SHA-256"));
+    }
+
+    #[test]
+    fn gcm() {
+        round_trip("AES-128-GCM", Some("SHA-256"));
+        round_trip("AES-256-GCM", None);
+    }
+
+    #[test]
+    fn multiple_recipients() {
+        let content = b"confidential document contents";
+        let mut builder = legacy_builder("AES-256-CBC");
+        builder.add_recipient(CERTIFICATE, Some(String::from("SHA-256")));
+        builder.add_recipient(CERTIFICATE, None);
+        let envelope = builder.encrypt_pem(content, SEED);
+        assert!(envelope.starts_with("-----BEGIN CMS-----\n"));
+        assert_eq!(private_key_pair().decrypt_cms(&envelope, CERTIFICATE), content.to_vec());
+    }
+
+    #[test]
+    fn strict_policy_with_oaep() {
+        let content = b"confidential document contents";
+        let mut builder = RSAEnvelopeBuilder::new("AES-256-GCM");
+        builder.add_recipient(CERTIFICATE, Some(String::from("SHA-256")));
+        let envelope = builder.encrypt(content, SEED);
+        assert_eq!(private_key_pair().decrypt_cms(&envelope, CERTIFICATE), content.to_vec());
+    }
+
+    #[test]
+    #[should_panic(expected = "padding RSAES-PKCS1-v1_5 is not allowed by the policy")]
+    fn strict_policy_rejects_pkcs1v15() {
+        RSAEnvelopeBuilder::new("AES-256-CBC").add_recipient(CERTIFICATE, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "key size 1024 is below the policy minimum of 2048 bits")]
+    fn strict_policy_rejects_small_key() {
+        // openssl req -x509 -newkey rsa:1024 -nodes -subj "/CN=rsa-lib 1024"
+        let certificate = include_str!("testdata/certificate_rsa1024.pem");
+        RSAEnvelopeBuilder::new("AES-256-CBC").add_recipient(certificate, Some(String::from("SHA-256")));
+    }
+
+    #[test]
+    fn oaep_algorithm_parameters() {
+        let algorithm = oaep_algorithm(HashAlgorithm::Sha256);
+        let parameters = der::parse_single(&algorithm).children()[1];
+        assert_eq!(parse_oaep_parameters(Some(&parameters)), HashAlgorithm::Sha256);
+        assert_eq!(parse_oaep_parameters(None), HashAlgorithm::Sha1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn tampered_gcm_envelope() {
+        let mut builder = legacy_builder("AES-256-GCM");
+        builder.add_recipient(CERTIFICATE, None);
+        let mut envelope = hex::decode(builder.encrypt(b"confidential", SEED)).unwrap();
+        let last = envelope.len() - 1;
+        envelope[last] ^= 1;
+        private_key_pair().decrypt_cms(&hex::encode(envelope), CERTIFICATE);
+    }
+
+    #[test]
+    #[should_panic]
+    fn decrypt_with_wrong_key() {
+        let mut builder = legacy_builder("AES-128-CBC");
+        builder.add_recipient(CERTIFICATE, None);
+        let envelope = builder.encrypt(b"confidential", SEED);
+        let mut other_private_instance = legacy_private_key_pair();
+        other_private_instance.generate(1024, SEED);
+        other_private_instance.decrypt_cms(&envelope, CERTIFICATE);
+    }
+
+    fn decrypt_openssl(name: &str, envelope: &[u8]) {
+        assert_eq!(private_key_pair().decrypt_cms(&hex::encode(envelope), CERTIFICATE), b"document contents".to_vec(), "{}", name);
+    }
+
+    #[test]
+    fn decrypt_openssl_envelopes() {
+        // openssl cms -encrypt -binary -aes-256-cbc -recip CERTIFICATE -outform DER
+        decrypt_openssl("cbc_v15", include_bytes!("testdata/envelope_openssl_cbc_v15.der"));
+        // openssl cms -encrypt -binary -aes-128-gcm -recip CERTIFICATE -keyopt rsa_padding_mode:oaep
+        //   -keyopt rsa_oaep_md:sha256 -keyopt rsa_mgf1_md:sha256 -outform DER
+        decrypt_openssl("gcm_oaep", include_bytes!("testdata/envelope_openssl_gcm_oaep.der"));
+    }
+
+    /// Message of a failed `decrypt_cms`.
+    fn decrypt_error(envelope: Vec<u8>) -> String {
+        let envelope = hex::encode(envelope);
+        let error = std::panic::catch_unwind(|| private_key_pair().decrypt_cms(&envelope, CERTIFICATE)).unwrap_err();
+        error.downcast_ref::<&str>().map(|message| message.to_string())
+            .or_else(|| error.downcast_ref::<String>().cloned())
+            .unwrap()
+    }
+
+    #[test]
+    fn key_transport_and_padding_errors_are_indistinguishable() {
+        let envelope = include_bytes!("testdata/envelope_openssl_cbc_v15.der").to_vec();
+        let encrypted_key = envelope.windows(4).position(|window| window == [0x04, 0x82, 0x01, 0x00]).unwrap() + 4;
+
+        let mut bad_key_transport = envelope.clone();
+        bad_key_transport[encrypted_key + 10] ^= 1;
+        // the last byte of the second to last block flips the padding length of the last block
+        let mut bad_padding = envelope.clone();
+        let index = bad_padding.len() - AES_BLOCK_SIZE - 1;
+        bad_padding[index] ^= 1;
+
+        assert_eq!(decrypt_error(bad_key_transport), "decryption error");
+        assert_eq!(decrypt_error(bad_padding), "decryption error");
+    }
+
+    #[test]
+    #[should_panic]
+    fn encrypt_without_recipients() {
+        RSAEnvelopeBuilder::new("AES-128-CBC").encrypt(b"confidential", SEED);
+    }
+}
@@ -0,0 +1,561 @@
+use super::*;
+use drbg::HmacDrbg;
+use hashing::HashAlgorithm;
+use num_bigint_dig::RandBigInt;
+use num_bigint_dig::prime::probably_prime;
+use num_integer::Integer;
+use num_traits::{ One, ToPrimitive, Zero };
+use primitives::mod_inverse;
+use private_keys::RSAPrivateKeyPair;
+use rand::CryptoRng;
+
+/// Modulus sizes accepted in FIPS mode.
+pub const APPROVED_SIZES: [usize; 3] = [2048, 3072, 4096];
+/// Provable primes, A.1.2.
+pub const PROVABLE_PRIMES: &str = "A.1.2";
+/// Random probable primes, A.1.3.
+pub const PROBABLE_PRIMES: &str = "A.1.3";
+/// Probable primes with conditions based on auxiliary probable primes, A.1.6.
+pub const AUXILIARY_PROBABLE_PRIMES: &str = "A.1.6";
+pub const METHODS: [&str; 3] = [PROVABLE_PRIMES, PROBABLE_PRIMES, AUXILIARY_PROBABLE_PRIMES];
+
+/// Miller-Rabin rounds, followed by a Lucas test, above the FIPS 186-5 Table B.1 minimums for these sizes.
+const MILLER_RABIN_ROUNDS: usize = 20;
+/// The public exponent has to be above 2^16 (FIPS 186-5 A.1.1).
+const MIN_EXPONENT_BITS: usize = 17;
+/// Output length of SHA-256, the hash of the provable prime construction.
+const OUTLEN: usize = 256;
+const DRBG_PERSONALIZATION: &[u8] = b"rsa-lib FIPS 186-5 key generation";
+
+type Primes = Option<(BigUint, BigUint)>;
+
+fn check_parameters(nlen: usize, e: &BigUint) {
+    if !APPROVED_SIZES.contains(&nlen) {
+        panic!("key size {} is not approved, use one of {:?}", nlen, APPROVED_SIZES);
+    }
+    if e.bits() < MIN_EXPONENT_BITS || e.bits() > keygen::MAX_EXPONENT_BITS || (e % 2u32).is_zero() {
+        panic!("public exponent must be odd and between 2^16 and 2^{}", keygen::MAX_EXPONENT_BITS);
+    }
+}
+
+/// Security strength of SP 800-56B Rev. 2 Appendix D for the approved sizes.
+fn security_strength(nlen: usize) -> usize {
+    match nlen {
+        2048 => 112,
+        3072 => 128,
+        _ => 152
+    }
+}
+
+/// Length of the auxiliary primes from FIPS 186-5 Table A.1.
+fn auxiliary_prime_bits(nlen: usize) -> usize {
+    match nlen {
+        2048 => 141,
+        3072 => 171,
+        _ => 201
+    }
+}
+
+/// Random odd `bits`-bit integer, steps 4.2 and 4.3 of A.1.3.
+fn prime_candidate<R: RngCore>(rng: &mut R, bits: usize) -> BigUint {
+    let mut bytes = vec![0u8; (bits + 7) / 8];
+    rng.fill_bytes(&mut bytes);
+
+    let excess = bytes.len() * 8 - bits;
+    bytes[0] &= 0xff >> excess;
+    bytes[0] |= 0x80 >> excess;
+    *bytes.last_mut().unwrap() |= 1;
+
+    BigUint::from_bytes_be(&bytes)
+}
+
+/// `⌊√2 · 2^(bits - 1)⌋`, the smallest prime allowed is one above it.
+fn lower_bound(bits: usize) -> BigUint {
+    (BigUint::one() << (2 * bits - 1)).sqrt()
+}
+
+/// `prime >= √2 · 2^(nlen/2 - 1)`, compared as `prime² >= 2^(nlen - 1)`.
+fn above_lower_bound(prime: &BigUint, nlen: usize) -> bool {
+    prime * prime >= BigUint::one() << (nlen - 1)
+}
+
+/// `|p - q| > 2^(nlen/2 - 100)`
+fn far_enough_apart(p: &BigUint, q: &BigUint, nlen: usize) -> bool {
+    let difference = if p > q { p - q } else { q - p };
+    difference > BigUint::one() << (nlen / 2 - 100)
+}
+
+fn is_probable_prime(candidate: &BigUint, e: &BigUint) -> bool {
+    (candidate - BigUint::one()).gcd(e).is_one() && probably_prime(candidate, MILLER_RABIN_ROUNDS)
+}
+
+/// Steps 4 (without `p`) and 5 (with `p`) of A.1.3. `None` is the FAILURE after `5 · nlen/2` candidates.
+fn generate_prime<R: RngCore>(rng: &mut R, nlen: usize, e: &BigUint, p: Option<&BigUint>, candidates_tested: &mut u64) -> Option<BigUint> {
+    for _ in 0..5 * nlen / 2 {
+        *candidates_tested += 1;
+        let candidate = prime_candidate(rng, nlen / 2);
+        if !above_lower_bound(&candidate, nlen) {
+            continue;
+        }
+        if matches!(p, Some(p) if !far_enough_apart(p, &candidate, nlen)) {
+            continue;
+        }
+        if is_probable_prime(&candidate, e) {
+            return Some(candidate);
+        }
+    }
+
+    None
+}
+
+/// Steps 4 and 5 of A.1.3.
+fn probable_primes<R: RngCore>(rng: &mut R, nlen: usize, e: &BigUint, candidates_tested: &mut u64) -> Primes {
+    let p = generate_prime(rng, nlen, e, None, candidates_tested)?;
+    let q = generate_prime(rng, nlen, e, Some(&p), candidates_tested)?;
+    Some((p, q))
+}
+
+/// Seed of the provable prime construction, an integer hashed as a `len`-byte string.
+struct Seed {
+    value: BigUint,
+    len: usize
+}
+
+impl Seed {
+    fn hash(&self, offset: usize) -> BigUint {
+        let bytes = ((&self.value + offset) % (BigUint::one() << (8 * self.len))).to_bytes_be();
+        let mut padded = vec![0u8; self.len.saturating_sub(bytes.len())];
+        padded.extend_from_slice(&bytes);
+        BigUint::from_bytes_be(&HashAlgorithm::Sha256.digest(&padded))
+    }
+
+    /// `Σ Hash(seed + i) · 2^(i · outlen)` for `i` up to `iterations`, then moves the seed past them.
+    fn take(&mut self, iterations: usize) -> BigUint {
+        let mut value = BigUint::zero();
+        for i in 0..=iterations {
+            value += self.hash(i) << (i * OUTLEN);
+        }
+        self.value += iterations + 1;
+        value
+    }
+}
+
+/// Trial division for the candidates below 2^32 of the Shawe-Taylor construction.
+fn is_small_prime(candidate: u64) -> bool {
+    candidate >= 2 && (2..).take_while(|divisor| divisor * divisor <= candidate).all(|divisor| candidate % divisor != 0)
+}
+
+/// ST_Random_Prime from C.6, with `prime_gen_counter` in `counter`. `None` is the FAILURE.
+fn st_random_prime(length: usize, seed: &mut Seed, counter: &mut usize, candidates_tested: &mut u64) -> Option<BigUint> {
+    if length < 33 {
+        *counter = 0;
+        loop {
+            let c = seed.hash(0) ^ seed.hash(1);
+            let c = ((BigUint::one() << (length - 1)) + c % (BigUint::one() << (length - 1))) | BigUint::one();
+            *counter += 1;
+            *candidates_tested += 1;
+            seed.value += 2u32;
+            if is_small_prime(c.to_u64().unwrap()) {
+                return Some(c);
+            }
+            if *counter > 4 * length {
+                return None;
+            }
+        }
+    }
+
+    let c0 = st_random_prime((length + 1) / 2 + 1, seed, counter, candidates_tested)?;
+    let iterations = (length + OUTLEN - 1) / OUTLEN - 1;
+    let old_counter = *counter;
+    let top = BigUint::one() << (length - 1);
+    let x = &top + seed.take(iterations) % &top;
+    let step = &c0 << 1;
+    let mut t = (&x + &step - 1u32) / &step;
+    loop {
+        if &t * &step + 1u32 > BigUint::one() << length {
+            t = (&top + &step - 1u32) / &step;
+        }
+        let c = &t * &step + 1u32;
+        *counter += 1;
+        *candidates_tested += 1;
+
+        let a = BigUint::from(2u32) + seed.take(iterations) % (&c - 3u32);
+        let z = a.modpow(&(&t << 1), &c);
+        if !z.is_zero() && (&z - 1u32).gcd(&c).is_one() && z.modpow(&c0, &c).is_one() {
+            return Some(c);
+        }
+        if *counter >= 4 * length + old_counter {
+            return None;
+        }
+        t += 1u32;
+    }
+}
+
+/// C.10 with `N1 = N2 = 1`, so `p1 = p2 = y = 1`. Returns `p` with the prime `p0` dividing `p - 1`.
+fn provable_prime(length: usize, seed: &mut Seed, e: &BigUint, candidates_tested: &mut u64) -> Primes {
+    let p0 = st_random_prime((length + 1) / 2 + 1, seed, &mut 0, candidates_tested)?;
+    let iterations = (length + OUTLEN - 1) / OUTLEN - 1;
+    let bound = lower_bound(length);
+    let x = &bound + seed.take(iterations) % ((BigUint::one() << length) - &bound);
+    let step = &p0 << 1;
+    let mut t = (&step + &x + &step - 1u32) / &step;
+    for _ in 0..5 * length {
+        if (&t - 1u32) * &step + 1u32 > BigUint::one() << length {
+            t = (&step + &bound + &step - 1u32) / &step;
+        }
+        let p = (&t - 1u32) * &step + 1u32;
+        *candidates_tested += 1;
+
+        if (&p - 1u32).gcd(e).is_one() {
+            let a = BigUint::from(2u32) + seed.take(iterations) % (&p - 3u32);
+            let z = a.modpow(&((&t - 1u32) << 1), &p);
+            if !z.is_zero() && (&z - 1u32).gcd(&p).is_one() && z.modpow(&p0, &p).is_one() {
+                return Some((p, p0));
+            }
+        }
+        t += 1u32;
+    }
+
+    None
+}
+
+/// Steps 4 to 10 of A.1.2, from a seed of twice the security strength.
+fn provable_primes<R: RngCore>(rng: &mut R, nlen: usize, e: &BigUint, candidates_tested: &mut u64) -> Primes {
+    let mut bytes = vec![0u8; 2 * security_strength(nlen) / 8];
+    rng.fill_bytes(&mut bytes);
+    let mut seed = Seed { value: BigUint::from_bytes_be(&bytes), len: bytes.len() };
+
+    let (p, _) = provable_prime(nlen / 2, &mut seed, e, candidates_tested)?;
+    loop {
+        let (q, _) = provable_prime(nlen / 2, &mut seed, e, candidates_tested)?;
+        if far_enough_apart(&p, &q, nlen) {
+            return Some((p, q));
+        }
+    }
+}
+
+/// First probable prime from `x` on, `x` odd, step 4.2 of A.1.6.
+fn next_probable_prime(mut x: BigUint, candidates_tested: &mut u64) -> BigUint {
+    loop {
+        *candidates_tested += 1;
+        if probably_prime(&x, MILLER_RABIN_ROUNDS) {
+            return x;
+        }
+        x += 2u32;
+    }
+}
+
+/// First probable prime from a random odd `bits`-bit integer, steps 4.1 and 4.2 of A.1.6.
+fn auxiliary_prime<R: RngCore>(rng: &mut R, bits: usize, candidates_tested: &mut u64) -> BigUint {
+    next_probable_prime(prime_candidate(rng, bits), candidates_tested)
+}
+
+/// Steps 6 to 11 of C.9 for a given `X`, a probable prime `Y` with `Y ≡ 1 mod 2r1` and
+/// `Y ≡ -1 mod r2`. `None` if `Y` outgrows `bits` or no prime follows within `20 · bits` steps.
+fn derive_prime_from(x: &BigUint, bits: usize, e: &BigUint, r1: &BigUint, r2: &BigUint, candidates_tested: &mut u64) -> Option<BigUint> {
+    let double_r1 = r1 << 1;
+    let modulus = &double_r1 * r2;
+    let r = (mod_inverse(r2, &double_r1)? * r2 + &modulus - mod_inverse(&double_r1, r2)? * &double_r1) % &modulus;
+
+    let mut y = x + (&r + &modulus - x % &modulus) % &modulus;
+    for _ in 0..20 * bits {
+        if y.bits() > bits {
+            return None;
+        }
+        *candidates_tested += 1;
+        if is_probable_prime(&y, e) {
+            return Some(y);
+        }
+        y += &modulus;
+    }
+
+    None
+}
+
+/// C.9 with a random `X`, returned with the prime it leads to. `None` after `5 · nlen/2` values of `X`.
+fn derive_prime<R: RngCore>(rng: &mut R, nlen: usize, e: &BigUint, r1: &BigUint, r2: &BigUint, candidates_tested: &mut u64) -> Primes {
+    let bits = nlen / 2;
+    let base = lower_bound(bits) + 1u32;
+    let range = (BigUint::one() << bits) - &base;
+    for _ in 0..5 * bits {
+        let x = &base + rng.gen_biguint_below(&range);
+        if let Some(y) = derive_prime_from(&x, bits, e, r1, r2, candidates_tested) {
+            return Some((y, x));
+        }
+    }
+
+    None
+}
+
+/// Steps 4 to 6 of A.1.6, `|Xp - Xq|` and `|p - q|` both have to be above `2^(nlen/2 - 100)`.
+fn auxiliary_probable_primes<R: RngCore>(rng: &mut R, nlen: usize, e: &BigUint, candidates_tested: &mut u64) -> Primes {
+    let bits = auxiliary_prime_bits(nlen);
+    let (p1, p2) = (auxiliary_prime(rng, bits, candidates_tested), auxiliary_prime(rng, bits, candidates_tested));
+    let (p, xp) = derive_prime(rng, nlen, e, &p1, &p2, candidates_tested)?;
+    loop {
+        let (q1, q2) = (auxiliary_prime(rng, bits, candidates_tested), auxiliary_prime(rng, bits, candidates_tested));
+        let (q, xq) = derive_prime(rng, nlen, e, &q1, &q2, candidates_tested)?;
+        if far_enough_apart(&xp, &xq, nlen) && far_enough_apart(&p, &q, nlen) {
+            return Some((p, q));
+        }
+    }
+}
+
+fn lambda(p: &BigUint, q: &BigUint) -> BigUint {
+    (p - BigUint::one()).lcm(&(q - BigUint::one()))
+}
+
+/// Checks `p` and `q` against the size, distance and primality conditions of A.1.3 and
+/// `d = e^-1 mod λ(n) > 2^(nlen/2)` from A.1.1, primes from any of the `METHODS` pass.
+pub fn check_primes(nlen: usize, e: &BigUint, p: &BigUint, q: &BigUint) -> bool {
+    let sizes_match = p.bits() == nlen / 2 && q.bits() == nlen / 2;
+    if !sizes_match || !above_lower_bound(p, nlen) || !above_lower_bound(q, nlen) || !far_enough_apart(p, q, nlen) {
+        return false;
+    }
+    if !is_probable_prime(p, e) || !is_probable_prime(q, e) {
+        return false;
+    }
+
+    matches!(mod_inverse(e, &lambda(p, q)), Some(d) if d > BigUint::one() << (nlen / 2))
+}
+
+/// Key pair per FIPS 186-5 A.1 with the primes of `method`, with the count of prime candidates tested.
+/// `rng` should be an approved DRBG, as the one `generate_fips` uses.
+pub fn generate<R: RngCore + CryptoRng>(rng: &mut R, nlen: usize, e: BigUint, method: &str) -> (RsaPrivateKey, u64) {
+    check_parameters(nlen, &e);
+    let generate_primes: fn(&mut R, usize, &BigUint, &mut u64) -> Primes = match method {
+        PROVABLE_PRIMES => provable_primes,
+        PROBABLE_PRIMES => probable_primes,
+        AUXILIARY_PROBABLE_PRIMES => auxiliary_probable_primes,
+        _ => panic!("unsupported key generation method {}, use one of {:?}", method, METHODS)
+    };
+
+    let mut candidates_tested = 0;
+    loop {
+        let (p, q) = match generate_primes(rng, nlen, &e, &mut candidates_tested) {
+            Some(primes) => primes,
+            None => continue
+        };
+
+        let d = mod_inverse(&e, &lambda(&p, &q)).expect("public exponent is not invertible");
+        if d <= BigUint::one() << (nlen / 2) {
+            continue;
+        }
+
+        let mut key = RsaPrivateKey::from_components(&p * &q, e, d, vec![p, q]);
+        key.precompute().expect("invalid private key");
+        return (key, candidates_tested);
+    }
+}
+
+#[wasm_bindgen]
+impl RSAPrivateKeyPair {
+    /// Generates a key following FIPS 186-5 A.1 with e = 65537, for the approved sizes only.
+    /// `method` is one of `METHODS` and the primes come from an HMAC_DRBG (SP 800-90A) seeded
+    /// from `random_seed`. Returns JSON describing how the key was generated.
+    pub fn generate_fips(&mut self, bits: usize, method: &str, random_seed: &str) -> String {
+        utils::set_panic_hook();
+        self.policy().check_key_size(bits);
+        let mut rng = HmacDrbg::from_seed(random_seed, DRBG_PERSONALIZATION);
+        let (key, candidates_tested) = generate(&mut rng, bits, BigUint::from(keygen::DEFAULT_EXPONENT), method);
+        self.set_instance(key);
+
+        let primes = match method {
+            PROVABLE_PRIMES => "provable primes",
+            PROBABLE_PRIMES => "random probable primes",
+            _ => "probable primes based on auxiliary probable primes"
+        };
+        let entropy_source = if random_seed == utils::OS_RANDOM { "platform CSPRNG" } else { "caller seed" };
+
+        serde_json::json!({
+            "standard": "FIPS 186-5",
+            "method": method,
+            "primes": primes,
+            "randomBitGenerator": "HMAC_DRBG with SHA-256 (SP 800-90A)",
+            "entropySource": entropy_source,
+            "bits": bits,
+            "publicExponent": self.get_e(),
+            "privateExponent": "e^-1 mod lcm(p - 1, q - 1)",
+            "millerRabinRounds": MILLER_RABIN_ROUNDS,
+            "candidatesTested": candidates_tested
+        }).to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fixtures::legacy_private_key_pair;
+    use num_traits::Num;
+    use serde_json::Value;
+
+    const SEED: &str = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+
+    fn next_prime(mut value: BigUint) -> BigUint {
+        value |= BigUint::one();
+        while !probably_prime(&value, MILLER_RABIN_ROUNDS) {
+            value += 2u32;
+        }
+        value
+    }
+
+    fn rng() -> HmacDrbg {
+        HmacDrbg::from_seed(SEED, DRBG_PERSONALIZATION)
+    }
+
+    fn e() -> BigUint {
+        BigUint::from(keygen::DEFAULT_EXPONENT)
+    }
+
+    #[test]
+    fn generate_fips_key() {
+        let mut private_instance = RSAPrivateKeyPair::new();
+        let report: Value = serde_json::from_str(&private_instance.generate_fips(2048, PROBABLE_PRIMES, SEED)).unwrap();
+        assert_eq!(report["method"], "A.1.3");
+        assert_eq!(report["randomBitGenerator"], "HMAC_DRBG with SHA-256 (SP 800-90A)");
+        assert_eq!(report["entropySource"], "caller seed");
+        assert_eq!(report["bits"], 2048);
+        assert_eq!(report["publicExponent"], "10001");
+        assert!(report["candidatesTested"].as_u64().unwrap() >= 2);
+
+        let key = private_instance.instance();
+        let (p, q) = (&key.primes()[0], &key.primes()[1]);
+        assert_eq!(key.n().bits(), 2048);
+        assert!(key.validate().is_ok());
+        assert!(check_primes(2048, key.e(), p, q));
+        assert!(key.d() < &lambda(p, q));
+        assert!(key.d() > &(BigUint::one() << 1024));
+
+        let mut other_instance = RSAPrivateKeyPair::new();
+        other_instance.generate_fips(2048, PROBABLE_PRIMES, SEED);
+        assert_eq!(other_instance.get_n(), private_instance.get_n());
+    }
+
+    #[test]
+    fn generate_with_every_method() {
+        for method in METHODS.iter() {
+            let mut private_instance = RSAPrivateKeyPair::new();
+            let report: Value = serde_json::from_str(&private_instance.generate_fips(2048, method, utils::OS_RANDOM)).unwrap();
+            assert_eq!(report["method"], *method);
+            assert_eq!(report["entropySource"], "platform CSPRNG");
+
+            let key = private_instance.instance();
+            assert!(key.validate().is_ok());
+            assert!(check_primes(2048, key.e(), &key.primes()[0], &key.primes()[1]));
+        }
+    }
+
+    #[test]
+    fn provable_prime_has_pocklington_certificate() {
+        let mut seed = Seed { value: BigUint::from_bytes_be(&hex::decode(SEED).unwrap()[..28]), len: 28 };
+        let (p, p0) = provable_prime(1024, &mut seed, &e(), &mut 0).unwrap();
+        assert_eq!(p.bits(), 1024);
+        assert!(p > lower_bound(1024));
+        assert!(probably_prime(&p0, MILLER_RABIN_ROUNDS));
+        assert!((&p - 1u32).is_multiple_of(&p0));
+        assert!(&p0 * &p0 > p);
+
+        let mut same_seed = Seed { value: BigUint::from_bytes_be(&hex::decode(SEED).unwrap()[..28]), len: 28 };
+        assert_eq!(provable_prime(1024, &mut same_seed, &e(), &mut 0).unwrap().0, p);
+        assert_eq!(same_seed.value, seed.value);
+    }
+
+    #[test]
+    fn small_shawe_taylor_primes() {
+        let mut seed = Seed { value: BigUint::from(12345u32), len: 28 };
+        for length in [2, 8, 17, 32].iter() {
+            let prime = st_random_prime(*length, &mut seed, &mut 0, &mut 0).unwrap();
+            assert_eq!(prime.bits(), *length);
+            assert!(probably_prime(&prime, MILLER_RABIN_ROUNDS));
+        }
+        assert!(is_small_prime(4294967291));
+        assert!(!is_small_prime(4294967297));
+        assert!(!is_small_prime(1));
+    }
+
+    #[test]
+    fn derived_prime_is_built_on_auxiliary_primes() {
+        let mut rng = rng();
+        let (r1, r2) = (auxiliary_prime(&mut rng, 141, &mut 0), auxiliary_prime(&mut rng, 141, &mut 0));
+        assert_eq!(r1.bits(), 141);
+        let (y, x) = derive_prime(&mut rng, 2048, &e(), &r1, &r2, &mut 0).unwrap();
+        assert!(x > lower_bound(1024) && x.bits() == 1024);
+        assert!(y >= x && y.bits() == 1024);
+        assert!((&y - 1u32).is_multiple_of(&(&r1 << 1)));
+        assert!((&y + 1u32).is_multiple_of(&r2));
+        assert!(is_probable_prime(&y, &e()));
+    }
+
+    fn field(vector: &Value, name: &str) -> BigUint {
+        BigUint::from_str_radix(vector[name].as_str().unwrap(), 16).unwrap()
+    }
+
+    /// CAVS RSA KeyGen, probable primes based on auxiliary probable primes (FIPS 186-4 B.3.6,
+    /// A.1.6 in 186-5), with the Xp1, Xp2, Xp, Xq1, Xq2 and Xq of the test case as inputs.
+    /// The vector is `rsa_keygen0` of OpenSSL test/acvp_test.inc, taken from the RSA2 CAVS data.
+    #[test]
+    fn nist_auxiliary_probable_primes_vectors() {
+        let vectors: Value = serde_json::from_str(include_str!("testdata/fips_186_keygen.json")).unwrap();
+        for vector in vectors.as_array().unwrap() {
+            let nlen = vector["bits"].as_u64().unwrap() as usize;
+            let e = field(vector, "e");
+            let aux = |name: &str| next_probable_prime(field(vector, name), &mut 0);
+            let (p1, p2, q1, q2) = (aux("xp1"), aux("xp2"), aux("xq1"), aux("xq2"));
+            assert_eq!(p1, field(vector, "p1"));
+            assert_eq!(p2, field(vector, "p2"));
+            assert_eq!(q1, field(vector, "q1"));
+            assert_eq!(q2, field(vector, "q2"));
+
+            let p = derive_prime_from(&field(vector, "xp"), nlen / 2, &e, &p1, &p2, &mut 0).unwrap();
+            let q = derive_prime_from(&field(vector, "xq"), nlen / 2, &e, &q1, &q2, &mut 0).unwrap();
+            assert_eq!(p, field(vector, "p"));
+            assert_eq!(q, field(vector, "q"));
+            assert_eq!(&p * &q, field(vector, "n"));
+            assert_eq!(mod_inverse(&e, &lambda(&p, &q)).unwrap(), field(vector, "d"));
+            assert!(check_primes(nlen, &e, &p, &q));
+        }
+    }
+
+    #[test]
+    fn check_primes_rejects_close_and_small_primes() {
+        let (key, _) = generate(&mut rng(), 2048, e(), PROBABLE_PRIMES);
+        let (p, q) = (&key.primes()[0], &key.primes()[1]);
+        let e = key.e();
+        assert!(check_primes(2048, e, p, q));
+        assert!(!check_primes(2048, e, p, p));
+        assert!(!check_primes(3072, e, p, q));
+
+        let close = next_prime(p + (BigUint::one() << 900));
+        assert!(!check_primes(2048, e, p, &close));
+
+        let small = next_prime(BigUint::one() << 1023);
+        assert!(!check_primes(2048, e, p, &small));
+    }
+
+    #[test]
+    fn lower_bounds() {
+        let bound = BigUint::from(181u32);
+        assert_eq!(lower_bound(8), bound);
+        assert!(!above_lower_bound(&bound, 16));
+        assert!(above_lower_bound(&(bound + 1u32), 16));
+    }
+
+    #[test]
+    #[should_panic(expected = "key size 1024 is not approved")]
+    fn generate_small_key() {
+        let mut private_instance = legacy_private_key_pair();
+        private_instance.generate_fips(1024, PROBABLE_PRIMES, SEED);
+    }
+
+    #[test]
+    #[should_panic(expected = "public exponent must be odd and between 2^16")]
+    fn generate_with_small_exponent() {
+        generate(&mut rng(), 2048, BigUint::from(3u32), PROBABLE_PRIMES);
+    }
+
+    #[test]
+    #[should_panic(expected = "unsupported key generation method A.1.4")]
+    fn generate_with_unsupported_method() {
+        generate(&mut rng(), 2048, e(), "A.1.4");
+    }
+}
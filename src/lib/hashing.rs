@@ -0,0 +1,153 @@
+use rsa::hash::Hash;
+use sha1::{ Sha1 };
+use sha2::{ Digest, Sha256, Sha384, Sha512 };
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HashAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512
+}
+
+impl HashAlgorithm {
+    pub fn from_name(name: &str) -> HashAlgorithm {
+        match name.to_uppercase().replace("-", "").as_str() {
+            "SHA1" => HashAlgorithm::Sha1,
+            "SHA256" => HashAlgorithm::Sha256,
+            "SHA384" => HashAlgorithm::Sha384,
+            "SHA512" => HashAlgorithm::Sha512,
+            _ => panic!("unsupported hash algorithm {}", name)
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha1 => "SHA-1",
+            HashAlgorithm::Sha256 => "SHA-256",
+            HashAlgorithm::Sha384 => "SHA-384",
+            HashAlgorithm::Sha512 => "SHA-512"
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            HashAlgorithm::Sha1 => 20,
+            HashAlgorithm::Sha256 => 32,
+            HashAlgorithm::Sha384 => 48,
+            HashAlgorithm::Sha512 => 64
+        }
+    }
+
+    pub fn pkcs1v15_hash(&self) -> Hash {
+        match self {
+            HashAlgorithm::Sha1 => Hash::SHA1,
+            HashAlgorithm::Sha256 => Hash::SHA2_256,
+            HashAlgorithm::Sha384 => Hash::SHA2_384,
+            HashAlgorithm::Sha512 => Hash::SHA2_512
+        }
+    }
+
+    pub fn hasher(&self) -> Hasher {
+        match self {
+            HashAlgorithm::Sha1 => Hasher::Sha1(Sha1::new()),
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            HashAlgorithm::Sha384 => Hasher::Sha384(Sha384::new()),
+            HashAlgorithm::Sha512 => Hasher::Sha512(Sha512::new())
+        }
+    }
+
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha1 => Sha1::digest(data).to_vec(),
+            HashAlgorithm::Sha256 => Sha256::digest(data).to_vec(),
+            HashAlgorithm::Sha384 => Sha384::digest(data).to_vec(),
+            HashAlgorithm::Sha512 => Sha512::digest(data).to_vec()
+        }
+    }
+
+    pub fn digest_parts(&self, parts: &[&[u8]]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for part in parts {
+            data.extend_from_slice(part);
+        }
+
+        self.digest(&data)
+    }
+
+    /// MGF1 mask generation function from RFC 8017 B.2.1.
+    pub fn mgf1(&self, seed: &[u8], mask_len: usize) -> Vec<u8> {
+        let mut mask = Vec::with_capacity(mask_len + self.size());
+        let mut counter: u32 = 0;
+        while mask.len() < mask_len {
+            mask.extend(self.digest_parts(&[seed, &counter.to_be_bytes()]));
+            counter += 1;
+        }
+
+        mask.truncate(mask_len);
+        mask
+    }
+}
+
+/// Incremental hash state for one of the supported algorithms.
+#[derive(Debug, Clone)]
+pub enum Hasher {
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha384(Sha384),
+    Sha512(Sha512)
+}
+
+impl Hasher {
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sha1(hasher) => hasher.update(data),
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Sha384(hasher) => hasher.update(data),
+            Hasher::Sha512(hasher) => hasher.update(data)
+        }
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        match self {
+            Hasher::Sha1(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha256(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha384(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha512(hasher) => hasher.finalize().to_vec()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_webcrypto_names() {
+        assert_eq!(HashAlgorithm::from_name("SHA-1"), HashAlgorithm::Sha1);
+        assert_eq!(HashAlgorithm::from_name("SHA-256"), HashAlgorithm::Sha256);
+        assert_eq!(HashAlgorithm::from_name("sha384"), HashAlgorithm::Sha384);
+        assert_eq!(HashAlgorithm::from_name("SHA-512"), HashAlgorithm::Sha512);
+    }
+
+    #[test]
+    #[should_panic]
+    fn parse_unknown_name() {
+        HashAlgorithm::from_name("MD5");
+    }
+
+    #[test]
+    fn incremental_hash_matches_digest() {
+        let mut hasher = HashAlgorithm::Sha512.hasher();
+        hasher.update(b"hello ");
+        hasher.update(b"world");
+        assert_eq!(hasher.finalize(), HashAlgorithm::Sha512.digest(b"hello world"));
+    }
+
+    #[test]
+    fn mgf1_output_length() {
+        let mask = HashAlgorithm::Sha256.mgf1(b"seed", 100);
+        assert_eq!(mask.len(), 100);
+        assert_eq!(&mask[..32], HashAlgorithm::Sha256.digest(b"seed\x00\x00\x00\x00").as_slice());
+    }
+}
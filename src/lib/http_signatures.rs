@@ -0,0 +1,731 @@
+use super::*;
+use rsa::PublicKey;
+use serde_json::Value;
+use hashing::HashAlgorithm;
+use public_keys::RSAPublicKeyPair;
+use private_keys::RSAPrivateKeyPair;
+use policy::Padding;
+
+/// Bare item of a structured field value from RFC 8941 section 3.3.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BareItem {
+    Integer(i64),
+    String(String),
+    Token(String),
+    ByteSequence(Vec<u8>),
+    Boolean(bool)
+}
+
+pub type Parameters = Vec<(String, BareItem)>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub value: BareItem,
+    pub parameters: Parameters
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Member {
+    Item(Item),
+    InnerList(Vec<Item>, Parameters)
+}
+
+fn serialize_bare_item(value: &BareItem) -> String {
+    match value {
+        BareItem::Integer(value) => value.to_string(),
+        BareItem::String(value) => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+        BareItem::Token(value) => value.clone(),
+        BareItem::ByteSequence(value) => format!(":{}:", base64::encode(value)),
+        BareItem::Boolean(value) => if *value { "?1".to_string() } else { "?0".to_string() }
+    }
+}
+
+fn serialize_parameters(parameters: &[(String, BareItem)]) -> String {
+    parameters.iter().map(|(key, value)| match value {
+        BareItem::Boolean(true) => format!(";{}", key),
+        _ => format!(";{}={}", key, serialize_bare_item(value))
+    }).collect()
+}
+
+pub fn serialize_item(item: &Item) -> String {
+    format!("{}{}", serialize_bare_item(&item.value), serialize_parameters(&item.parameters))
+}
+
+pub fn serialize_inner_list(items: &[Item], parameters: &[(String, BareItem)]) -> String {
+    let items: Vec<String> = items.iter().map(serialize_item).collect();
+    format!("({}){}", items.join(" "), serialize_parameters(parameters))
+}
+
+/// Parser for the structured field dictionaries used by Signature-Input and Signature.
+/// Every method returns `None` on malformed input.
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Parser<'a> {
+        Parser { input: input.as_bytes(), position: 0 }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).cloned()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn expect(&mut self, expected: u8) -> Option<()> {
+        if self.next()? != expected {
+            return None;
+        }
+
+        Some(())
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek() == Some(b' ') {
+            self.position += 1;
+        }
+    }
+
+    fn skip_ows(&mut self) {
+        while self.peek() == Some(b' ') || self.peek() == Some(b'\t') {
+            self.position += 1;
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(u8) -> bool) -> String {
+        let start = self.position;
+        while matches!(self.peek(), Some(byte) if predicate(byte)) {
+            self.position += 1;
+        }
+
+        String::from_utf8_lossy(&self.input[start..self.position]).into_owned()
+    }
+
+    fn key(&mut self) -> Option<String> {
+        match self.peek() {
+            Some(byte) if byte.is_ascii_lowercase() || byte == b'*' => {},
+            _ => return None
+        }
+
+        Some(self.take_while(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || b"_-.*".contains(&byte)))
+    }
+
+    fn bare_item(&mut self) -> Option<BareItem> {
+        match self.peek() {
+            Some(b'"') => {
+                self.position += 1;
+                let mut value = String::new();
+                loop {
+                    match self.next()? {
+                        b'\\' => match self.next()? {
+                            byte @ (b'\\' | b'"') => value.push(byte as char),
+                            _ => return None
+                        },
+                        b'"' => return Some(BareItem::String(value)),
+                        byte if (0x20..0x7f).contains(&byte) => value.push(byte as char),
+                        _ => return None
+                    }
+                }
+            },
+            Some(b':') => {
+                self.position += 1;
+                let encoded = self.take_while(|byte| byte != b':');
+                self.expect(b':')?;
+                base64::decode(&encoded).ok().map(BareItem::ByteSequence)
+            },
+            Some(b'?') => {
+                self.position += 1;
+                match self.next()? {
+                    b'1' => Some(BareItem::Boolean(true)),
+                    b'0' => Some(BareItem::Boolean(false)),
+                    _ => None
+                }
+            },
+            Some(byte) if byte == b'-' || byte.is_ascii_digit() => {
+                let start = self.position;
+                self.position += 1;
+                self.take_while(|byte| byte.is_ascii_digit());
+                let value = std::str::from_utf8(&self.input[start..self.position]).ok()?;
+                value.parse().ok().map(BareItem::Integer)
+            },
+            Some(byte) if byte.is_ascii_alphabetic() || byte == b'*' => {
+                Some(BareItem::Token(self.take_while(|byte| byte.is_ascii_graphic() && !b"\"(),;=[]{}".contains(&byte))))
+            },
+            _ => None
+        }
+    }
+
+    fn parameters(&mut self) -> Option<Parameters> {
+        let mut parameters = vec![];
+        while self.peek() == Some(b';') {
+            self.position += 1;
+            self.skip_spaces();
+            let key = self.key()?;
+            let value = if self.peek() == Some(b'=') {
+                self.position += 1;
+                self.bare_item()?
+            } else {
+                BareItem::Boolean(true)
+            };
+            parameters.push((key, value));
+        }
+
+        Some(parameters)
+    }
+
+    fn item(&mut self) -> Option<Item> {
+        let value = self.bare_item()?;
+        Some(Item { value, parameters: self.parameters()? })
+    }
+
+    fn member(&mut self) -> Option<Member> {
+        if self.peek() != Some(b'(') {
+            return self.item().map(Member::Item);
+        }
+
+        self.position += 1;
+        let mut items = vec![];
+        loop {
+            self.skip_spaces();
+            if self.peek() == Some(b')') {
+                self.position += 1;
+                return Some(Member::InnerList(items, self.parameters()?));
+            }
+
+            items.push(self.item()?);
+            match self.peek() {
+                Some(b' ') | Some(b')') => {},
+                _ => return None
+            }
+        }
+    }
+
+    fn dictionary(&mut self) -> Option<Vec<(String, Member)>> {
+        let mut members = vec![];
+        self.skip_spaces();
+        while self.peek().is_some() {
+            let key = self.key()?;
+            let member = if self.peek() == Some(b'=') {
+                self.position += 1;
+                self.member()?
+            } else {
+                let parameters = self.parameters()?;
+                Member::Item(Item { value: BareItem::Boolean(true), parameters })
+            };
+            members.retain(|(existing, _)| *existing != key);
+            members.push((key, member));
+
+            self.skip_ows();
+            if self.peek().is_none() {
+                break;
+            }
+            self.expect(b',')?;
+            self.skip_ows();
+            self.peek()?;
+        }
+
+        Some(members)
+    }
+}
+
+/// Structured field dictionary from RFC 8941 section 3.2, `None` if `input` is malformed.
+pub fn parse_dictionary(input: &str) -> Option<Vec<(String, Member)>> {
+    Parser::new(input.trim()).dictionary()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SignatureAlgorithm {
+    RsaV15Sha256,
+    RsaPssSha512
+}
+
+impl SignatureAlgorithm {
+    pub fn from_name(name: &str) -> SignatureAlgorithm {
+        match name {
+            "rsa-v1_5-sha256" => SignatureAlgorithm::RsaV15Sha256,
+            "rsa-pss-sha512" => SignatureAlgorithm::RsaPssSha512,
+            _ => panic!("unsupported signature algorithm {}", name)
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SignatureAlgorithm::RsaV15Sha256 => "rsa-v1_5-sha256",
+            SignatureAlgorithm::RsaPssSha512 => "rsa-pss-sha512"
+        }
+    }
+
+    fn padding(&self) -> Padding {
+        match self {
+            SignatureAlgorithm::RsaV15Sha256 => Padding::Pkcs1v15Sign,
+            SignatureAlgorithm::RsaPssSha512 => Padding::Pss
+        }
+    }
+
+    fn hash(&self) -> HashAlgorithm {
+        match self {
+            SignatureAlgorithm::RsaV15Sha256 => HashAlgorithm::Sha256,
+            SignatureAlgorithm::RsaPssSha512 => HashAlgorithm::Sha512
+        }
+    }
+
+    fn sign<R: RngCore>(&self, rng: &mut R, key: &RsaPrivateKey, data: &[u8]) -> Vec<u8> {
+        match self {
+            SignatureAlgorithm::RsaV15Sha256 => key.sign(
+                PaddingScheme::new_pkcs1v15_sign(Some(Hash::SHA2_256)),
+                &HashAlgorithm::Sha256.digest(data)
+            ).expect("sign error"),
+            SignatureAlgorithm::RsaPssSha512 => {
+                let hash = HashAlgorithm::Sha512;
+                let mut salt = vec![0u8; hash.size()];
+                rng.fill_bytes(&mut salt);
+                padding::pss_sign(key, hash, &hash.digest(data), &salt)
+            }
+        }
+    }
+
+    fn verify(&self, key: &RsaPublicKey, data: &[u8], signature: &[u8]) -> bool {
+        match self {
+            SignatureAlgorithm::RsaV15Sha256 => key.verify(
+                PaddingScheme::new_pkcs1v15_sign(Some(Hash::SHA2_256)),
+                &HashAlgorithm::Sha256.digest(data),
+                signature
+            ).is_ok(),
+            SignatureAlgorithm::RsaPssSha512 => {
+                let hash = HashAlgorithm::Sha512;
+                padding::pss_verify(key, hash, &hash.digest(data), signature, Some(hash.size()))
+            }
+        }
+    }
+}
+
+/// Request components available to HTTP Message Signatures.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct HttpMessage {
+    method: String,
+    target_uri: String,
+    headers: Vec<(String, String)>
+}
+
+#[wasm_bindgen]
+impl HttpMessage {
+    /// `headers` is a JSON object (`{"Content-Type": "...", "X-Multi": ["a", "b"]}`)
+    /// or a JSON array of `[name, value]` pairs.
+    #[wasm_bindgen(constructor)]
+    pub fn new(method: &str, target_uri: &str, headers: &str) -> Self {
+        utils::set_panic_hook();
+        let parsed_headers: Value = serde_json::from_str(headers).expect("invalid headers json");
+        let value_to_string = |value: &Value| value.as_str().expect("invalid header value").to_string();
+        let headers = match parsed_headers {
+            Value::Object(map) => map.iter().flat_map(|(name, value)| match value {
+                Value::Array(values) => values.iter().map(|value| (name.clone(), value_to_string(value))).collect(),
+                value => vec![(name.clone(), value_to_string(value))]
+            }).collect(),
+            Value::Array(pairs) => pairs.iter().map(|pair| match pair.as_array().map(|pair| pair.as_slice()) {
+                Some([name, value]) => (value_to_string(name), value_to_string(value)),
+                _ => panic!("invalid header pair")
+            }).collect(),
+            _ => panic!("invalid headers json")
+        };
+
+        HttpMessage {
+            method: method.to_string(),
+            target_uri: target_uri.to_string(),
+            headers
+        }
+    }
+}
+
+impl HttpMessage {
+    /// (scheme, authority, path, query) of the target URI.
+    fn uri_parts(&self) -> (String, String, String, Option<String>) {
+        let (scheme, rest) = self.target_uri.split_once("://").expect("target uri must be absolute");
+        let rest = rest.split('#').next().unwrap();
+        let authority_end = rest.find(&['/', '?'][..]).unwrap_or(rest.len());
+        let (authority, path_and_query) = rest.split_at(authority_end);
+        let (path, query) = match path_and_query.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_string())),
+            None => (path_and_query, None)
+        };
+
+        let scheme = scheme.to_lowercase();
+        let mut authority = authority.to_lowercase();
+        let default_port = match scheme.as_str() {
+            "https" => Some(":443"),
+            "http" => Some(":80"),
+            _ => None
+        };
+        if let Some(default_port) = default_port {
+            if authority.ends_with(default_port) {
+                authority.truncate(authority.len() - default_port.len());
+            }
+        }
+
+        let path = if path.is_empty() { "/".to_string() } else { path.to_string() };
+        (scheme, authority, path, query)
+    }
+
+    /// Component value from RFC 9421 section 2.1 / 2.2, an error when the message lacks it
+    /// or the component is not supported.
+    fn component_value(&self, component: &Item) -> Result<String, String> {
+        let name = match &component.value {
+            BareItem::String(name) => name.as_str(),
+            _ => return Err("component identifier must be a string".to_string())
+        };
+        if !component.parameters.is_empty() {
+            return Err(format!("unsupported component parameters for {}", name));
+        }
+
+        let (scheme, authority, path, query) = self.uri_parts();
+        let query = format!("?{}", query.unwrap_or_default());
+        match name {
+            "@method" => Ok(self.method.clone()),
+            "@target-uri" => Ok(self.target_uri.clone()),
+            "@authority" => Ok(authority),
+            "@scheme" => Ok(scheme),
+            "@request-target" => Ok(if query == "?" { path } else { format!("{}{}", path, query) }),
+            "@path" => Ok(path),
+            "@query" => Ok(query),
+            name if name.starts_with('@') => Err(format!("unsupported derived component {}", name)),
+            name => {
+                let values: Vec<String> = self.headers
+                    .iter()
+                    .filter(|(header, _)| header.to_lowercase() == name)
+                    .map(|(_, value)| value.lines().map(|line| line.trim()).collect::<Vec<&str>>().join(" "))
+                    .collect();
+
+                if values.is_empty() {
+                    Err(format!("missing covered component {}", name))
+                } else {
+                    Ok(values.join(", "))
+                }
+            }
+        }
+    }
+
+    /// Signature base from RFC 9421 section 2.5.
+    pub fn signature_base(&self, components: &[Item], parameters: &[(String, BareItem)]) -> Result<String, String> {
+        let mut base = String::new();
+        for component in components {
+            if component.value == BareItem::String("@signature-params".to_string()) {
+                return Err("@signature-params can not be a covered component".to_string());
+            }
+
+            base.push_str(&format!("{}: {}\n", serialize_item(component), self.component_value(component)?));
+        }
+
+        base.push_str(&format!("\"@signature-params\": {}", serialize_inner_list(components, parameters)));
+        Ok(base)
+    }
+}
+
+fn signature_parameters(parameters: &str, algorithm: SignatureAlgorithm) -> Parameters {
+    let parameters: Value = serde_json::from_str(parameters).expect("invalid parameters json");
+    let mut result = vec![];
+    for key in ["created", "expires", "nonce", "alg", "keyid", "tag"].iter() {
+        let value = match (*key, parameters.get(*key)) {
+            ("alg", _) => Some(BareItem::String(algorithm.name().to_string())),
+            (_, None) | (_, Some(Value::Null)) => None,
+            ("created", Some(value)) | ("expires", Some(value)) => {
+                Some(BareItem::Integer(value.as_i64().expect("timestamps must be integers")))
+            },
+            (key, Some(value)) => Some(BareItem::String(
+                value.as_str().unwrap_or_else(|| panic!("{} must be a string", key)).to_string()
+            ))
+        };
+
+        if let Some(value) = value {
+            result.push((key.to_string(), value));
+        }
+    }
+
+    result
+}
+
+#[wasm_bindgen]
+impl RSAPrivateKeyPair {
+    /// Signs `message` per RFC 9421 and returns JSON with `signature-input` and `signature` header values.
+    /// `components` is a JSON array of component identifiers; `parameters` is a JSON object with
+    /// optional `created`, `expires`, `nonce`, `keyid` and `tag`.
+    pub fn sign_http_message(
+        &self,
+        message: &HttpMessage,
+        label: &str,
+        components: &str,
+        parameters: &str,
+        algorithm: &str,
+        random_seed: &str
+    ) -> String {
+        utils::set_panic_hook();
+        let algorithm = SignatureAlgorithm::from_name(algorithm);
+        let components: Vec<String> = serde_json::from_str(components).expect("invalid components json");
+        let components: Vec<Item> = components
+            .into_iter()
+            .map(|name| Item { value: BareItem::String(name.to_lowercase()), parameters: vec![] })
+            .collect();
+        let parameters = signature_parameters(parameters, algorithm);
+        let base = message.signature_base(&components, &parameters).unwrap_or_else(|error| panic!("{}", error));
+
+        let mut rng = utils::seeded_rng(random_seed);
+        let key = self.signing_instance(algorithm.padding(), algorithm.hash());
+        let signature = algorithm.sign(&mut rng, key, base.as_bytes());
+
+        serde_json::json!({
+            "signature-input": format!("{}={}", label, serialize_inner_list(&components, &parameters)),
+            "signature": format!("{}={}", label, serialize_bare_item(&BareItem::ByteSequence(signature)))
+        }).to_string()
+    }
+}
+
+/// Signature of `label` with its covered components and parameters, `None` if either header
+/// is malformed or lacks the label.
+fn find_signature(signature_input: &str, signature: &str, label: &str) -> Option<(Vec<Item>, Parameters, Vec<u8>)> {
+    let (components, parameters) = match parse_dictionary(signature_input)?.into_iter().find(|(key, _)| key == label)? {
+        (_, Member::InnerList(components, parameters)) => (components, parameters),
+        _ => return None
+    };
+    match parse_dictionary(signature)?.into_iter().find(|(key, _)| key == label)? {
+        (_, Member::Item(Item { value: BareItem::ByteSequence(signature), .. })) => Some((components, parameters, signature)),
+        _ => None
+    }
+}
+
+/// Checks `alg` against `algorithm` and the validity period against `now`: `created` must not be in
+/// the future, `expires` not in the past and, with `max_age`, `created` at most `max_age` seconds ago.
+fn check_parameters(parameters: &[(String, BareItem)], algorithm: SignatureAlgorithm, now: i64, max_age: Option<i64>) -> bool {
+    let mut created = None;
+    for (key, value) in parameters {
+        match (key.as_str(), value) {
+            ("alg", BareItem::String(alg)) if alg == algorithm.name() => {},
+            ("created", BareItem::Integer(value)) => created = Some(*value),
+            ("expires", BareItem::Integer(expires)) if *expires >= now => {},
+            ("alg", _) | ("created", _) | ("expires", _) => return false,
+            _ => {}
+        }
+    }
+
+    match (created, max_age) {
+        (Some(created), _) if created > now => false,
+        (Some(created), Some(max_age)) => now - created <= max_age,
+        (None, Some(_)) => false,
+        _ => true
+    }
+}
+
+#[wasm_bindgen]
+impl RSAPublicKeyPair {
+    /// Verifies the `label` signature of `message` at `now` (unix seconds). Signatures created after
+    /// `now`, expired or, with `max_age` (seconds), created more than `max_age` before `now` are rejected,
+    /// as are malformed or unsupported headers.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_http_message(
+        &self,
+        message: &HttpMessage,
+        signature_input: &str,
+        signature: &str,
+        label: &str,
+        algorithm: &str,
+        now: u32,
+        max_age: Option<u32>
+    ) -> bool {
+        utils::set_panic_hook();
+        let algorithm = SignatureAlgorithm::from_name(algorithm);
+        let (components, parameters, signature) = match find_signature(signature_input, signature, label) {
+            Some(signature) => signature,
+            None => return false
+        };
+        if !check_parameters(&parameters, algorithm, now as i64, max_age.map(|max_age| max_age as i64)) {
+            return false;
+        }
+
+        match message.signature_base(&components, &parameters) {
+            Ok(base) => algorithm.verify(self.verifying_instance(algorithm.padding(), algorithm.hash()), base.as_bytes(), &signature),
+            Err(_) => false
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fixtures::key_pairs;
+
+    const SEED: &str = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+    const NOW: u32 = 1618884500;
+
+    fn request() -> HttpMessage {
+        HttpMessage::new(
+            "POST",
+            "https://example.com/foo?param=Value&Pet=dog",
+            r#"{
+                "Host": "example.com",
+                "Date": "Tue, 20 Apr 2021 02:07:55 GMT",
+                "Content-Type": "application/json",
+                "Content-Digest": "sha-512=:WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+TaPm+AbwAgBWnrIiYllu7BNNyealdVLvRwEmTHWXvJwew==:",
+                "Content-Length": "18"
+            }"#
+        )
+    }
+
+    fn signed(algorithm: &str) -> (String, String) {
+        let (private_instance, _) = key_pairs();
+        let result: Value = serde_json::from_str(&private_instance.sign_http_message(
+            &request(),
+            "sig1",
+            r#"["@method", "@authority", "@path", "content-digest", "content-type"]"#,
+            r#"{"created": 1618884473, "expires": 1618884773, "keyid": "test-key-rsa", "nonce": "b3k2pp5k7z-50gnwp.yemd"}"#,
+            algorithm,
+            SEED
+        )).unwrap();
+
+        (result["signature-input"].as_str().unwrap().to_string(), result["signature"].as_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn signature_base() {
+        let components: Vec<Item> = ["@method", "@authority", "@path", "@query", "@target-uri", "content-type"]
+            .iter()
+            .map(|name| Item { value: BareItem::String(name.to_string()), parameters: vec![] })
+            .collect();
+        let parameters = vec![
+            ("created".to_string(), BareItem::Integer(1618884473)),
+            ("keyid".to_string(), BareItem::String("test-key-rsa-pss".to_string()))
+        ];
+
+        assert_eq!(request().signature_base(&components, &parameters).unwrap(), [
+            "\"@method\": POST",
+            "\"@authority\": example.com",
+            "\"@path\": /foo",
+            "\"@query\": ?param=Value&Pet=dog",
+            "\"@target-uri\": https://example.com/foo?param=Value&Pet=dog",
+            "\"content-type\": application/json",
+            "\"@signature-params\": (\"@method\" \"@authority\" \"@path\" \"@query\" \"@target-uri\" \"content-type\");created=1618884473;keyid=\"test-key-rsa-pss\""
+        ].join("\n"));
+    }
+
+    #[test]
+    fn authority_default_port() {
+        let message = HttpMessage::new("GET", "https://Example.COM:443", "{}");
+        let component = Item { value: BareItem::String("@authority".to_string()), parameters: vec![] };
+        assert_eq!(message.component_value(&component).unwrap(), "example.com");
+        let component = Item { value: BareItem::String("@request-target".to_string()), parameters: vec![] };
+        assert_eq!(message.component_value(&component).unwrap(), "/");
+    }
+
+    #[test]
+    fn combined_header_values() {
+        let message = HttpMessage::new("GET", "https://example.com/", r#"[["X-Multi", " a "], ["x-multi", "b"]]"#);
+        let component = Item { value: BareItem::String("x-multi".to_string()), parameters: vec![] };
+        assert_eq!(message.component_value(&component).unwrap(), "a, b");
+    }
+
+    #[test]
+    fn parse_signature_input() {
+        let input = "sig1=(\"@method\" \"content-type\");created=1618884473;keyid=\"test-key\", sig2=:AQID:";
+        let dictionary = parse_dictionary(input).unwrap();
+        assert_eq!(dictionary.len(), 2);
+        match &dictionary[0].1 {
+            Member::InnerList(items, parameters) => {
+                assert_eq!(serialize_inner_list(items, parameters), &input[5..input.find(", ").unwrap()]);
+            },
+            _ => panic!("expected inner list")
+        }
+        assert_eq!(dictionary[1].1, Member::Item(Item { value: BareItem::ByteSequence(vec![1, 2, 3]), parameters: vec![] }));
+    }
+
+    #[test]
+    fn sign_and_verify_rsa_v1_5_sha256() {
+        let (_, public_instance) = key_pairs();
+        let (signature_input, signature) = signed("rsa-v1_5-sha256");
+        assert!(signature_input.starts_with("sig1=(\"@method\" \"@authority\" \"@path\" \"content-digest\" \"content-type\");created=1618884473;expires=1618884773;nonce=\"b3k2pp5k7z-50gnwp.yemd\";alg=\"rsa-v1_5-sha256\";keyid=\"test-key-rsa\""));
+        assert!(public_instance.verify_http_message(&request(), &signature_input, &signature, "sig1", "rsa-v1_5-sha256", NOW, None));
+        assert!(!public_instance.verify_http_message(&request(), &signature_input, &signature, "sig1", "rsa-pss-sha512", NOW, None));
+    }
+
+    #[test]
+    fn sign_and_verify_rsa_pss_sha512() {
+        let (_, public_instance) = key_pairs();
+        let (signature_input, signature) = signed("rsa-pss-sha512");
+        assert!(public_instance.verify_http_message(&request(), &signature_input, &signature, "sig1", "rsa-pss-sha512", NOW, None));
+        assert!(!public_instance.verify_http_message(&request(), &signature_input, &signature, "sig2", "rsa-pss-sha512", NOW, None));
+    }
+
+    #[test]
+    fn verify_expired_signature() {
+        let (_, public_instance) = key_pairs();
+        let (signature_input, signature) = signed("rsa-pss-sha512");
+        assert!(!public_instance.verify_http_message(&request(), &signature_input, &signature, "sig1", "rsa-pss-sha512", 1618884774, None));
+    }
+
+    #[test]
+    fn verify_future_signature() {
+        let (_, public_instance) = key_pairs();
+        let (signature_input, signature) = signed("rsa-pss-sha512");
+        assert!(!public_instance.verify_http_message(&request(), &signature_input, &signature, "sig1", "rsa-pss-sha512", 1618884472, None));
+    }
+
+    #[test]
+    fn verify_max_age() {
+        let (_, public_instance) = key_pairs();
+        let (signature_input, signature) = signed("rsa-pss-sha512");
+        assert!(public_instance.verify_http_message(&request(), &signature_input, &signature, "sig1", "rsa-pss-sha512", NOW, Some(60)));
+        assert!(!public_instance.verify_http_message(&request(), &signature_input, &signature, "sig1", "rsa-pss-sha512", NOW, Some(10)));
+
+        let signature_input = signature_input.replace("created=1618884473;", "");
+        assert!(!public_instance.verify_http_message(&request(), &signature_input, &signature, "sig1", "rsa-pss-sha512", NOW, Some(60)));
+    }
+
+    #[test]
+    fn verify_malformed_headers() {
+        let (_, public_instance) = key_pairs();
+        let (signature_input, signature) = signed("rsa-pss-sha512");
+        let verify = |signature_input: &str, signature: &str| {
+            public_instance.verify_http_message(&request(), signature_input, signature, "sig1", "rsa-pss-sha512", NOW, None)
+        };
+
+        for malformed in ["sig1=(", "sig1=(\"@method\"", "sig1=(\"@method\");created=x", "sig1=(\"@method\"),", "sig1=(\"\\x\")", "Sig1=()", "sig1=(\"@method\");created=99999999999999999999"].iter() {
+            assert!(!verify(malformed, &signature), "{}", malformed);
+        }
+        for unsupported in ["sig1=(\"@query-param\";name=\"Pet\")", "sig1=(\"@status\")", "sig1=(@method)", "sig1=(\"@signature-params\")", "sig1=();created=\"1618884473\""].iter() {
+            assert!(!verify(unsupported, &signature), "{}", unsupported);
+        }
+        for malformed in ["sig1=:AQID", "sig1=:!!:", "sig1=\"signature\"", "sig1"].iter() {
+            assert!(!verify(&signature_input, malformed), "{}", malformed);
+        }
+    }
+
+    #[test]
+    fn verify_independent_vectors() {
+        // signature inputs of RFC 9421 B.2.1 and B.2.3 signed by pyca/cryptography with PRIVATE_KEY
+        let vectors: Value = serde_json::from_str(include_str!("testdata/http_signatures.json")).unwrap();
+        let (_, public_instance) = key_pairs();
+        for vector in vectors.as_array().unwrap() {
+            let field = |name: &str| vector[name].as_str().unwrap().to_string();
+            let (components, parameters, _) = find_signature(&field("signature_input"), &field("signature"), &field("name")).unwrap();
+            assert_eq!(request().signature_base(&components, &parameters).unwrap(), field("signature_base"));
+            assert!(public_instance.verify_http_message(&request(), &field("signature_input"), &field("signature"), &field("name"), &field("algorithm"), NOW, Some(60)));
+        }
+    }
+
+    #[test]
+    fn verify_modified_request() {
+        let (_, public_instance) = key_pairs();
+        let (signature_input, signature) = signed("rsa-v1_5-sha256");
+        let message = HttpMessage::new("POST", "https://example.com/foo", r#"{"Content-Type": "text/plain"}"#);
+        assert!(!public_instance.verify_http_message(&message, &signature_input, &signature, "sig1", "rsa-v1_5-sha256", NOW, None));
+    }
+
+    #[test]
+    #[should_panic]
+    fn sign_with_missing_header() {
+        let (private_instance, _) = key_pairs();
+        private_instance.sign_http_message(&request(), "sig1", r#"["x-missing"]"#, "{}", "rsa-pss-sha512", SEED);
+    }
+}
@@ -0,0 +1,175 @@
+use super::*;
+use serde_json::Value;
+
+/// Number serialization from ECMAScript Number.prototype.toString, as required by RFC 8785 3.2.2.3.
+pub fn serialize_number(value: f64) -> String {
+    if !value.is_finite() {
+        panic!("invalid json number");
+    }
+    if value == 0.0 {
+        return "0".to_string();
+    }
+
+    let sign = if value < 0.0 { "-" } else { "" };
+    // `{:e}` yields the shortest round-tripping digit count, the correctly rounded
+    // output with that precision is the closest candidate ECMAScript asks for.
+    let shortest = format!("{:e}", value.abs());
+    let precision = shortest.split('e').next().unwrap().replace('.', "").len() - 1;
+    let closest = format!("{:.*e}", precision, value.abs());
+    let scientific = if closest.parse::<f64>() == Ok(value.abs()) { closest } else { shortest };
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    let n = exponent.parse::<i32>().unwrap() + 1;
+
+    let formatted = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let exponent_sign = if n - 1 < 0 { "-" } else { "+" };
+        let fraction = if k > 1 { format!(".{}", &digits[1..]) } else { String::new() };
+        format!("{}{}e{}{}", &digits[..1], fraction, exponent_sign, (n - 1).abs())
+    };
+
+    format!("{}{}", sign, formatted)
+}
+
+fn serialize(value: &Value, output: &mut String) {
+    match value {
+        Value::Null | Value::Bool(_) | Value::String(_) => {
+            output.push_str(&serde_json::to_string(value).unwrap());
+        },
+        Value::Number(number) => {
+            output.push_str(&serialize_number(number.as_f64().expect("invalid json number")));
+        },
+        Value::Array(items) => {
+            output.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                serialize(item, output);
+            }
+            output.push(']');
+        },
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+            output.push('{');
+            for (index, (key, item)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                output.push_str(&serde_json::to_string(key).unwrap());
+                output.push(':');
+                serialize(item, output);
+            }
+            output.push('}');
+        }
+    }
+}
+
+/// JSON Canonicalization Scheme (RFC 8785) serialization of a parsed value.
+pub fn canonicalize_value(value: &Value) -> String {
+    let mut output = String::new();
+    serialize(value, &mut output);
+    output
+}
+
+pub fn canonicalize(json: &str) -> String {
+    canonicalize_value(&serde_json::from_str(json).expect("invalid json"))
+}
+
+#[wasm_bindgen]
+pub fn canonicalize_json(json: &str) -> String {
+    utils::set_panic_hook();
+    canonicalize(json)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn serialize_numbers() {
+        let vectors = [
+            (0x0000000000000000u64, "0"),
+            (0x8000000000000000, "0"),
+            (0x0000000000000001, "5e-324"),
+            (0x8000000000000001, "-5e-324"),
+            (0x7fefffffffffffff, "1.7976931348623157e+308"),
+            (0x4340000000000000, "9007199254740992"),
+            (0xc340000000000000, "-9007199254740992"),
+            (0x4430000000000000, "295147905179352830000"),
+            (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+            (0x44b52d02c7e14af6, "1e+23"),
+            (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+            (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+            (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+            (0x444b1ae4d6e2ef50, "1e+21"),
+            (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+            (0x3eb0c6f7a0b5ed8d, "0.000001"),
+            (0x41b3de4355555553, "333333333.3333332"),
+            (0x41b3de4355555554, "333333333.33333325"),
+            (0x41b3de4355555555, "333333333.3333333"),
+            (0x41b3de4355555556, "333333333.3333334"),
+            (0x41b3de4355555557, "333333333.33333343"),
+            (0xbecbf647612f3696, "-0.0000033333333333333333"),
+            (0x43143ff3c1cb0959, "1424953923781206.2")
+        ];
+
+        for (bits, expected) in vectors.iter() {
+            assert_eq!(serialize_number(f64::from_bits(*bits)), *expected);
+        }
+    }
+
+    #[test]
+    fn canonicalize_primitives() {
+        let input = r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#;
+
+        assert_eq!(
+            canonicalize(input),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+
+    #[test]
+    fn canonicalize_property_order() {
+        let input = r#"{
+            "\u20ac": "Euro Sign",
+            "\r": "Carriage Return",
+            "דּ": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "\ud83d\ude00": "Emoji: Grinning Face",
+            "\u0080": "Control",
+            "\u00f6": "Latin Small Letter O With Diaeresis"
+        }"#;
+
+        let keys: Vec<String> = canonicalize(input)
+            .split(",\"")
+            .map(|entry| entry.split("\":").next().unwrap().trim_start_matches("{\"").to_string())
+            .collect();
+        assert_eq!(keys, vec!["\\r", "1", "\u{80}", "\u{f6}", "\u{20ac}", "\u{1f600}", "\u{fb33}"]);
+    }
+
+    #[test]
+    fn canonicalize_is_stable() {
+        let input = r#"{ "b": [1, {"d": 1.0, "c": -0}], "a": "x" }"#;
+        assert_eq!(canonicalize(input), r#"{"a":"x","b":[1,{"c":0,"d":1}]}"#);
+        assert_eq!(canonicalize(&canonicalize(input)), canonicalize(input));
+    }
+
+    #[test]
+    #[should_panic]
+    fn canonicalize_invalid_json() {
+        canonicalize("{\"a\":");
+    }
+}
@@ -0,0 +1,102 @@
+use super::*;
+use serde_json::Value;
+use public_keys::RSAPublicKeyPair;
+use private_keys::RSAPrivateKeyPair;
+
+fn parse_object(json: &str) -> serde_json::Map<String, Value> {
+    match serde_json::from_str(json).expect("invalid json") {
+        Value::Object(map) => map,
+        _ => panic!("json document must be an object")
+    }
+}
+
+#[wasm_bindgen]
+impl RSAPrivateKeyPair {
+    /// Signs the RFC 8785 canonical form of `json` the same way as `sign_message`.
+    pub fn sign_json(&self, json: &str) -> String {
+        utils::set_panic_hook();
+        self.sign_message(&jcs::canonicalize(json))
+    }
+
+    /// Signs the canonical object without `field` and returns the canonical object with the signature in `field`.
+    pub fn sign_json_embedded(&self, json: &str, field: &str) -> String {
+        utils::set_panic_hook();
+        let mut object = parse_object(json);
+        if object.contains_key(field) {
+            panic!("field {} is reserved for the signature", field);
+        }
+
+        let signature = self.sign_message(&jcs::canonicalize_value(&Value::Object(object.clone())));
+        object.insert(field.to_string(), Value::String(signature));
+        jcs::canonicalize_value(&Value::Object(object))
+    }
+}
+
+#[wasm_bindgen]
+impl RSAPublicKeyPair {
+    pub fn verify_json(&self, json: &str, signature: &str) -> bool {
+        utils::set_panic_hook();
+        self.verify_message(&jcs::canonicalize(json), signature)
+    }
+
+    pub fn verify_json_embedded(&self, json: &str, field: &str) -> bool {
+        utils::set_panic_hook();
+        let mut object = parse_object(json);
+        let signature = match object.remove(field) {
+            Some(Value::String(signature)) => signature,
+            _ => return false
+        };
+        if hex::decode(&signature).is_err() {
+            return false;
+        }
+
+        self.verify_message(&jcs::canonicalize_value(&Value::Object(object)), &signature)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fixtures::key_pairs;
+
+    #[test]
+    fn verify_reordered_document() {
+        let (private_instance, public_instance) = key_pairs();
+        let signature = private_instance.sign_json(r#"{"amount": 1.50, "currency": "EUR", "id": 7}"#);
+        assert!(public_instance.verify_json(r#"{"id":7,"currency":"EUR","amount":1.5}"#, &signature));
+        assert!(!public_instance.verify_json(r#"{"id":7,"currency":"EUR","amount":1.51}"#, &signature));
+    }
+
+    #[test]
+    fn signature_matches_sign_message() {
+        let (private_instance, _) = key_pairs();
+        assert_eq!(
+            private_instance.sign_json(r#"{ "b": 2, "a": 1 }"#),
+            private_instance.sign_message(r#"{"a":1,"b":2}"#)
+        );
+    }
+
+    #[test]
+    fn embedded_signature() {
+        let (private_instance, public_instance) = key_pairs();
+        let signed = private_instance.sign_json_embedded(r#"{"b": [1, 2], "a": "x"}"#, "signature");
+        assert!(signed.starts_with(r#"{"a":"x","b":[1,2],"signature":""#));
+        assert!(public_instance.verify_json_embedded(&signed, "signature"));
+        assert!(!public_instance.verify_json_embedded(&signed.replace("\"x\"", "\"y\""), "signature"));
+        assert!(!public_instance.verify_json_embedded(&signed, "other"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn embedded_signature_field_in_use() {
+        let (private_instance, _) = key_pairs();
+        private_instance.sign_json_embedded(r#"{"signature": 1}"#, "signature");
+    }
+
+    #[test]
+    #[should_panic]
+    fn embedded_signature_without_object() {
+        let (private_instance, _) = key_pairs();
+        private_instance.sign_json_embedded("[1, 2]", "signature");
+    }
+}
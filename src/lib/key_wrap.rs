@@ -0,0 +1,192 @@
+use aes::{ Aes128, Aes192, Aes256 };
+use aes::cipher::{ BlockDecrypt, BlockEncrypt, KeyInit, generic_array::GenericArray };
+
+const KWP_IV_PREFIX: [u8; 4] = [0xa6, 0x59, 0x59, 0xa6];
+
+pub enum AesKey {
+    Aes128(Aes128),
+    Aes192(Aes192),
+    Aes256(Aes256)
+}
+
+impl AesKey {
+    pub fn new(key: &[u8]) -> AesKey {
+        match key.len() {
+            16 => AesKey::Aes128(Aes128::new(GenericArray::from_slice(key))),
+            24 => AesKey::Aes192(Aes192::new(GenericArray::from_slice(key))),
+            32 => AesKey::Aes256(Aes256::new(GenericArray::from_slice(key))),
+            len => panic!("invalid aes key length {}", len)
+        }
+    }
+
+    pub fn encrypt_block(&self, block: &mut [u8]) {
+        let block = GenericArray::from_mut_slice(block);
+        match self {
+            AesKey::Aes128(cipher) => cipher.encrypt_block(block),
+            AesKey::Aes192(cipher) => cipher.encrypt_block(block),
+            AesKey::Aes256(cipher) => cipher.encrypt_block(block)
+        }
+    }
+
+    pub fn decrypt_block(&self, block: &mut [u8]) {
+        let block = GenericArray::from_mut_slice(block);
+        match self {
+            AesKey::Aes128(cipher) => cipher.decrypt_block(block),
+            AesKey::Aes192(cipher) => cipher.decrypt_block(block),
+            AesKey::Aes256(cipher) => cipher.decrypt_block(block)
+        }
+    }
+}
+
+fn wrap_blocks(kek: &AesKey, iv: [u8; 8], data: &[u8]) -> Vec<u8> {
+    let n = data.len() / 8;
+    let mut a = iv;
+    let mut r: Vec<[u8; 8]> = data.chunks(8).map(|chunk| {
+        let mut block = [0u8; 8];
+        block.copy_from_slice(chunk);
+        block
+    }).collect();
+
+    for j in 0..6 {
+        for (i, r_block) in r.iter_mut().enumerate() {
+            let mut block = [0u8; 16];
+            block[..8].copy_from_slice(&a);
+            block[8..].copy_from_slice(r_block);
+            kek.encrypt_block(&mut block);
+
+            let t = ((n * j) + i + 1) as u64;
+            a.copy_from_slice(&block[..8]);
+            for (byte, counter) in a.iter_mut().zip(t.to_be_bytes().iter()) {
+                *byte ^= counter;
+            }
+            r_block.copy_from_slice(&block[8..]);
+        }
+    }
+
+    let mut result = a.to_vec();
+    for block in r {
+        result.extend_from_slice(&block);
+    }
+
+    result
+}
+
+fn unwrap_blocks(kek: &AesKey, data: &[u8]) -> ([u8; 8], Vec<u8>) {
+    let n = data.len() / 8 - 1;
+    let mut a = [0u8; 8];
+    a.copy_from_slice(&data[..8]);
+    let mut r: Vec<[u8; 8]> = data[8..].chunks(8).map(|chunk| {
+        let mut block = [0u8; 8];
+        block.copy_from_slice(chunk);
+        block
+    }).collect();
+
+    for j in (0..6).rev() {
+        for i in (0..n).rev() {
+            let t = ((n * j) + i + 1) as u64;
+            for (byte, counter) in a.iter_mut().zip(t.to_be_bytes().iter()) {
+                *byte ^= counter;
+            }
+
+            let mut block = [0u8; 16];
+            block[..8].copy_from_slice(&a);
+            block[8..].copy_from_slice(&r[i]);
+            kek.decrypt_block(&mut block);
+
+            a.copy_from_slice(&block[..8]);
+            r[i].copy_from_slice(&block[8..]);
+        }
+    }
+
+    (a, r.concat())
+}
+
+/// AES Key Wrap with Padding from RFC 5649.
+pub fn aes_key_wrap_pad(kek: &[u8], key: &[u8]) -> Vec<u8> {
+    if key.is_empty() {
+        panic!("empty key for wrap");
+    }
+
+    let mut iv = [0u8; 8];
+    iv[..4].copy_from_slice(&KWP_IV_PREFIX);
+    iv[4..].copy_from_slice(&(key.len() as u32).to_be_bytes());
+
+    let mut padded = key.to_vec();
+    padded.resize((key.len() + 7) / 8 * 8, 0);
+
+    let kek = AesKey::new(kek);
+    if padded.len() == 8 {
+        let mut block = [0u8; 16];
+        block[..8].copy_from_slice(&iv);
+        block[8..].copy_from_slice(&padded);
+        kek.encrypt_block(&mut block);
+        return block.to_vec();
+    }
+
+    wrap_blocks(&kek, iv, &padded)
+}
+
+/// AES Key Unwrap with Padding from RFC 5649.
+pub fn aes_key_unwrap_pad(kek: &[u8], wrapped: &[u8]) -> Vec<u8> {
+    if wrapped.len() < 16 || wrapped.len() % 8 != 0 {
+        panic!("invalid wrapped key length {}", wrapped.len());
+    }
+
+    let kek = AesKey::new(kek);
+    let (iv, padded) = if wrapped.len() == 16 {
+        let mut block = [0u8; 16];
+        block.copy_from_slice(wrapped);
+        kek.decrypt_block(&mut block);
+        let mut iv = [0u8; 8];
+        iv.copy_from_slice(&block[..8]);
+        (iv, block[8..].to_vec())
+    } else {
+        unwrap_blocks(&kek, wrapped)
+    };
+
+    let mut len_bytes = [0u8; 4];
+    len_bytes.copy_from_slice(&iv[4..]);
+    let len = u32::from_be_bytes(len_bytes) as usize;
+    let valid = iv[..4] == KWP_IV_PREFIX
+        && len <= padded.len()
+        && len + 8 > padded.len()
+        && padded[len..].iter().all(|byte| *byte == 0);
+
+    if !valid {
+        panic!("integrity check failed");
+    }
+
+    padded[..len].to_vec()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rfc5649_20_octets() {
+        let kek = hex::decode("5840df6e29b02af1ab493b705bf16ea1ae8338f4dcc176a8").unwrap();
+        let key = hex::decode("c37b7e6492584340bed12207808941155068f738").unwrap();
+        let wrapped = aes_key_wrap_pad(&kek, &key);
+        assert_eq!(hex::encode(&wrapped), "138bdeaa9b8fa7fc61f97742e72248ee5ae6ae5360d1ae6a5f54f373fa543b6a");
+        assert_eq!(aes_key_unwrap_pad(&kek, &wrapped), key);
+    }
+
+    #[test]
+    fn rfc5649_7_octets() {
+        let kek = hex::decode("5840df6e29b02af1ab493b705bf16ea1ae8338f4dcc176a8").unwrap();
+        let key = hex::decode("466f7250617369").unwrap();
+        let wrapped = aes_key_wrap_pad(&kek, &key);
+        assert_eq!(hex::encode(&wrapped), "afbeb0f07dfbf5419200f2ccb50bb24f");
+        assert_eq!(aes_key_unwrap_pad(&kek, &wrapped), key);
+    }
+
+    #[test]
+    #[should_panic]
+    fn unwrap_with_wrong_kek() {
+        let kek = hex::decode("5840df6e29b02af1ab493b705bf16ea1ae8338f4dcc176a8").unwrap();
+        let key = hex::decode("c37b7e6492584340bed12207808941155068f738").unwrap();
+        let wrapped = aes_key_wrap_pad(&kek, &key);
+        aes_key_unwrap_pad(&[0u8; 24], &wrapped);
+    }
+}
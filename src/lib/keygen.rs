@@ -0,0 +1,462 @@
+use super::*;
+use num_bigint_dig::prime::probably_prime;
+use num_traits::{ Num, One, Zero };
+use primitives::mod_inverse;
+use private_keys::RSAPrivateKeyPair;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+#[cfg(all(feature = "parallel", target_arch = "wasm32"))]
+pub use wasm_bindgen_rayon::init_thread_pool;
+
+pub const DEFAULT_EXPONENT: u32 = 65537;
+const MILLER_RABIN_ROUNDS: usize = 20;
+const SMALL_PRIMES_LIMIT: u32 = 2000;
+const MIN_PRIME_BITS: usize = 32;
+
+/// Upper bound for the public exponent, the largest the rsa crate accepts when loading a key.
+pub(crate) const MAX_EXPONENT_BITS: usize = 30;
+
+/// Most primes for a modulus size such that factoring the modulus stays harder than
+/// factoring its primes, the same bounds OpenSSL applies to multi-prime keys.
+pub fn max_primes(bits: usize) -> usize {
+    match bits {
+        0..=1023 => 2,
+        1024..=4095 => 3,
+        4096..=8191 => 4,
+        _ => 5
+    }
+}
+
+/// Primes below `limit`, sieve of Eratosthenes.
+pub(crate) fn small_primes(limit: u32) -> Vec<u32> {
+    let mut sieve = vec![true; limit as usize];
+    let mut primes = vec![];
+    for value in 2..limit {
+        if sieve[value as usize] {
+            primes.push(value);
+            for multiple in (value * value..limit).step_by(value as usize) {
+                sieve[multiple as usize] = false;
+            }
+        }
+    }
+
+    primes
+}
+
+/// Random odd `bits`-bit integer with the two top bits set, so that the product of such primes has the full size.
+fn prime_candidate<R: RngCore>(rng: &mut R, bits: usize) -> BigUint {
+    let mut bytes = vec![0u8; (bits + 7) / 8];
+    rng.fill_bytes(&mut bytes);
+
+    let excess = bytes.len() * 8 - bits;
+    bytes[0] &= 0xff >> excess;
+    if bits % 8 == 1 {
+        bytes[0] |= 1;
+        bytes[1] |= 0x80;
+    } else {
+        bytes[0] |= 0xc0 >> excess;
+    }
+    *bytes.last_mut().unwrap() |= 1;
+
+    BigUint::from_bytes_be(&bytes)
+}
+
+/// Incremental prime search for an RSA modulus, one candidate at a time.
+pub struct PrimeSearch {
+    bits: usize,
+    e: BigUint,
+    prime_bits: Vec<usize>,
+    small_primes: Vec<u32>,
+    primes: Vec<BigUint>,
+    candidates_tested: u64
+}
+
+impl PrimeSearch {
+    pub fn new(bits: usize, prime_count: usize, e: BigUint) -> PrimeSearch {
+        if prime_count < 2 {
+            panic!("rsa keys need at least two primes");
+        }
+        if bits / prime_count < MIN_PRIME_BITS || prime_count > max_primes(bits) {
+            panic!("key size {} is too small for {} primes", bits, prime_count);
+        }
+        if e < BigUint::from(3u32) || (&e % 2u32).is_zero() {
+            panic!("public exponent must be odd and at least 3");
+        }
+        if e.bits() > MAX_EXPONENT_BITS {
+            panic!("public exponent must be less than 2^{}", MAX_EXPONENT_BITS);
+        }
+
+        // Same split as the rsa crate: the remaining bits are divided over the remaining primes.
+        let mut remaining = bits;
+        let prime_bits = (0..prime_count).map(|index| {
+            let size = remaining / (prime_count - index);
+            remaining -= size;
+            size
+        }).collect();
+
+        PrimeSearch { bits, e, prime_bits, small_primes: small_primes(SMALL_PRIMES_LIMIT), primes: vec![], candidates_tested: 0 }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.primes.len() == self.prime_bits.len()
+    }
+
+    pub fn candidates_tested(&self) -> u64 {
+        self.candidates_tested
+    }
+
+    pub fn primes_found(&self) -> usize {
+        self.primes.len()
+    }
+
+    fn is_acceptable(&self, candidate: &BigUint) -> bool {
+        let has_small_factor = self.small_primes.iter().any(|prime| (candidate % *prime).is_zero());
+        if has_small_factor || self.primes.contains(candidate) {
+            return false;
+        }
+
+        mod_inverse(&self.e, &(candidate - BigUint::one())).is_some() && probably_prime(candidate, MILLER_RABIN_ROUNDS)
+    }
+
+    /// Tests one candidate for the next prime and returns `true` once all primes are found.
+    pub fn test_candidate<R: RngCore>(&mut self, rng: &mut R) -> bool {
+        if self.is_done() {
+            return true;
+        }
+
+        self.candidates_tested += 1;
+        let candidate = prime_candidate(rng, self.prime_bits[self.primes.len()]);
+        if self.is_acceptable(&candidate) {
+            self.primes.push(candidate);
+        }
+
+        // With more than two primes the product can still come out one bit short, start over then.
+        if self.is_done() && product(&self.primes).bits() != self.bits {
+            self.primes.clear();
+        }
+
+        self.is_done()
+    }
+
+    pub fn into_key(self) -> RsaPrivateKey {
+        if !self.is_done() {
+            panic!("key generation is not finished");
+        }
+
+        assemble_key(self.e, self.primes)
+    }
+}
+
+fn product(primes: &[BigUint]) -> BigUint {
+    primes.iter().fold(BigUint::one(), |product, prime| product * prime)
+}
+
+/// Private key from primes with `d = e^-1 mod (p_1 - 1)...(p_k - 1)`, as the rsa crate computes it.
+pub fn assemble_key(e: BigUint, primes: Vec<BigUint>) -> RsaPrivateKey {
+    let n = product(&primes);
+    let totient = primes.iter().fold(BigUint::one(), |product, prime| product * (prime - BigUint::one()));
+    let d = mod_inverse(&e, &totient).expect("public exponent is not invertible");
+
+    let mut key = RsaPrivateKey::from_components(n, e, d, primes);
+    key.precompute().expect("invalid private key");
+    key
+}
+
+pub fn generate<R: RngCore>(rng: &mut R, bits: usize, prime_count: usize, e: BigUint) -> RsaPrivateKey {
+    let mut search = PrimeSearch::new(bits, prime_count, e);
+    while !search.test_candidate(rng) {}
+    search.into_key()
+}
+
+/// Same result as `generate` in distribution, with every prime searched on its own rayon task.
+/// Each task draws from a generator seeded by `rng`, so the key is still determined by `rng`.
+#[cfg(feature = "parallel")]
+pub fn generate_parallel<R: RngCore>(rng: &mut R, bits: usize, prime_count: usize, e: BigUint) -> RsaPrivateKey {
+    let search = PrimeSearch::new(bits, prime_count, e);
+    loop {
+        let seeds: Vec<[u8; 32]> = (0..prime_count).map(|_| {
+            let mut seed = [0u8; 32];
+            rng.fill_bytes(&mut seed);
+            seed
+        }).collect();
+
+        let primes: Vec<BigUint> = seeds.into_par_iter().zip(search.prime_bits.par_iter()).map(|(seed, prime_bits)| {
+            let mut prime_rng = StdRng::from_seed(seed);
+            loop {
+                let candidate = prime_candidate(&mut prime_rng, *prime_bits);
+                if search.is_acceptable(&candidate) {
+                    return candidate;
+                }
+            }
+        }).collect();
+
+        let distinct = (1..primes.len()).all(|index| !primes[..index].contains(&primes[index]));
+        if distinct && product(&primes).bits() == bits {
+            return assemble_key(search.e, primes);
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+#[wasm_bindgen]
+impl RSAPrivateKeyPair {
+    /// `generate` with the primes searched in parallel on the rayon thread pool. In the browser
+    /// this has to run in a Web Worker, after `initThreadPool` has resolved.
+    pub fn generate_parallel(&mut self, bits: usize, random_seed: &str) {
+        utils::set_panic_hook();
+        self.policy().check_key_size(bits);
+        let mut rng = utils::seeded_rng(random_seed);
+        self.set_instance(generate_parallel(&mut rng, bits, 2, BigUint::from(DEFAULT_EXPONENT)));
+    }
+}
+
+#[wasm_bindgen]
+impl RSAPrivateKeyPair {
+    /// Generates a key with the hex `public_exponent` (3, 11, 10001 or another odd value below 2^30)
+    /// and `prime_count` primes (RFC 8017 multi-prime when above 2), up to `max_primes` for the size.
+    pub fn generate_with(&mut self, bits: usize, public_exponent: &str, prime_count: usize, random_seed: &str) {
+        utils::set_panic_hook();
+        let e = BigUint::from_str_radix(public_exponent, 16).expect("invalid public exponent");
+        self.policy().check_key_size(bits);
+        self.policy().check_exponent(&e);
+        let mut rng = utils::seeded_rng(random_seed);
+        self.set_instance(generate(&mut rng, bits, prime_count, e));
+    }
+}
+
+/// Key generation that runs in small steps, so a caller can yield to the event loop,
+/// report progress and stop between them.
+#[wasm_bindgen]
+pub struct RSAKeyGenerator {
+    rng: StdRng,
+    search: Option<PrimeSearch>
+}
+
+#[wasm_bindgen]
+impl RSAKeyGenerator {
+    /// Panics unless the policy of `private_key`, the key pair `finish` fills, allows `bits`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(bits: usize, private_key: &RSAPrivateKeyPair, random_seed: &str) -> Self {
+        utils::set_panic_hook();
+        private_key.policy().check_key_size(bits);
+        RSAKeyGenerator {
+            rng: utils::seeded_rng(random_seed),
+            search: Some(PrimeSearch::new(bits, 2, BigUint::from(DEFAULT_EXPONENT)))
+        }
+    }
+
+    /// Tests up to `max_candidates` prime candidates and returns `true` once the key is complete.
+    pub fn step(&mut self, max_candidates: u32) -> bool {
+        utils::set_panic_hook();
+        let rng = &mut self.rng;
+        let search = self.search.as_mut().expect("key generation cancelled");
+        for _ in 0..max_candidates {
+            if search.test_candidate(rng) {
+                return true;
+            }
+        }
+
+        search.is_done()
+    }
+
+    pub fn candidates_tested(&self) -> u32 {
+        self.search.as_ref().expect("key generation cancelled").candidates_tested() as u32
+    }
+
+    pub fn primes_found(&self) -> u32 {
+        self.search.as_ref().expect("key generation cancelled").primes_found() as u32
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(&self.search, Some(search) if search.is_done())
+    }
+
+    /// Discards the primes found so far. Any later call except `is_done` panics.
+    pub fn cancel(&mut self) {
+        self.search = None;
+    }
+
+    /// Moves the finished key into `private_key`, if its policy allows the key.
+    pub fn finish(&mut self, private_key: &mut RSAPrivateKeyPair) {
+        utils::set_panic_hook();
+        if !self.search.as_ref().expect("key generation cancelled").is_done() {
+            panic!("key generation is not finished");
+        }
+
+        private_key.set_instance(self.search.take().unwrap().into_key());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fixtures::legacy_private_key_pair;
+    use fixtures::legacy_public_key_pair;
+
+    const SEED: &str = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+
+    #[test]
+    fn prime_candidates_have_exact_size() {
+        let mut rng = utils::seeded_rng(SEED);
+        for bits in [32usize, 33, 39, 40, 512, 1023].iter() {
+            for _ in 0..20 {
+                let candidate = prime_candidate(&mut rng, *bits);
+                assert_eq!(candidate.bits(), *bits);
+                assert!(!(&candidate % 2u32).is_zero());
+                assert_eq!(&candidate >> (*bits - 2), BigUint::from(3u32));
+            }
+        }
+    }
+
+    #[test]
+    fn generate_in_steps() {
+        let mut private_instance = legacy_private_key_pair();
+        let mut generator = RSAKeyGenerator::new(1024, &private_instance, SEED);
+        let mut steps = 0;
+        let mut last_tested = 0;
+        while !generator.step(4) {
+            steps += 1;
+            assert!(generator.candidates_tested() > last_tested);
+            last_tested = generator.candidates_tested();
+        }
+        assert!(steps > 1);
+        assert_eq!(generator.primes_found(), 2);
+        assert!(generator.is_done());
+
+        generator.finish(&mut private_instance);
+        let instance = private_instance.instance();
+        assert_eq!(instance.n().bits(), 1024);
+        assert_eq!(instance.e(), &BigUint::from(DEFAULT_EXPONENT));
+        assert!(instance.validate().is_ok());
+        assert_eq!(private_instance.get_n(), instance.n().to_str_radix(16));
+    }
+
+    #[test]
+    fn generate_is_deterministic() {
+        let first = generate(&mut utils::seeded_rng(SEED), 768, 2, BigUint::from(DEFAULT_EXPONENT));
+        let second = generate(&mut utils::seeded_rng(SEED), 768, 2, BigUint::from(DEFAULT_EXPONENT));
+        assert_eq!(first.n(), second.n());
+        assert_eq!(first.primes().len(), 2);
+    }
+
+    #[test]
+    fn generate_multi_prime() {
+        let key = generate(&mut utils::seeded_rng(SEED), 1024, 3, BigUint::from(DEFAULT_EXPONENT));
+        assert_eq!(key.n().bits(), 1024);
+        assert_eq!(key.primes().len(), 3);
+        assert!(key.validate().is_ok());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn generate_parallel_keys() {
+        let first = generate_parallel(&mut utils::seeded_rng(SEED), 1024, 2, BigUint::from(DEFAULT_EXPONENT));
+        let second = generate_parallel(&mut utils::seeded_rng(SEED), 1024, 2, BigUint::from(DEFAULT_EXPONENT));
+        assert_eq!(first.n(), second.n());
+        assert_eq!(first.n().bits(), 1024);
+        assert!(first.validate().is_ok());
+
+        let multi_prime = generate_parallel(&mut utils::seeded_rng(SEED), 1536, 3, BigUint::from(DEFAULT_EXPONENT));
+        assert_eq!(multi_prime.primes().len(), 3);
+        assert!(multi_prime.validate().is_ok());
+    }
+
+    /// `cargo test --release --features parallel -- --ignored --nocapture bench_`
+    #[cfg(feature = "parallel")]
+    #[test]
+    #[ignore]
+    fn bench_parallel_generation() {
+        for bits in [2048usize, 4096].iter() {
+            let mut rng = utils::seeded_rng(SEED);
+            let start = std::time::Instant::now();
+            for _ in 0..4 {
+                generate(&mut rng, *bits, 2, BigUint::from(DEFAULT_EXPONENT));
+            }
+            let sequential = start.elapsed() / 4;
+
+            let start = std::time::Instant::now();
+            for _ in 0..4 {
+                generate_parallel(&mut rng, *bits, 2, BigUint::from(DEFAULT_EXPONENT));
+            }
+            println!("{} bits: sequential {:?}, parallel {:?}", bits, sequential, start.elapsed() / 4);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "key generation cancelled")]
+    fn step_after_cancel() {
+        let mut generator = RSAKeyGenerator::new(1024, &legacy_private_key_pair(), SEED);
+        generator.step(1);
+        generator.cancel();
+        assert!(!generator.is_done());
+        generator.step(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "key generation is not finished")]
+    fn finish_before_done() {
+        let mut private_instance = legacy_private_key_pair();
+        let mut generator = RSAKeyGenerator::new(1024, &private_instance, SEED);
+        generator.finish(&mut private_instance);
+    }
+
+    #[test]
+    #[should_panic(expected = "key size 1024 is below the policy minimum of 2048 bits")]
+    fn generator_below_policy() {
+        RSAKeyGenerator::new(1024, &RSAPrivateKeyPair::new(), SEED);
+    }
+
+    #[test]
+    #[should_panic(expected = "public exponent must be odd")]
+    fn even_exponent() {
+        PrimeSearch::new(1024, 2, BigUint::from(65536u32));
+    }
+
+    #[test]
+    fn generate_with_exponent_and_primes() {
+        for (bits, exponent, prime_count) in [(1024, "3", 2), (1024, "11", 3), (2048, "10001", 3), (1024, "3ffffffd", 2)].iter() {
+            let mut private_instance = legacy_private_key_pair();
+            private_instance.generate_with(*bits, exponent, *prime_count, SEED);
+
+            let instance = private_instance.instance();
+            assert_eq!(instance.n().bits(), *bits);
+            assert_eq!(private_instance.get_e(), *exponent);
+            assert_eq!(instance.primes().len(), *prime_count);
+            assert!(instance.validate().is_ok());
+
+            let mut public_instance = legacy_public_key_pair();
+            public_instance.create(&private_instance.get_n(), &private_instance.get_e());
+            assert!(public_instance.verify_message("message", &private_instance.sign_message("message")));
+        }
+    }
+
+    #[test]
+    fn max_primes_by_size() {
+        assert_eq!(max_primes(512), 2);
+        assert_eq!(max_primes(2048), 3);
+        assert_eq!(max_primes(4096), 4);
+        assert_eq!(max_primes(15360), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "key size 2048 is too small for 4 primes")]
+    fn generate_with_too_many_primes() {
+        RSAPrivateKeyPair::new().generate_with(2048, "10001", 4, SEED);
+    }
+
+    #[test]
+    #[should_panic(expected = "public exponent must be odd and at least 3")]
+    fn generate_with_exponent_one() {
+        let mut private_instance = legacy_private_key_pair();
+        private_instance.generate_with(1024, "1", 2, SEED);
+    }
+
+    #[test]
+    #[should_panic(expected = "public exponent must be less than 2^30")]
+    fn generate_with_large_exponent() {
+        let mut private_instance = legacy_private_key_pair();
+        private_instance.generate_with(1024, "40000001", 2, SEED);
+    }
+}
@@ -2,7 +2,6 @@ use sha2::{ Sha256 };
 use wasm_bindgen::prelude::*;
 
 use rand::prelude::*;
-use rand::{ SeedableRng };
 
 use rsa::hash::Hash;
 use rsa::padding::PaddingScheme;
@@ -12,8 +11,37 @@ use rsa::pkcs8::{ ToPrivateKey, ToPublicKey };
 use num_bigint_dig::{ BigUint };
 
 mod utils;
+mod drbg;
+mod hashing;
+mod primitives;
+mod padding;
+mod key_wrap;
+mod der;
+mod x509;
+mod jcs;
+mod cbor;
+#[cfg(test)]
+mod fixtures;
+pub mod keygen;
+pub mod fips;
+pub mod derive;
+pub mod validation;
+pub mod analysis;
+pub mod batch_gcd;
+pub mod policy;
 pub mod public_keys;
 pub mod private_keys;
+pub mod blind_signatures;
+pub mod streaming;
+pub mod cms;
+pub mod envelope;
+pub mod http_signatures;
+pub mod json_signatures;
+pub mod cose;
+pub mod webauthn;
+pub mod paseto;
+pub mod openpgp;
+pub mod age;
 
 #[wasm_bindgen]
 extern "C" {
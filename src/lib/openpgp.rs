@@ -0,0 +1,517 @@
+use super::*;
+use rsa::PublicKey;
+use hashing::HashAlgorithm;
+use primitives::mod_inverse;
+use public_keys::RSAPublicKeyPair;
+use private_keys::RSAPrivateKeyPair;
+use policy::Padding;
+
+const TAG_SIGNATURE: u8 = 2;
+const TAG_SECRET_KEY: u8 = 5;
+const TAG_PUBLIC_KEY: u8 = 6;
+const TAG_USER_ID: u8 = 13;
+
+const KEY_VERSION: u8 = 4;
+const SIGNATURE_VERSION: u8 = 4;
+const ALGORITHM_RSA: u8 = 1;
+
+const SIGNATURE_BINARY: u8 = 0x00;
+const SIGNATURE_POSITIVE_CERTIFICATION: u8 = 0x13;
+
+const SUBPACKET_CREATION_TIME: u8 = 2;
+const SUBPACKET_ISSUER: u8 = 16;
+const SUBPACKET_PREFERRED_HASH: u8 = 21;
+const SUBPACKET_KEY_FLAGS: u8 = 27;
+const SUBPACKET_ISSUER_FINGERPRINT: u8 = 33;
+
+const KEY_FLAG_CERTIFY: u8 = 0x01;
+const KEY_FLAG_SIGN: u8 = 0x02;
+
+fn hash_id(hash: HashAlgorithm) -> u8 {
+    match hash {
+        HashAlgorithm::Sha256 => 8,
+        HashAlgorithm::Sha384 => 9,
+        HashAlgorithm::Sha512 => 10,
+        HashAlgorithm::Sha1 => panic!("sha1 openpgp signatures are not supported")
+    }
+}
+
+fn hash_from_id(id: u8) -> Option<HashAlgorithm> {
+    match id {
+        8 => Some(HashAlgorithm::Sha256),
+        9 => Some(HashAlgorithm::Sha384),
+        10 => Some(HashAlgorithm::Sha512),
+        _ => None
+    }
+}
+
+/// CRC-24 of the ASCII armor checksum, RFC 4880 6.1.
+fn crc24(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xb704ce;
+    for byte in data {
+        crc ^= (*byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= 0x1864cfb;
+            }
+        }
+    }
+
+    crc & 0xffffff
+}
+
+fn armor(label: &str, data: &[u8]) -> String {
+    let mut armored = format!("-----BEGIN PGP {}-----\n\n", label);
+    for line in base64::encode(data).as_bytes().chunks(64) {
+        armored.push_str(std::str::from_utf8(line).unwrap());
+        armored.push('\n');
+    }
+    armored.push_str(&format!("={}\n", base64::encode(&crc24(data).to_be_bytes()[1..])));
+    armored.push_str(&format!("-----END PGP {}-----\n", label));
+    armored
+}
+
+fn dearmor(text: &str) -> Vec<u8> {
+    let mut lines = text.lines().map(|line| line.trim()).skip_while(|line| !line.starts_with("-----BEGIN PGP "));
+    if lines.next().is_none() {
+        panic!("invalid openpgp armor");
+    }
+
+    let mut body = String::new();
+    let mut checksum = None;
+    for line in lines {
+        if line.starts_with("-----END PGP ") {
+            let data = base64::decode(&body).expect("invalid openpgp armor");
+            if let Some(checksum) = checksum {
+                if base64::decode(checksum).ok().as_deref() != Some(&crc24(&data).to_be_bytes()[1..]) {
+                    panic!("invalid openpgp armor checksum");
+                }
+            }
+
+            return data;
+        }
+
+        // Armor headers ("Version: ...") never occur in base64 data.
+        if line.is_empty() || line.contains(':') {
+            continue;
+        }
+        match line.strip_prefix('=') {
+            Some(value) => checksum = Some(value),
+            None => body.push_str(line)
+        }
+    }
+
+    panic!("invalid openpgp armor");
+}
+
+/// New format packet, RFC 4880 4.2.2.
+fn packet(tag: u8, body: &[u8]) -> Vec<u8> {
+    let mut output = vec![0xc0 | tag];
+    let len = body.len();
+    if len < 192 {
+        output.push(len as u8);
+    } else if len < 8384 {
+        output.push((((len - 192) >> 8) + 192) as u8);
+        output.push((len - 192) as u8);
+    } else {
+        output.push(0xff);
+        output.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+    output.extend_from_slice(body);
+    output
+}
+
+fn take<'a>(data: &'a [u8], position: &mut usize, len: usize) -> &'a [u8] {
+    if data.len() - *position < len {
+        panic!("invalid openpgp packet");
+    }
+
+    let bytes = &data[*position..*position + len];
+    *position += len;
+    bytes
+}
+
+fn read_u16(data: &[u8], position: &mut usize) -> usize {
+    let bytes = take(data, position, 2);
+    ((bytes[0] as usize) << 8) | bytes[1] as usize
+}
+
+fn read_packets(data: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut packets = vec![];
+    let mut position = 0;
+    while position < data.len() {
+        let header = take(data, &mut position, 1)[0];
+        if header & 0x80 == 0 {
+            panic!("invalid openpgp packet");
+        }
+
+        let (tag, len) = if header & 0x40 != 0 {
+            let first = take(data, &mut position, 1)[0] as usize;
+            let len = match first {
+                0..=191 => first,
+                192..=223 => ((first - 192) << 8) + take(data, &mut position, 1)[0] as usize + 192,
+                255 => take(data, &mut position, 4).iter().fold(0, |acc, byte| (acc << 8) | *byte as usize),
+                _ => panic!("partial body lengths are not supported")
+            };
+            (header & 0x3f, len)
+        } else {
+            let len = match header & 0x03 {
+                0 => take(data, &mut position, 1)[0] as usize,
+                1 => read_u16(data, &mut position),
+                2 => take(data, &mut position, 4).iter().fold(0, |acc, byte| (acc << 8) | *byte as usize),
+                _ => panic!("indeterminate packet lengths are not supported")
+            };
+            ((header >> 2) & 0x0f, len)
+        };
+        packets.push((tag, take(data, &mut position, len)));
+    }
+
+    packets
+}
+
+/// Multiprecision integer, RFC 4880 3.2.
+fn mpi(value: &BigUint) -> Vec<u8> {
+    let mut output = (value.bits() as u16).to_be_bytes().to_vec();
+    output.extend(value.to_bytes_be());
+    output
+}
+
+fn read_mpi<'a>(data: &'a [u8], position: &mut usize) -> &'a [u8] {
+    let bits = read_u16(data, position);
+    take(data, position, (bits + 7) / 8)
+}
+
+fn public_key_body(key: &impl PublicKeyParts, creation_time: u32) -> Vec<u8> {
+    let mut body = vec![KEY_VERSION];
+    body.extend_from_slice(&creation_time.to_be_bytes());
+    body.push(ALGORITHM_RSA);
+    body.extend(mpi(key.n()));
+    body.extend(mpi(key.e()));
+    body
+}
+
+/// Prefix under which key material is hashed for fingerprints and certifications, RFC 4880 5.2.4.
+fn hashed_public_key(key: &impl PublicKeyParts, creation_time: u32) -> Vec<u8> {
+    let body = public_key_body(key, creation_time);
+    let mut output = vec![0x99];
+    output.extend_from_slice(&(body.len() as u16).to_be_bytes());
+    output.extend(body);
+    output
+}
+
+/// V4 fingerprint, RFC 4880 12.2. The key ID is its low 64 bits.
+pub fn fingerprint(key: &impl PublicKeyParts, creation_time: u32) -> Vec<u8> {
+    HashAlgorithm::Sha1.digest(&hashed_public_key(key, creation_time))
+}
+
+fn subpacket(kind: u8, data: &[u8]) -> Vec<u8> {
+    let mut output = vec![data.len() as u8 + 1, kind];
+    output.extend_from_slice(data);
+    output
+}
+
+fn issuer_subpackets(fingerprint: &[u8], signing_time: u32) -> (Vec<u8>, Vec<u8>) {
+    let mut hashed = subpacket(SUBPACKET_CREATION_TIME, &signing_time.to_be_bytes());
+    hashed.extend(subpacket(SUBPACKET_ISSUER_FINGERPRINT, &[&[KEY_VERSION], fingerprint].concat()));
+    let unhashed = subpacket(SUBPACKET_ISSUER, &fingerprint[12..]);
+    (hashed, unhashed)
+}
+
+fn signature_trailer(hashed_part: &[u8]) -> Vec<u8> {
+    let mut trailer = vec![SIGNATURE_VERSION, 0xff];
+    trailer.extend_from_slice(&(hashed_part.len() as u32).to_be_bytes());
+    trailer
+}
+
+/// V4 signature packet over `prefix`, RFC 4880 5.2.3.
+fn signature_packet(
+    key: &RsaPrivateKey,
+    hash: HashAlgorithm,
+    signature_type: u8,
+    hashed_subpackets: &[u8],
+    unhashed_subpackets: &[u8],
+    prefix: &[u8]
+) -> Vec<u8> {
+    let mut hashed_part = vec![SIGNATURE_VERSION, signature_type, ALGORITHM_RSA, hash_id(hash)];
+    hashed_part.extend_from_slice(&(hashed_subpackets.len() as u16).to_be_bytes());
+    hashed_part.extend_from_slice(hashed_subpackets);
+
+    let digest = hash.digest_parts(&[prefix, &hashed_part, &signature_trailer(&hashed_part)]);
+    let signature = key.sign(PaddingScheme::new_pkcs1v15_sign(Some(hash.pkcs1v15_hash())), &digest).expect("sign error");
+
+    let mut body = hashed_part;
+    body.extend_from_slice(&(unhashed_subpackets.len() as u16).to_be_bytes());
+    body.extend_from_slice(unhashed_subpackets);
+    body.extend_from_slice(&digest[..2]);
+    body.extend(mpi(&BigUint::from_bytes_be(&signature)));
+    packet(TAG_SIGNATURE, &body)
+}
+
+/// User ID packet followed by its positive certification self-signature.
+fn certified_user_id(key: &RsaPrivateKey, user_id: &str, creation_time: u32) -> Vec<u8> {
+    let fingerprint = fingerprint(key, creation_time);
+    let (mut hashed, unhashed) = issuer_subpackets(&fingerprint, creation_time);
+    hashed.extend(subpacket(SUBPACKET_KEY_FLAGS, &[KEY_FLAG_CERTIFY | KEY_FLAG_SIGN]));
+    hashed.extend(subpacket(SUBPACKET_PREFERRED_HASH, &[10, 9, 8]));
+
+    let mut prefix = hashed_public_key(key, creation_time);
+    prefix.push(0xb4);
+    prefix.extend_from_slice(&(user_id.len() as u32).to_be_bytes());
+    prefix.extend_from_slice(user_id.as_bytes());
+
+    let mut output = packet(TAG_USER_ID, user_id.as_bytes());
+    output.extend(signature_packet(key, HashAlgorithm::Sha256, SIGNATURE_POSITIVE_CERTIFICATION, &hashed, &unhashed, &prefix));
+    output
+}
+
+/// Unprotected secret key packet body, RFC 4880 5.5.3, with OpenPGP's `p < q` and `u = p^-1 mod q`.
+fn secret_key_body(key: &RsaPrivateKey, creation_time: u32) -> Vec<u8> {
+    let primes = key.primes();
+    if primes.len() != 2 {
+        panic!("openpgp keys must have exactly two primes");
+    }
+    let (p, q) = if primes[0] < primes[1] { (&primes[0], &primes[1]) } else { (&primes[1], &primes[0]) };
+    let u = mod_inverse(p, q).expect("invalid primes");
+
+    let mut secret = mpi(key.d());
+    secret.extend(mpi(p));
+    secret.extend(mpi(q));
+    secret.extend(mpi(&u));
+    let checksum = secret.iter().fold(0u16, |acc, byte| acc.wrapping_add(*byte as u16));
+
+    let mut body = public_key_body(key, creation_time);
+    body.push(0);
+    body.extend(secret);
+    body.extend_from_slice(&checksum.to_be_bytes());
+    body
+}
+
+fn verify_detached(key: &RSAPublicKeyPair, data: &[u8], signature: &[u8]) -> bool {
+    let body = match read_packets(signature).into_iter().find(|(tag, _)| *tag == TAG_SIGNATURE) {
+        Some((_, body)) => body,
+        None => panic!("no openpgp signature packet")
+    };
+
+    let mut position = 0;
+    let header = take(body, &mut position, 4);
+    if header[0] != SIGNATURE_VERSION || header[1] != SIGNATURE_BINARY || header[2] != ALGORITHM_RSA {
+        return false;
+    }
+    let hash = match hash_from_id(header[3]) {
+        Some(hash) => hash,
+        None => return false
+    };
+    let key = key.verifying_instance(Padding::Pkcs1v15Sign, hash);
+
+    let hashed_len = read_u16(body, &mut position);
+    take(body, &mut position, hashed_len);
+    let hashed_part = &body[..position];
+    let unhashed_len = read_u16(body, &mut position);
+    take(body, &mut position, unhashed_len);
+    let left_bits = take(body, &mut position, 2);
+    let value = read_mpi(body, &mut position);
+
+    let digest = hash.digest_parts(&[data, hashed_part, &signature_trailer(hashed_part)]);
+    if digest[..2] != *left_bits || value.len() > key.size() {
+        return false;
+    }
+
+    let mut padded = vec![0u8; key.size() - value.len()];
+    padded.extend_from_slice(value);
+    key.verify(PaddingScheme::new_pkcs1v15_sign(Some(hash.pkcs1v15_hash())), &digest, &padded).is_ok()
+}
+
+#[wasm_bindgen]
+impl RSAPrivateKeyPair {
+    /// Armored OpenPGP v4 public key with a self-signed user ID.
+    pub fn to_openpgp_public_key(&self, user_id: &str, creation_time: u32) -> String {
+        utils::set_panic_hook();
+        let mut data = packet(TAG_PUBLIC_KEY, &public_key_body(self.instance(), creation_time));
+        data.extend(certified_user_id(self.signing_instance(Padding::Pkcs1v15Sign, HashAlgorithm::Sha256), user_id, creation_time));
+        armor("PUBLIC KEY BLOCK", &data)
+    }
+
+    /// Armored OpenPGP v4 secret key, not protected by a passphrase, with a self-signed user ID.
+    pub fn to_openpgp_secret_key(&self, user_id: &str, creation_time: u32) -> String {
+        utils::set_panic_hook();
+        let mut data = packet(TAG_SECRET_KEY, &secret_key_body(self.instance(), creation_time));
+        data.extend(certified_user_id(self.signing_instance(Padding::Pkcs1v15Sign, HashAlgorithm::Sha256), user_id, creation_time));
+        armor("PRIVATE KEY BLOCK", &data)
+    }
+
+    /// Armored detached binary signature. `key_creation_time` identifies the key as in its export.
+    pub fn sign_openpgp_detached(&self, data: &[u8], hash: &str, key_creation_time: u32, signing_time: u32) -> String {
+        utils::set_panic_hook();
+        let hash = HashAlgorithm::from_name(hash);
+        let key = self.signing_instance(Padding::Pkcs1v15Sign, hash);
+        let fingerprint = fingerprint(key, key_creation_time);
+        let (hashed, unhashed) = issuer_subpackets(&fingerprint, signing_time);
+        let signature = signature_packet(
+            key,
+            hash,
+            SIGNATURE_BINARY,
+            &hashed,
+            &unhashed,
+            data
+        );
+        armor("SIGNATURE", &signature)
+    }
+}
+
+#[wasm_bindgen]
+impl RSAPublicKeyPair {
+    /// Verifies an armored detached binary signature. The issuer is not checked against this key.
+    pub fn verify_openpgp_detached(&self, data: &[u8], signature: &str) -> bool {
+        utils::set_panic_hook();
+        verify_detached(self, data, &dearmor(signature))
+    }
+
+    pub fn openpgp_fingerprint(&self, creation_time: u32) -> String {
+        utils::set_panic_hook();
+        hex::encode(fingerprint(self.instance(), creation_time))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fixtures::key_pairs;
+
+    const CREATION_TIME: u32 = 1650000000;
+
+    #[test]
+    fn crc24_check_value() {
+        assert_eq!(crc24(b""), 0xb704ce);
+        assert_eq!(crc24(b"123456789"), 0x21cf02);
+    }
+
+    #[test]
+    fn armor_round_trip() {
+        let data: Vec<u8> = (0..=255).collect();
+        let armored = armor("SIGNATURE", &data);
+        assert!(armored.starts_with("-----BEGIN PGP SIGNATURE-----\n\n"));
+        assert_eq!(dearmor(&armored.replace("\n\n", "\nVersion: test\n\n")), data);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid openpgp armor checksum")]
+    fn armor_with_wrong_checksum() {
+        let armored = armor("SIGNATURE", b"data");
+        let checksum = armored.lines().find(|line| line.starts_with('=')).unwrap();
+        dearmor(&armored.replace(checksum, "=AAAA"));
+    }
+
+    #[test]
+    fn packet_lengths() {
+        for len in [0usize, 191, 192, 8383, 8384, 70000].iter() {
+            let body = vec![7u8; *len];
+            let packets = [packet(TAG_USER_ID, &body), packet(TAG_SIGNATURE, b"x")].concat();
+            assert_eq!(read_packets(&packets), vec![(TAG_USER_ID, &body[..]), (TAG_SIGNATURE, &b"x"[..])]);
+        }
+    }
+
+    /// Document signed by the fixtures below.
+    const DOCUMENT: &[u8] = b"rsa-lib release 1.0.0\n";
+
+    /// Public key and creation time of the first public key packet of an armored key.
+    fn public_key_from_openpgp(armored: &str) -> (RSAPublicKeyPair, u32) {
+        let data = dearmor(armored);
+        let body = read_packets(&data).into_iter().find(|(tag, _)| *tag == TAG_PUBLIC_KEY).unwrap().1;
+        let mut position = 0;
+        let header = take(body, &mut position, 6);
+        assert_eq!((header[0], header[5]), (KEY_VERSION, ALGORITHM_RSA));
+        let n = hex::encode(read_mpi(body, &mut position));
+        let e = hex::encode(read_mpi(body, &mut position));
+
+        let mut public_instance = RSAPublicKeyPair::new();
+        public_instance.create(&n, &e);
+        (public_instance, u32::from_be_bytes([header[1], header[2], header[3], header[4]]))
+    }
+
+    #[test]
+    fn fingerprint_matches_gnupg() {
+        // gpg --import of to_openpgp_public_key("Release <release@example.com>", CREATION_TIME)
+        // lists fingerprint 994B7F6268AE2C3B0E69439F8BE4E0572F7944B4
+        let (private_instance, public_instance) = key_pairs();
+        assert_eq!(public_instance.openpgp_fingerprint(CREATION_TIME), "994b7f6268ae2c3b0e69439f8be4e0572f7944b4");
+
+        let exported = private_instance.to_openpgp_public_key("Release <release@example.com>", CREATION_TIME);
+        let (imported, creation_time) = public_key_from_openpgp(&exported);
+        assert_eq!(imported.openpgp_fingerprint(creation_time), public_instance.openpgp_fingerprint(CREATION_TIME));
+    }
+
+    #[test]
+    fn verify_gnupg_signature() {
+        // gpg --quick-gen-key "Release Signing <release@example.com>" rsa2048 sign never
+        // gpg --armor --export-options export-minimal --export release@example.com
+        // gpg --armor --digest-algo SHA256 --detach-sign
+        let (public_instance, creation_time) = public_key_from_openpgp(include_str!("testdata/openpgp_gpg_public_key.asc"));
+        assert_eq!(public_instance.openpgp_fingerprint(creation_time), "f08d48e28241ccd5eaadc4fa758e99e520df117c");
+
+        let signature = include_str!("testdata/openpgp_gpg_signature.asc");
+        assert!(public_instance.verify_openpgp_detached(DOCUMENT, signature));
+        assert!(!public_instance.verify_openpgp_detached(b"rsa-lib release 1.0.1\n", signature));
+    }
+
+    #[test]
+    fn signature_verified_by_gnupg() {
+        // gpg --verify reports a good signature from 994B7F6268AE2C3B0E69439F8BE4E0572F7944B4
+        let (private_instance, public_instance) = key_pairs();
+        let signature = private_instance.sign_openpgp_detached(DOCUMENT, "SHA-256", CREATION_TIME, CREATION_TIME + 100);
+        assert_eq!(signature, include_str!("testdata/openpgp_signature.asc"));
+        assert!(public_instance.verify_openpgp_detached(DOCUMENT, &signature));
+    }
+
+    #[test]
+    fn sign_and_verify_detached() {
+        let (private_instance, public_instance) = key_pairs();
+        for hash in ["SHA-256", "SHA-384", "SHA-512"].iter() {
+            let signature = private_instance.sign_openpgp_detached(b"release.tar.gz", hash, CREATION_TIME, CREATION_TIME + 10);
+            assert!(public_instance.verify_openpgp_detached(b"release.tar.gz", &signature), "{}", hash);
+            assert!(!public_instance.verify_openpgp_detached(b"release.tar.gz.1", &signature), "{}", hash);
+        }
+    }
+
+    #[test]
+    fn signature_issuer() {
+        let (private_instance, public_instance) = key_pairs();
+        let fingerprint = hex::decode(public_instance.openpgp_fingerprint(CREATION_TIME)).unwrap();
+        let signature = dearmor(&private_instance.sign_openpgp_detached(b"data", "SHA-256", CREATION_TIME, CREATION_TIME));
+        let packets = read_packets(&signature);
+        assert_eq!(packets.len(), 1);
+
+        let body = packets[0].1;
+        assert_eq!(&body[..4], &[4, SIGNATURE_BINARY, ALGORITHM_RSA, 8]);
+        assert!(body.windows(20).any(|window| window == &fingerprint[..]));
+        assert!(body.windows(8).any(|window| window == &fingerprint[12..]));
+    }
+
+    #[test]
+    fn export_keys() {
+        let (private_instance, public_instance) = key_pairs();
+        let public_key = dearmor(&private_instance.to_openpgp_public_key("Release <release@example.com>", CREATION_TIME));
+        let tags: Vec<u8> = read_packets(&public_key).iter().map(|(tag, _)| *tag).collect();
+        assert_eq!(tags, vec![TAG_PUBLIC_KEY, TAG_USER_ID, TAG_SIGNATURE]);
+        assert_eq!(read_packets(&public_key)[0].1, &public_key_body(public_instance.instance(), CREATION_TIME)[..]);
+
+        let secret_key = dearmor(&private_instance.to_openpgp_secret_key("Release <release@example.com>", CREATION_TIME));
+        let packets = read_packets(&secret_key);
+        assert_eq!(packets[0].0, TAG_SECRET_KEY);
+        assert_eq!(packets[1..], read_packets(&public_key)[1..]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn verify_without_signature_packet() {
+        let (_, public_instance) = key_pairs();
+        public_instance.verify_openpgp_detached(b"data", &armor("SIGNATURE", &packet(TAG_USER_ID, b"user")));
+    }
+
+    #[test]
+    fn verify_certification_as_detached_signature() {
+        let (private_instance, public_instance) = key_pairs();
+        let public_key = private_instance.to_openpgp_public_key("user", CREATION_TIME).replace("PUBLIC KEY BLOCK", "SIGNATURE");
+        assert!(!public_instance.verify_openpgp_detached(b"data", &public_key));
+    }
+}
@@ -0,0 +1,294 @@
+use super::*;
+use super::hashing::HashAlgorithm;
+use super::primitives::{ i2osp, os2ip, rsa_private, rsa_public };
+use subtle::{ Choice, ConditionallySelectable, ConstantTimeEq };
+
+/// RSAES-OAEP encryption from RFC 8017 7.1.1.
+pub fn oaep_encrypt<R: RngCore>(
+    rng: &mut R,
+    key: &impl PublicKeyParts,
+    hash: HashAlgorithm,
+    label: &[u8],
+    message: &[u8]
+) -> Vec<u8> {
+    let k = key.size();
+    let h_len = hash.size();
+    if message.len() + 2 * h_len + 2 > k {
+        panic!("message too long");
+    }
+
+    let mut db = hash.digest(label);
+    db.resize(k - message.len() - h_len - 2, 0);
+    db.push(1);
+    db.extend_from_slice(message);
+
+    let mut seed = vec![0u8; h_len];
+    rng.fill_bytes(&mut seed);
+
+    let db_mask = hash.mgf1(&seed, db.len());
+    for (byte, mask) in db.iter_mut().zip(db_mask) {
+        *byte ^= mask;
+    }
+
+    let seed_mask = hash.mgf1(&db, h_len);
+    for (byte, mask) in seed.iter_mut().zip(seed_mask) {
+        *byte ^= mask;
+    }
+
+    let mut em = vec![0u8];
+    em.extend_from_slice(&seed);
+    em.extend_from_slice(&db);
+
+    i2osp(&rsa_public(key, &os2ip(&em)), k)
+}
+
+/// RSAES-OAEP decryption from RFC 8017 7.1.2.
+pub fn oaep_decrypt(
+    key: &RsaPrivateKey,
+    hash: HashAlgorithm,
+    label: &[u8],
+    ciphertext: &[u8]
+) -> Vec<u8> {
+    try_oaep_decrypt(key, hash, label, ciphertext).expect("decryption error")
+}
+
+/// `oaep_decrypt` returning `None` instead of panicking on a decryption error.
+pub fn try_oaep_decrypt(
+    key: &RsaPrivateKey,
+    hash: HashAlgorithm,
+    label: &[u8],
+    ciphertext: &[u8]
+) -> Option<Vec<u8>> {
+    let k = key.size();
+    let h_len = hash.size();
+    if ciphertext.len() != k || k < 2 * h_len + 2 {
+        return None;
+    }
+
+    let em = i2osp(&rsa_private(key, &os2ip(ciphertext)), k);
+    let mut seed = em[1..h_len + 1].to_vec();
+    let mut db = em[h_len + 1..].to_vec();
+
+    let seed_mask = hash.mgf1(&db, h_len);
+    for (byte, mask) in seed.iter_mut().zip(seed_mask) {
+        *byte ^= mask;
+    }
+
+    let db_mask = hash.mgf1(&seed, db.len());
+    for (byte, mask) in db.iter_mut().zip(db_mask) {
+        *byte ^= mask;
+    }
+
+    // Every check runs over the whole block and is only branched on once, so the timing does not
+    // reveal which one failed (Manger's attack).
+    let label_hash = hash.digest(label);
+    let mut valid = em[0].ct_eq(&0) & db[..h_len].ct_eq(&label_hash[..]);
+    let mut searching = Choice::from(1);
+    let mut separator = 0u32;
+    for (index, byte) in db[h_len..].iter().enumerate() {
+        let (is_zero, is_one) = (byte.ct_eq(&0), byte.ct_eq(&1));
+        separator.conditional_assign(&(index as u32), searching & is_one);
+        valid &= !searching | is_zero | is_one;
+        searching &= is_zero;
+    }
+    valid &= !searching;
+
+    if !bool::from(valid) {
+        return None;
+    }
+
+    Some(db[h_len + separator as usize + 1..].to_vec())
+}
+
+/// EMSA-PSS encoding from RFC 8017 9.1.1, applied to an already computed message hash.
+pub fn emsa_pss_encode(hash: HashAlgorithm, m_hash: &[u8], em_bits: usize, salt: &[u8]) -> Vec<u8> {
+    let h_len = hash.size();
+    let em_len = (em_bits + 7) / 8;
+    if m_hash.len() != h_len || em_len < h_len + salt.len() + 2 {
+        panic!("encoding error");
+    }
+
+    let h = hash.digest_parts(&[&[0u8; 8], m_hash, salt]);
+    let mut db = vec![0u8; em_len - salt.len() - h_len - 2];
+    db.push(1);
+    db.extend_from_slice(salt);
+
+    let db_mask = hash.mgf1(&h, db.len());
+    for (byte, mask) in db.iter_mut().zip(db_mask) {
+        *byte ^= mask;
+    }
+    db[0] &= 0xff >> (8 * em_len - em_bits);
+
+    let mut em = db;
+    em.extend_from_slice(&h);
+    em.push(0xbc);
+    em
+}
+
+/// EMSA-PSS verification from RFC 8017 9.1.2. Without `salt_len` the salt
+/// length is recovered from the encoded message.
+pub fn emsa_pss_verify(
+    hash: HashAlgorithm,
+    m_hash: &[u8],
+    em: &[u8],
+    em_bits: usize,
+    salt_len: Option<usize>
+) -> bool {
+    let h_len = hash.size();
+    let em_len = (em_bits + 7) / 8;
+    if m_hash.len() != h_len || em.len() != em_len || em_len < h_len + 2 || em[em_len - 1] != 0xbc {
+        return false;
+    }
+
+    let top_mask = 0xff >> (8 * em_len - em_bits);
+    let (masked_db, h) = em[..em_len - 1].split_at(em_len - h_len - 1);
+    if masked_db[0] & !top_mask != 0 {
+        return false;
+    }
+
+    let mut db = masked_db.to_vec();
+    let db_mask = hash.mgf1(h, db.len());
+    for (byte, mask) in db.iter_mut().zip(db_mask) {
+        *byte ^= mask;
+    }
+    db[0] &= top_mask;
+
+    let separator = match db.iter().position(|byte| *byte != 0) {
+        Some(index) if db[index] == 1 => index,
+        _ => return false
+    };
+    let salt = &db[separator + 1..];
+    if let Some(len) = salt_len {
+        if salt.len() != len {
+            return false;
+        }
+    }
+
+    hash.digest_parts(&[&[0u8; 8], m_hash, salt]) == h
+}
+
+/// RSASSA-PSS signature generation from RFC 8017 8.1.1.
+pub fn pss_sign(key: &RsaPrivateKey, hash: HashAlgorithm, m_hash: &[u8], salt: &[u8]) -> Vec<u8> {
+    let em = emsa_pss_encode(hash, m_hash, key.n().bits() - 1, salt);
+    i2osp(&rsa_private(key, &os2ip(&em)), key.size())
+}
+
+/// RSASSA-PSS signature verification from RFC 8017 8.1.2.
+pub fn pss_verify(
+    key: &impl PublicKeyParts,
+    hash: HashAlgorithm,
+    m_hash: &[u8],
+    signature: &[u8],
+    salt_len: Option<usize>
+) -> bool {
+    let signature_representative = os2ip(signature);
+    if signature.len() != key.size() || &signature_representative >= key.n() {
+        return false;
+    }
+
+    let em_bits = key.n().bits() - 1;
+    let m = rsa_public(key, &signature_representative);
+    if m.bits() > em_bits {
+        return false;
+    }
+
+    emsa_pss_verify(hash, m_hash, &i2osp(&m, (em_bits + 7) / 8), em_bits, salt_len)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn oaep_round_trip() {
+        let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+        let mut rng = utils::seeded_rng(seed);
+        let key = RsaPrivateKey::new(&mut rng, 1024).unwrap();
+        let ciphertext = oaep_encrypt(&mut rng, &key, HashAlgorithm::Sha256, b"label", b"hello");
+        let message = oaep_decrypt(&key, HashAlgorithm::Sha256, b"label", &ciphertext);
+        assert_eq!(message, b"hello".to_vec());
+    }
+
+    #[test]
+    #[should_panic]
+    fn oaep_decrypt_with_wrong_label() {
+        let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+        let mut rng = utils::seeded_rng(seed);
+        let key = RsaPrivateKey::new(&mut rng, 1024).unwrap();
+        let ciphertext = oaep_encrypt(&mut rng, &key, HashAlgorithm::Sha256, b"label", b"hello");
+        oaep_decrypt(&key, HashAlgorithm::Sha256, b"other", &ciphertext);
+    }
+
+    /// Masks `db` with a zero seed and encrypts it behind `first_byte`, bypassing the checks of `oaep_encrypt`.
+    fn raw_oaep_ciphertext(key: &RsaPrivateKey, first_byte: u8, mut db: Vec<u8>) -> Vec<u8> {
+        let hash = HashAlgorithm::Sha256;
+        let mut seed = vec![0u8; hash.size()];
+        let db_mask = hash.mgf1(&seed, db.len());
+        for (byte, mask) in db.iter_mut().zip(db_mask) {
+            *byte ^= mask;
+        }
+        let seed_mask = hash.mgf1(&db, seed.len());
+        for (byte, mask) in seed.iter_mut().zip(seed_mask) {
+            *byte ^= mask;
+        }
+
+        let mut em = vec![first_byte];
+        em.extend_from_slice(&seed);
+        em.extend_from_slice(&db);
+        i2osp(&rsa_public(key, &os2ip(&em)), key.size())
+    }
+
+    #[test]
+    fn oaep_decrypt_rejects_malformed_blocks() {
+        let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+        let key = RsaPrivateKey::new(&mut utils::seeded_rng(seed), 1024).unwrap();
+        let hash = HashAlgorithm::Sha256;
+        let db_len = key.size() - hash.size() - 1;
+        let block = |separator: usize, value: u8| {
+            let mut db = hash.digest(b"");
+            db.resize(db_len, 0);
+            db[separator] = value;
+            db
+        };
+
+        let empty = raw_oaep_ciphertext(&key, 0, block(db_len - 1, 1));
+        assert_eq!(try_oaep_decrypt(&key, hash, b"", &empty), Some(vec![]));
+        let message = raw_oaep_ciphertext(&key, 0, block(hash.size(), 1));
+        assert_eq!(try_oaep_decrypt(&key, hash, b"", &message).map(|message| message.len()), Some(db_len - hash.size() - 1));
+
+        assert_eq!(try_oaep_decrypt(&key, hash, b"", &raw_oaep_ciphertext(&key, 1, block(db_len - 1, 1))), None);
+        assert_eq!(try_oaep_decrypt(&key, hash, b"", &raw_oaep_ciphertext(&key, 0, block(db_len - 1, 0))), None);
+        assert_eq!(try_oaep_decrypt(&key, hash, b"", &raw_oaep_ciphertext(&key, 0, block(hash.size() + 3, 2))), None);
+        assert_eq!(try_oaep_decrypt(&key, hash, b"label", &empty), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn oaep_encrypt_too_long_message() {
+        let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+        let mut rng = utils::seeded_rng(seed);
+        let key = RsaPrivateKey::new(&mut rng, 1024).unwrap();
+        oaep_encrypt(&mut rng, &key, HashAlgorithm::Sha512, b"", &[0u8; 100]);
+    }
+
+    #[test]
+    fn pss_round_trip() {
+        let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+        let mut rng = utils::seeded_rng(seed);
+        let key = RsaPrivateKey::new(&mut rng, 1025).unwrap();
+        let m_hash = HashAlgorithm::Sha384.digest(b"hello");
+        let signature = pss_sign(&key, HashAlgorithm::Sha384, &m_hash, &[7u8; 48]);
+        assert!(pss_verify(&key, HashAlgorithm::Sha384, &m_hash, &signature, Some(48)));
+        assert!(pss_verify(&key, HashAlgorithm::Sha384, &m_hash, &signature, None));
+        assert!(!pss_verify(&key, HashAlgorithm::Sha384, &m_hash, &signature, Some(0)));
+    }
+
+    #[test]
+    fn pss_verify_with_other_message() {
+        let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+        let mut rng = utils::seeded_rng(seed);
+        let key = RsaPrivateKey::new(&mut rng, 1024).unwrap();
+        let signature = pss_sign(&key, HashAlgorithm::Sha256, &HashAlgorithm::Sha256.digest(b"hello"), &[]);
+        assert!(!pss_verify(&key, HashAlgorithm::Sha256, &HashAlgorithm::Sha256.digest(b"hellO"), &signature, None));
+    }
+}
@@ -0,0 +1,197 @@
+use super::*;
+use hashing::HashAlgorithm;
+use public_keys::RSAPublicKeyPair;
+use private_keys::RSAPrivateKeyPair;
+use policy::Padding;
+
+const HEADER: &str = "v1.public.";
+const KEY_BITS: usize = 2048;
+const SIGNATURE_LEN: usize = KEY_BITS / 8;
+
+/// Pre-Authentication Encoding from the PASETO specification.
+pub fn pae(pieces: &[&[u8]]) -> Vec<u8> {
+    let mut output = (pieces.len() as u64 & i64::MAX as u64).to_le_bytes().to_vec();
+    for piece in pieces {
+        output.extend_from_slice(&(piece.len() as u64 & i64::MAX as u64).to_le_bytes());
+        output.extend_from_slice(piece);
+    }
+
+    output
+}
+
+fn encode_base64(data: &[u8]) -> String {
+    base64::encode_config(data, base64::URL_SAFE_NO_PAD)
+}
+
+/// Unpadded base64url, rejecting anything that does not re-encode to the same text.
+fn decode_base64(data: &str) -> Vec<u8> {
+    let decoded = base64::decode_config(data, base64::URL_SAFE_NO_PAD).expect("invalid paseto token encoding");
+    if encode_base64(&decoded) != data {
+        panic!("invalid paseto token encoding");
+    }
+
+    decoded
+}
+
+fn check_key_size(key: &impl PublicKeyParts) {
+    if key.n().bits() != KEY_BITS {
+        panic!("v1.public requires a {}-bit key", KEY_BITS);
+    }
+}
+
+fn split_token(token: &str) -> (&str, Option<&str>) {
+    let body = token.strip_prefix(HEADER).expect("invalid paseto token header");
+    match body.split_once('.') {
+        Some((payload, footer)) => (payload, Some(footer)),
+        None => (body, None)
+    }
+}
+
+#[wasm_bindgen]
+impl RSAPrivateKeyPair {
+    /// PASETO `v1.public.` token with RSASSA-PSS, SHA-384 and a 48 byte salt. An empty footer is omitted.
+    pub fn encode_paseto_v1(&self, message: &str, footer: &str, random_seed: &str) -> String {
+        utils::set_panic_hook();
+        check_key_size(self.instance());
+
+        let hash = HashAlgorithm::Sha384;
+        let mut salt = vec![0u8; hash.size()];
+        utils::seeded_rng(random_seed).fill_bytes(&mut salt);
+        let m2 = pae(&[HEADER.as_bytes(), message.as_bytes(), footer.as_bytes()]);
+        let signature = padding::pss_sign(self.signing_instance(Padding::Pss, hash), hash, &hash.digest(&m2), &salt);
+
+        let mut token = format!("{}{}", HEADER, encode_base64(&[message.as_bytes(), &signature].concat()));
+        if !footer.is_empty() {
+            token.push('.');
+            token.push_str(&encode_base64(footer.as_bytes()));
+        }
+
+        token
+    }
+}
+
+#[wasm_bindgen]
+impl RSAPublicKeyPair {
+    /// Verifies a `v1.public.` token and returns its message. Panics when the signature or the expected footer does not match.
+    pub fn decode_paseto_v1(&self, token: &str, footer: &str) -> String {
+        utils::set_panic_hook();
+        check_key_size(self.instance());
+
+        let (payload, token_footer) = split_token(token);
+        let token_footer = token_footer.map(decode_base64).unwrap_or_default();
+        let footer_matches = token_footer.len() == footer.len()
+            && token_footer.iter().zip(footer.as_bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0;
+        if !footer_matches {
+            panic!("invalid paseto token footer");
+        }
+
+        let decoded = decode_base64(payload);
+        if decoded.len() < SIGNATURE_LEN {
+            panic!("invalid paseto token");
+        }
+        let (message, signature) = decoded.split_at(decoded.len() - SIGNATURE_LEN);
+
+        let hash = HashAlgorithm::Sha384;
+        let m2 = pae(&[HEADER.as_bytes(), message, &token_footer]);
+        if !padding::pss_verify(self.verifying_instance(Padding::Pss, hash), hash, &hash.digest(&m2), signature, Some(hash.size())) {
+            panic!("invalid paseto token signature");
+        }
+
+        String::from_utf8(message.to_vec()).expect("invalid paseto token message")
+    }
+}
+
+/// Footer of a `v1.public.` token, e.g. to select the key. Does not verify the signature.
+#[wasm_bindgen]
+pub fn paseto_v1_footer(token: &str) -> String {
+    utils::set_panic_hook();
+    let footer = split_token(token).1.map(decode_base64).unwrap_or_default();
+    String::from_utf8(footer).expect("invalid paseto token footer")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fixtures::legacy_private_key_pair;
+    use fixtures::key_pairs;
+
+    const SEED: &str = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+
+    #[test]
+    fn pae_vectors() {
+        assert_eq!(hex::encode(pae(&[])), "0000000000000000");
+        assert_eq!(hex::encode(pae(&[b""])), "01000000000000000000000000000000");
+        assert_eq!(hex::encode(pae(&[b"test"])), "0100000000000000040000000000000074657374");
+    }
+
+    #[test]
+    fn encode_and_decode() {
+        let (private_instance, public_instance) = key_pairs();
+        let message = r#"{"data":"this is a signed message","exp":"2022-01-01T00:00:00+00:00"}"#;
+
+        let token = private_instance.encode_paseto_v1(message, "", SEED);
+        assert!(token.starts_with("v1.public."));
+        assert!(!token[HEADER.len()..].contains('.'));
+        assert_eq!(public_instance.decode_paseto_v1(&token, ""), message);
+
+        let token = private_instance.encode_paseto_v1(message, r#"{"kid":"partner"}"#, SEED);
+        assert_eq!(paseto_v1_footer(&token), r#"{"kid":"partner"}"#);
+        assert_eq!(public_instance.decode_paseto_v1(&token, r#"{"kid":"partner"}"#), message);
+    }
+
+    #[test]
+    fn decode_independent_vectors() {
+        // Tokens signed by pyca/cryptography with PRIVATE_KEY, reusing the message and footer of the
+        // official v1-S-1 and v1-S-2 vectors. These are not the official vectors, which use another key.
+        let vectors: serde_json::Value = serde_json::from_str(include_str!("testdata/paseto_v1.json")).unwrap();
+        let (_, public_instance) = key_pairs();
+        for vector in vectors.as_array().unwrap() {
+            let field = |name: &str| vector[name].as_str().unwrap().to_string();
+            assert_eq!(paseto_v1_footer(&field("token")), field("footer"), "{}", field("name"));
+            assert_eq!(public_instance.decode_paseto_v1(&field("token"), &field("footer")), field("payload"), "{}", field("name"));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid paseto token footer")]
+    fn decode_with_other_footer() {
+        let (private_instance, public_instance) = key_pairs();
+        let token = private_instance.encode_paseto_v1("message", "footer", SEED);
+        public_instance.decode_paseto_v1(&token, "other");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid paseto token signature")]
+    fn decode_with_replaced_footer() {
+        let (private_instance, public_instance) = key_pairs();
+        let token = private_instance.encode_paseto_v1("message", "footer", SEED);
+        let (payload, _) = token.rsplit_once('.').unwrap();
+        public_instance.decode_paseto_v1(&format!("{}.{}", payload, encode_base64(b"other")), "other");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid paseto token signature")]
+    fn decode_tampered_message() {
+        let (private_instance, public_instance) = key_pairs();
+        let token = private_instance.encode_paseto_v1("message", "", SEED);
+        let mut decoded = decode_base64(&token[HEADER.len()..]);
+        decoded[0] ^= 1;
+        public_instance.decode_paseto_v1(&format!("{}{}", HEADER, encode_base64(&decoded)), "");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid paseto token header")]
+    fn decode_other_version() {
+        let (private_instance, public_instance) = key_pairs();
+        let token = private_instance.encode_paseto_v1("message", "", SEED);
+        public_instance.decode_paseto_v1(&token.replacen("v1.", "v2.", 1), "");
+    }
+
+    #[test]
+    #[should_panic(expected = "requires a 2048-bit key")]
+    fn encode_with_small_key() {
+        let mut private_instance = legacy_private_key_pair();
+        private_instance.generate(1024, SEED);
+        private_instance.encode_paseto_v1("message", "", SEED);
+    }
+}
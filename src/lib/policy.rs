@@ -0,0 +1,247 @@
+use super::*;
+use num_traits::Num;
+use hashing::HashAlgorithm;
+
+const ALL_HASHES: [HashAlgorithm; 4] = [HashAlgorithm::Sha1, HashAlgorithm::Sha256, HashAlgorithm::Sha384, HashAlgorithm::Sha512];
+const ALL_PADDINGS: [Padding; 4] = [Padding::Pkcs1v15Encrypt, Padding::Oaep, Padding::Pkcs1v15Sign, Padding::Pss];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Padding {
+    Pkcs1v15Encrypt,
+    Oaep,
+    Pkcs1v15Sign,
+    Pss
+}
+
+impl Padding {
+    pub fn from_name(name: &str) -> Padding {
+        match name {
+            "RSAES-PKCS1-v1_5" => Padding::Pkcs1v15Encrypt,
+            "RSAES-OAEP" => Padding::Oaep,
+            "RSASSA-PKCS1-v1_5" => Padding::Pkcs1v15Sign,
+            "RSASSA-PSS" => Padding::Pss,
+            _ => panic!("unsupported padding {}", name)
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Padding::Pkcs1v15Encrypt => "RSAES-PKCS1-v1_5",
+            Padding::Oaep => "RSAES-OAEP",
+            Padding::Pkcs1v15Sign => "RSASSA-PKCS1-v1_5",
+            Padding::Pss => "RSASSA-PSS"
+        }
+    }
+}
+
+/// Key sizes, exponents, paddings and hashes a key pair accepts. Every generate and import checks
+/// the key, every sign, verify, encrypt and decrypt checks the key, the padding and the hash.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RSAPolicy {
+    min_modulus_bits: usize,
+    /// `None` accepts any exponent the key itself is valid with.
+    exponents: Option<Vec<BigUint>>,
+    paddings: Vec<Padding>,
+    hashes: Vec<HashAlgorithm>,
+    sha1_verification: bool
+}
+
+impl Default for RSAPolicy {
+    fn default() -> Self {
+        RSAPolicy::strict()
+    }
+}
+
+#[wasm_bindgen]
+impl RSAPolicy {
+    /// The default: keys of at least 2048 bits with e = 65537, no RSAES-PKCS1-v1_5 and no SHA-1.
+    pub fn strict() -> RSAPolicy {
+        RSAPolicy {
+            min_modulus_bits: 2048,
+            exponents: Some(vec![BigUint::from(keygen::DEFAULT_EXPONENT)]),
+            paddings: vec![Padding::Oaep, Padding::Pkcs1v15Sign, Padding::Pss],
+            hashes: vec![HashAlgorithm::Sha256, HashAlgorithm::Sha384, HashAlgorithm::Sha512],
+            sha1_verification: false
+        }
+    }
+
+    /// Keys of at least 1024 bits with any exponent, every padding and hash.
+    pub fn legacy() -> RSAPolicy {
+        RSAPolicy {
+            min_modulus_bits: 1024,
+            exponents: None,
+            paddings: ALL_PADDINGS.to_vec(),
+            hashes: ALL_HASHES.to_vec(),
+            sha1_verification: true
+        }
+    }
+
+    /// Policy from JSON with `minModulusBits`, `exponents` (hex, null for any), `paddings`
+    /// (RSAES-PKCS1-v1_5, RSAES-OAEP, RSASSA-PKCS1-v1_5, RSASSA-PSS), `hashes` and `sha1Verification`.
+    /// Missing fields are taken from the strict profile.
+    pub fn from_json(json: &str) -> RSAPolicy {
+        utils::set_panic_hook();
+        let value: serde_json::Value = serde_json::from_str(json).expect("invalid policy");
+        let names = |field: &str| value[field].as_array()
+            .unwrap_or_else(|| panic!("invalid {}", field))
+            .iter()
+            .map(|name| name.as_str().unwrap_or_else(|| panic!("invalid {}", field)).to_string())
+            .collect::<Vec<String>>();
+
+        let mut policy = RSAPolicy::strict();
+        if !value["minModulusBits"].is_null() {
+            policy.min_modulus_bits = value["minModulusBits"].as_u64().expect("invalid minModulusBits") as usize;
+        }
+        match value.get("exponents") {
+            Some(serde_json::Value::Null) => policy.exponents = None,
+            Some(_) => policy.exponents = Some(names("exponents").iter()
+                .map(|e| BigUint::from_str_radix(e, 16).expect("invalid exponents"))
+                .collect()),
+            None => {}
+        }
+        if !value["paddings"].is_null() {
+            policy.paddings = names("paddings").iter().map(|name| Padding::from_name(name)).collect();
+        }
+        if !value["hashes"].is_null() {
+            policy.hashes = names("hashes").iter().map(|name| HashAlgorithm::from_name(name)).collect();
+        }
+        if !value["sha1Verification"].is_null() {
+            policy.sha1_verification = value["sha1Verification"].as_bool().expect("invalid sha1Verification");
+        }
+
+        policy
+    }
+
+    pub fn to_json(&self) -> String {
+        let exponents = self.exponents.as_ref()
+            .map(|exponents| exponents.iter().map(|e| e.to_str_radix(16)).collect::<Vec<String>>());
+
+        serde_json::json!({
+            "minModulusBits": self.min_modulus_bits,
+            "exponents": exponents,
+            "paddings": self.paddings.iter().map(Padding::name).collect::<Vec<&str>>(),
+            "hashes": self.hashes.iter().map(HashAlgorithm::name).collect::<Vec<&str>>(),
+            "sha1Verification": self.sha1_verification
+        }).to_string()
+    }
+}
+
+impl RSAPolicy {
+    pub fn check_key_size(&self, bits: usize) {
+        if bits < self.min_modulus_bits {
+            panic!("key size {} is below the policy minimum of {} bits", bits, self.min_modulus_bits);
+        }
+    }
+
+    pub fn check_exponent(&self, e: &BigUint) {
+        if matches!(&self.exponents, Some(exponents) if !exponents.contains(e)) {
+            panic!("public exponent {} is not allowed by the policy", e.to_str_radix(16));
+        }
+    }
+
+    pub fn check_key(&self, key: &impl PublicKeyParts) {
+        self.check_key_size(key.n().bits());
+        self.check_exponent(key.e());
+    }
+
+    fn check_padding(&self, padding: Padding) {
+        if !self.paddings.contains(&padding) {
+            panic!("padding {} is not allowed by the policy", padding.name());
+        }
+    }
+
+    fn check_hash(&self, hash: HashAlgorithm) {
+        if !self.hashes.contains(&hash) {
+            panic!("hash {} is not allowed by the policy", hash.name());
+        }
+    }
+
+    /// For signing, encryption and decryption. `hash` is the message or OAEP hash, `None` for RSAES-PKCS1-v1_5.
+    pub fn check_operation(&self, key: &impl PublicKeyParts, padding: Padding, hash: Option<HashAlgorithm>) {
+        self.check_key(key);
+        self.check_padding(padding);
+        if let Some(hash) = hash {
+            self.check_hash(hash);
+        }
+    }
+
+    /// `check_operation`, except that SHA-1 passes with `sha1Verification`.
+    pub fn check_verification(&self, key: &impl PublicKeyParts, padding: Padding, hash: HashAlgorithm) {
+        let sha1_allowed = hash == HashAlgorithm::Sha1 && self.sha1_verification;
+        self.check_operation(key, padding, if sha1_allowed { None } else { Some(hash) });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::Value;
+
+    fn key(bits: usize, e: u32) -> RsaPublicKey {
+        let n = (BigUint::from(1u32) << (bits - 1)) + 1u32;
+        RsaPublicKey::new(n, BigUint::from(e)).unwrap()
+    }
+
+    #[test]
+    fn strict_profile() {
+        let policy = RSAPolicy::default();
+        assert_eq!(policy, RSAPolicy::strict());
+        policy.check_operation(&key(2048, 65537), Padding::Pss, Some(HashAlgorithm::Sha256));
+        policy.check_verification(&key(4096, 65537), Padding::Pkcs1v15Sign, HashAlgorithm::Sha512);
+
+        let json: Value = serde_json::from_str(&policy.to_json()).unwrap();
+        assert_eq!(json["minModulusBits"], 2048);
+        assert_eq!(json["exponents"], serde_json::json!(["10001"]));
+        assert_eq!(json["paddings"], serde_json::json!(["RSAES-OAEP", "RSASSA-PKCS1-v1_5", "RSASSA-PSS"]));
+        assert_eq!(json["sha1Verification"], false);
+    }
+
+    #[test]
+    #[should_panic(expected = "key size 1024 is below the policy minimum of 2048 bits")]
+    fn strict_small_key() {
+        RSAPolicy::strict().check_key(&key(1024, 65537));
+    }
+
+    #[test]
+    #[should_panic(expected = "public exponent 3 is not allowed by the policy")]
+    fn strict_small_exponent() {
+        RSAPolicy::strict().check_key(&key(2048, 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "padding RSAES-PKCS1-v1_5 is not allowed by the policy")]
+    fn strict_pkcs1v15_encryption() {
+        RSAPolicy::strict().check_operation(&key(2048, 65537), Padding::Pkcs1v15Encrypt, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "hash SHA-1 is not allowed by the policy")]
+    fn strict_sha1_verification() {
+        RSAPolicy::strict().check_verification(&key(2048, 65537), Padding::Pkcs1v15Sign, HashAlgorithm::Sha1);
+    }
+
+    #[test]
+    fn legacy_profile() {
+        let policy = RSAPolicy::legacy();
+        policy.check_operation(&key(1024, 3), Padding::Pkcs1v15Encrypt, None);
+        policy.check_operation(&key(1024, 65537), Padding::Oaep, Some(HashAlgorithm::Sha1));
+        assert_eq!(RSAPolicy::from_json(&policy.to_json()), policy);
+    }
+
+    #[test]
+    fn custom_policy() {
+        let policy = RSAPolicy::from_json(r#"{"minModulusBits":3072,"exponents":null,"hashes":["SHA-512"],"sha1Verification":true}"#);
+        policy.check_key(&key(3072, 3));
+        policy.check_verification(&key(3072, 65537), Padding::Pss, HashAlgorithm::Sha1);
+        assert_eq!(RSAPolicy::from_json(&policy.to_json()), policy);
+        assert_eq!(RSAPolicy::from_json("{}"), RSAPolicy::strict());
+    }
+
+    #[test]
+    #[should_panic(expected = "hash SHA-1 is not allowed by the policy")]
+    fn sha1_verification_only() {
+        let policy = RSAPolicy::from_json(r#"{"sha1Verification":true}"#);
+        policy.check_operation(&key(2048, 65537), Padding::Pss, Some(HashAlgorithm::Sha1));
+    }
+}
@@ -0,0 +1,187 @@
+use super::*;
+use num_bigint_dig::{ BigInt, ModInverse, RandBigInt, Sign };
+use num_integer::Integer;
+use num_traits::{ One, Zero };
+use zeroize::Zeroizing;
+
+/// Integer-to-octet-string conversion from RFC 8017 4.1.
+pub fn i2osp(value: &BigUint, len: usize) -> Vec<u8> {
+    let bytes = value.to_bytes_be();
+    if bytes.len() > len {
+        panic!("integer too large");
+    }
+
+    let mut result = vec![0u8; len - bytes.len()];
+    result.extend_from_slice(&bytes);
+    result
+}
+
+/// Octet-string-to-integer conversion from RFC 8017 4.2.
+pub fn os2ip(data: &[u8]) -> BigUint {
+    BigUint::from_bytes_be(data)
+}
+
+/// RSAEP / RSAVP1: raw public key operation.
+pub fn rsa_public(key: &impl PublicKeyParts, message: &BigUint) -> BigUint {
+    if message >= key.n() {
+        panic!("message representative out of range");
+    }
+
+    message.modpow(key.e(), key.n())
+}
+
+/// RSADP / RSASP1: private key operation, computed with CRT as in RFC 8017 5.1.2 2.b. The input is
+/// blinded by `r^e` for a random `r` from the platform CSPRNG and the result unblinded by `r^-1`.
+/// `BigUint::modpow` is not constant time, blinding only keeps its timing unrelated to the input.
+pub fn rsa_private(key: &RsaPrivateKey, cipher: &BigUint) -> BigUint {
+    let n = key.n();
+    if cipher >= n {
+        panic!("ciphertext representative out of range");
+    }
+
+    let mut rng = utils::os_rng();
+    let (r, r_inverse) = loop {
+        let r = rng.gen_biguint_below(n);
+        if let Some(r_inverse) = mod_inverse(&r, n).filter(|_| !r.is_zero()) {
+            break (r, r_inverse);
+        }
+    };
+
+    let blinded = cipher * rsa_public(key, &r) % n;
+    let result = crt_private(key, &blinded) * r_inverse % n;
+    if &rsa_public(key, &result) != cipher {
+        panic!("private key operation failed");
+    }
+
+    result
+}
+
+/// `c^d mod n` from the residues `c^(d mod (r_i - 1)) mod r_i`, RFC 8017 5.1.2 2.b.
+fn crt_private(key: &RsaPrivateKey, cipher: &BigUint) -> BigUint {
+    let primes = key.primes();
+    let exponents = crt_exponents(key);
+    let coefficients = crt_coefficients(key);
+    let residues: Vec<BigUint> = primes.iter().zip(exponents.iter())
+        .map(|(prime, exponent)| (cipher % prime).modpow(exponent, prime))
+        .collect();
+
+    let (p, q) = (&primes[0], &primes[1]);
+    let h = (&residues[0] + p - &residues[1] % p) * &coefficients[0] % p;
+    let mut m = &residues[1] + q * h;
+    let mut product = p * q;
+    for ((prime, residue), coefficient) in primes[2..].iter().zip(&residues[2..]).zip(&coefficients[1..]) {
+        let h = (residue + prime - &m % prime) * coefficient % prime;
+        m += &product * h;
+        product *= prime;
+    }
+
+    m
+}
+
+pub fn mod_inverse(value: &BigUint, modulus: &BigUint) -> Option<BigUint> {
+    let inverse = value.clone().mod_inverse(modulus)?;
+    let modulus = BigInt::from_biguint(Sign::Plus, modulus.clone());
+    ((inverse % &modulus + &modulus) % &modulus).to_biguint()
+}
+
+/// CRT exponents `d mod (r_i - 1)` for every prime, RFC 8017 3.2, wiped when dropped. The
+/// temporaries of the division inside `num-bigint-dig` are freed without being wiped.
+pub fn crt_exponents(key: &RsaPrivateKey) -> Zeroizing<Vec<BigUint>> {
+    let one = BigUint::from(1u32);
+    Zeroizing::new(key.primes().iter().map(|prime| key.d() % (prime - &one)).collect())
+}
+
+/// CRT coefficients from RFC 8017 3.2: `qInv = q^-1 mod p` followed by
+/// `t_i = (r_1 * ... * r_(i-1))^-1 mod r_i` for additional primes, wiped when dropped.
+pub fn crt_coefficients(key: &RsaPrivateKey) -> Zeroizing<Vec<BigUint>> {
+    let primes = key.primes();
+    if primes.len() < 2 {
+        panic!("private key has no prime factors");
+    }
+
+    let mut coefficients = vec![mod_inverse(&primes[1], &primes[0]).expect("invalid primes")];
+    let mut product = &primes[0] * &primes[1];
+    for prime in &primes[2..] {
+        coefficients.push(mod_inverse(&product, prime).expect("invalid primes"));
+        product *= prime;
+    }
+
+    Zeroizing::new(coefficients)
+}
+
+/// Factors `p > q` of a two-prime modulus from its exponents, NIST SP 800-56B Appendix C.2.
+/// Tries the bases 2 to 101 in order instead of random ones. `None` when `d` does not match `(n, e)`.
+pub fn recover_primes(n: &BigUint, e: &BigUint, d: &BigUint) -> Option<(BigUint, BigUint)> {
+    let one = BigUint::one();
+    let de = d * e;
+    if *n <= one || de <= one {
+        return None;
+    }
+
+    let k = de - &one;
+    if !(&k % 2u32).is_zero() {
+        return None;
+    }
+
+    let t = k.trailing_zeros()?;
+    let r = &k >> t;
+    let n_minus_one = n - &one;
+    for base in 2u32..102 {
+        let mut y = BigUint::from(base).modpow(&r, n);
+        if y == one || y == n_minus_one {
+            continue;
+        }
+
+        for _ in 0..t {
+            let x = &y * &y % n;
+            if x == one {
+                let p = (&y - &one).gcd(n);
+                let q = n / &p;
+                return Some(if p > q { (p, q) } else { (q, p) });
+            }
+            if x == n_minus_one {
+                break;
+            }
+            y = x;
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SEED: &str = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+
+    #[test]
+    fn private_operation_matches_modpow() {
+        let mut rng = utils::seeded_rng(SEED);
+        let two_primes = RsaPrivateKey::new(&mut rng, 1024).unwrap();
+        let three_primes = keygen::generate(&mut rng, 1536, 3, BigUint::from(keygen::DEFAULT_EXPONENT));
+        for key in [&two_primes, &three_primes] {
+            let cipher = rng.gen_biguint_below(key.n());
+            assert_eq!(rsa_private(key, &cipher), cipher.modpow(key.d(), key.n()));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ciphertext representative out of range")]
+    fn private_operation_out_of_range() {
+        let key = RsaPrivateKey::new(&mut utils::seeded_rng(SEED), 1024).unwrap();
+        rsa_private(&key, key.n());
+    }
+
+    #[test]
+    fn recover_primes_from_degenerate_exponents() {
+        let (zero, one) = (BigUint::zero(), BigUint::one());
+        let n = BigUint::from(3233u32);
+        let e = BigUint::from(17u32);
+        assert_eq!(recover_primes(&n, &e, &BigUint::from(413u32)), Some((BigUint::from(61u32), BigUint::from(53u32))));
+        assert_eq!(recover_primes(&n, &e, &zero), None);
+        assert_eq!(recover_primes(&n, &one, &one), None);
+        assert_eq!(recover_primes(&zero, &e, &BigUint::from(413u32)), None);
+        assert_eq!(recover_primes(&one, &e, &BigUint::from(413u32)), None);
+    }
+}
@@ -2,14 +2,32 @@ use super::*;
 use rsa::pkcs8::FromPrivateKey;
 use sha2::{ Digest };
 use num_traits::{ Num };
-
+use hashing::HashAlgorithm;
+use primitives::{ crt_coefficients, crt_exponents, recover_primes };
+use validation::{ CrtValues, pkcs8_crt_values, validate_key };
+use policy::{ Padding, RSAPolicy };
+use std::fmt;
+use zeroize::Zeroizing;
+
+/// Holds the only copy of the private components, `RsaPrivateKey` zeroizes them when it is
+/// dropped, so on `free()` and when the pair is re-keyed.
 #[wasm_bindgen]
-#[derive(Debug, Clone)]
 pub struct RSAPrivateKeyPair {
     n: String,
-    d: String,
     e: String,
-    private_instance: Option<RsaPrivateKey>
+    private_instance: Option<RsaPrivateKey>,
+    policy: RSAPolicy
+}
+
+impl fmt::Debug for RSAPrivateKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RSAPrivateKeyPair")
+            .field("n", &self.n)
+            .field("e", &self.e)
+            .field("private_instance", &self.private_instance.as_ref().map(|_| "[redacted]"))
+            .field("policy", &self.policy)
+            .finish()
+    }
 }
 
 #[wasm_bindgen]
@@ -19,24 +37,28 @@ impl RSAPrivateKeyPair {
         RSAPrivateKeyPair {
             n: "".to_string(),
             e: "".to_string(),
-            d: "".to_string(),
-            private_instance: None
+            private_instance: None,
+            policy: RSAPolicy::default()
         }
     }
 
+    /// Replaces the strict default policy, checked by every following generate, import and operation.
+    pub fn set_policy(&mut self, policy: &RSAPolicy) {
+        self.policy = policy.clone();
+    }
+
     pub fn generate(&mut self, bits: usize, random_seed: &str) {
         utils::set_panic_hook();
-        let mut seed_array: [u8; 32] = [0; 32];
-        let decode_seed = hex::decode(random_seed).unwrap();
-        seed_array.copy_from_slice(&decode_seed.as_slice());
-        
-        let mut rng: StdRng = SeedableRng::from_seed(seed_array);
+        self.policy.check_key_size(bits);
+        let mut rng = utils::seeded_rng(random_seed);
         let keys = RsaPrivateKey::new(&mut rng, bits).unwrap();
 
-        self.n = keys.n().to_str_radix(16);
-        self.d = keys.d().to_str_radix(16);
-        self.e = keys.e().to_str_radix(16);
-        self.private_instance = Some(keys);
+        self.set_instance(keys);
+    }
+
+    /// `generate` with randomness from the platform CSPRNG.
+    pub fn generate_seedless(&mut self, bits: usize) {
+        self.generate(bits, utils::OS_RANDOM);
     }
 
     pub fn generate_from(&mut self, n: &str, d: &str, e: &str, primes: &str) {
@@ -56,58 +78,113 @@ impl RSAPrivateKeyPair {
             BigUint::from_str_radix(d, 16).expect("invalid d"),
             primes_vec
         );
+        validate_key(&keys, None).expect_valid();
 
-        self.n = keys.n().to_str_radix(16);
-        self.d = keys.d().to_str_radix(16);
-        self.e = keys.e().to_str_radix(16);
-        self.private_instance = Some(keys);
+        self.set_instance(keys);
+    }
+
+    /// Rebuilds a two-prime key from hex `n`, `d` and `e` alone, recovering the primes.
+    pub fn recover_from(&mut self, n: &str, d: &str, e: &str) {
+        utils::set_panic_hook();
+        let n = BigUint::from_str_radix(n, 16).expect("invalid n");
+        let d = BigUint::from_str_radix(d, 16).expect("invalid d");
+        let e = BigUint::from_str_radix(e, 16).expect("invalid e");
+        let (p, q) = recover_primes(&n, &e, &d).expect("failed to recover primes from n, d and e");
+
+        let mut keys = RsaPrivateKey::from_components(n, e, d, vec![p, q]);
+        validate_key(&keys, None).expect_valid();
+        keys.precompute().expect("invalid private key");
+        self.set_instance(keys);
     }
 
     pub fn sign_message(&self, message: &str) -> String {
         utils::set_panic_hook();
         let digest = Sha256::digest(message.as_bytes()).to_vec();
+        let instance = self.signing_instance(Padding::Pkcs1v15Sign, HashAlgorithm::Sha256);
 
-        match &self.private_instance {
-            Some(instance) => {
-                let sign = match instance.sign(
-                    PaddingScheme::new_pkcs1v15_sign(Some(Hash::SHA2_256)),
-                    &digest
-                ) {
-                    Ok(res) => res,
-                    Err(e) => panic!("sign error {}", e)
-                };
-
-                hex::encode(&sign)
-            },
-            None => panic!("Instance not created")
-        }
+        let sign = match instance.sign(
+            PaddingScheme::new_pkcs1v15_sign(Some(Hash::SHA2_256)),
+            &digest
+        ) {
+            Ok(res) => res,
+            Err(e) => panic!("sign error {}", e)
+        };
+
+        hex::encode(&sign)
+    }
+
+    pub fn sign_message_pss(&self, message: &str, hash: &str, random_seed: &str) -> String {
+        utils::set_panic_hook();
+        let hash = HashAlgorithm::from_name(hash);
+        let mut rng = utils::seeded_rng(random_seed);
+        let mut salt = vec![0u8; hash.size()];
+        rng.fill_bytes(&mut salt);
+
+        let sign = padding::pss_sign(self.signing_instance(Padding::Pss, hash), hash, &hash.digest(message.as_bytes()), &salt);
+        hex::encode(&sign)
     }
 
     pub fn decrypt(&self, ciphermessage: &str) -> String {
         utils::set_panic_hook();
         let decode_message = hex::decode(&ciphermessage).expect("invalid decode message");
-        match &self.private_instance {
-            Some(instance) => {
-                let decrypt_message = match instance.decrypt(
-                    PaddingScheme::new_pkcs1v15_encrypt(),
-                    &decode_message
-                ) {
-                    Ok(res) => res,
-                    Err(e) => panic!("decrypt error {}", e)
-                };
-
-                String::from_utf8(decrypt_message).expect("invalid parse decrypt message")
-            },
-            None => panic!("Instance not created")
+        let instance = self.decrypting_instance(Padding::Pkcs1v15Encrypt, None);
+
+        let decrypt_message = match instance.decrypt(
+            PaddingScheme::new_pkcs1v15_encrypt(),
+            &decode_message
+        ) {
+            Ok(res) => res,
+            Err(e) => panic!("decrypt error {}", e)
+        };
+
+        String::from_utf8(decrypt_message).expect("invalid parse decrypt message")
+    }
+
+    pub fn unwrap_key(&self, wrapped_key: &str, hash: &str) -> String {
+        utils::set_panic_hook();
+        let decode_wrapped_key = hex::decode(wrapped_key).expect("invalid decode wrapped key");
+        let hash = HashAlgorithm::from_name(hash);
+        let key = padding::oaep_decrypt(
+            self.decrypting_instance(Padding::Oaep, Some(hash)),
+            hash,
+            &[],
+            &decode_wrapped_key
+        );
+        if ![16, 24, 32].contains(&key.len()) {
+            panic!("invalid key length {}", key.len());
         }
+
+        hex::encode(&key)
+    }
+
+    pub fn unwrap_key_rsa_aes(&self, wrapped_key: &str, hash: &str) -> String {
+        utils::set_panic_hook();
+        let decode_wrapped_key = hex::decode(wrapped_key).expect("invalid decode wrapped key");
+        let hash = HashAlgorithm::from_name(hash);
+        let instance = self.decrypting_instance(Padding::Oaep, Some(hash));
+        let size = instance.size();
+        if decode_wrapped_key.len() <= size {
+            panic!("invalid wrapped key length {}", decode_wrapped_key.len());
+        }
+
+        let aes_key = Zeroizing::new(padding::oaep_decrypt(
+            instance,
+            hash,
+            &[],
+            &decode_wrapped_key[..size]
+        ));
+        let key = key_wrap::aes_key_unwrap_pad(&aes_key, &decode_wrapped_key[size..]);
+
+        hex::encode(&key)
     }
 
     pub fn get_e(&self) -> String {
         self.e.to_string()
     }
 
+    /// Copies `d` out of the key, the returned string is not wiped.
     pub fn get_d(&self) -> String {
-        self.d.to_string()
+        self.private_instance.as_ref().map(|instance| instance.d().to_str_radix(16)).unwrap_or_default()
     }
 
     pub fn get_n(&self) -> String {
@@ -129,6 +206,85 @@ impl RSAPrivateKeyPair {
         }
     }
 
+    /// Same as `get_primes` in hex, like every other key component.
+    pub fn get_primes_hex(&self) -> String {
+        utils::set_panic_hook();
+        match &self.private_instance {
+            Some(instance) => instance.primes().iter().map(|prime| prime.to_str_radix(16)).collect::<Vec<String>>().join("_"),
+            None => panic!("Instance not created")
+        }
+    }
+
+    /// All private key components as an RSA JWK (RFC 7518 6.3.2): base64url `n`, `e`, `d`, `p`, `q`,
+    /// `dp`, `dq`, `qi` and `oth` with `r`, `d`, `t` for each additional prime. The CRT values are
+    /// wiped once encoded, the returned string is not.
+    pub fn get_crt_components(&self) -> String {
+        utils::set_panic_hook();
+        let instance = self.instance();
+        let primes = instance.primes();
+        let exponents = crt_exponents(instance);
+        let coefficients = crt_coefficients(instance);
+
+        let mut components = serde_json::json!({
+            "kty": "RSA",
+            "n": jwk_value(instance.n()),
+            "e": jwk_value(instance.e()),
+            "d": jwk_value(instance.d()),
+            "p": jwk_value(&primes[0]),
+            "q": jwk_value(&primes[1]),
+            "dp": jwk_value(&exponents[0]),
+            "dq": jwk_value(&exponents[1]),
+            "qi": jwk_value(&coefficients[0])
+        });
+        if primes.len() > 2 {
+            components["oth"] = (2..primes.len()).map(|index| serde_json::json!({
+                "r": jwk_value(&primes[index]),
+                "d": jwk_value(&exponents[index]),
+                "t": jwk_value(&coefficients[index - 1])
+            })).collect();
+        }
+
+        components.to_string()
+    }
+
+    /// Imports an RSA JWK as returned by `get_crt_components`. Panics unless the key is valid and
+    /// every CRT exponent and coefficient matches the one computed from the primes.
+    pub fn from_crt_components(&mut self, components: &str) {
+        utils::set_panic_hook();
+        let components: serde_json::Value = serde_json::from_str(components).expect("invalid crt components");
+        if components["kty"] != "RSA" {
+            panic!("invalid kty");
+        }
+        let component = |value: &serde_json::Value, name: &str| {
+            let encoded = value[name].as_str().unwrap_or_else(|| panic!("missing {}", name));
+            let bytes = base64::decode_config(encoded, base64::URL_SAFE_NO_PAD).unwrap_or_else(|_| panic!("invalid {}", name));
+            BigUint::from_bytes_be(&bytes)
+        };
+
+        let other_primes = match &components["oth"] {
+            serde_json::Value::Null => vec![],
+            value => value.as_array().expect("invalid oth").clone()
+        };
+        let mut primes = vec![component(&components, "p"), component(&components, "q")];
+        let mut exponents = vec![component(&components, "dp"), component(&components, "dq")];
+        let mut coefficients = vec![component(&components, "qi")];
+        for other_prime in &other_primes {
+            primes.push(component(other_prime, "r"));
+            exponents.push(component(other_prime, "d"));
+            coefficients.push(component(other_prime, "t"));
+        }
+
+        let keys = RsaPrivateKey::from_components(
+            component(&components, "n"),
+            component(&components, "e"),
+            component(&components, "d"),
+            primes
+        );
+        validate_key(&keys, Some(&CrtValues { exponents, coefficients })).expect_valid();
+
+        self.set_instance(keys);
+    }
+
     pub fn to_pkcs8_pem(&self) -> String {
         utils::set_panic_hook();
         match &self.private_instance {
@@ -143,16 +299,56 @@ impl RSAPrivateKeyPair {
     pub fn from_pkcs8_pem(&mut self, data: &str) {
         utils::set_panic_hook();
         let keys = RsaPrivateKey::from_pkcs8_pem(data).expect("failed to parse private key");
+        validate_key(&keys, Some(&pkcs8_crt_values(&der::from_pem_or_hex(data)))).expect_valid();
+        self.set_instance(keys);
+    }
+}
+
+impl RSAPrivateKeyPair {
+    pub(crate) fn instance(&self) -> &RsaPrivateKey {
+        match &self.private_instance {
+            Some(instance) => instance,
+            None => panic!("Instance not created")
+        }
+    }
+
+    pub(crate) fn policy(&self) -> &RSAPolicy {
+        &self.policy
+    }
+
+    /// The key, once the policy allows signing with it, `padding` and `hash`.
+    pub(crate) fn signing_instance(&self, padding: Padding, hash: HashAlgorithm) -> &RsaPrivateKey {
+        let instance = self.instance();
+        self.policy.check_operation(instance, padding, Some(hash));
+        instance
+    }
+
+    /// The key, once the policy allows decrypting with it, `padding` and the OAEP `hash`.
+    pub(crate) fn decrypting_instance(&self, padding: Padding, hash: Option<HashAlgorithm>) -> &RsaPrivateKey {
+        let instance = self.instance();
+        self.policy.check_operation(instance, padding, hash);
+        instance
+    }
+
+    /// Panics unless the policy allows `keys`.
+    pub(crate) fn set_instance(&mut self, keys: RsaPrivateKey) {
+        self.policy.check_key(&keys);
         self.n = keys.n().to_str_radix(16);
-        self.d = keys.d().to_str_radix(16);
         self.e = keys.e().to_str_radix(16);
         self.private_instance = Some(keys);
     }
 }
 
+/// Unpadded base64url of the big-endian bytes, the JWK encoding of RFC 7518 6.3.
+fn jwk_value(value: &BigUint) -> String {
+    base64::encode_config(value.to_bytes_be(), base64::URL_SAFE_NO_PAD)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use fixtures::legacy_private_key_pair;
+    use fixtures::legacy_public_key_pair;
 
     mod generate {
         use super::*;
@@ -160,7 +356,7 @@ mod test {
         #[test]
         fn generate_private_keys() {
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut instance = RSAPrivateKeyPair::new();
+            let mut instance = legacy_private_key_pair();
             instance.generate(1024, seed);
             assert_ne!(instance.get_d(), "".to_string());
             assert_ne!(instance.get_e(), "".to_string());
@@ -170,15 +366,24 @@ mod test {
         #[test]
         #[should_panic]
         fn generate_private_keys_with_empty_seed() {
-            let mut instance = RSAPrivateKeyPair::new();
+            let mut instance = legacy_private_key_pair();
             instance.generate(1024, "");
         }
     
+        #[test]
+        fn generate_private_keys_seedless() {
+            let mut instance = legacy_private_key_pair();
+            let mut other_instance = legacy_private_key_pair();
+            instance.generate_seedless(1024);
+            other_instance.generate_seedless(1024);
+            assert_ne!(instance.get_n(), other_instance.get_n());
+        }
+    
         #[test]
         #[should_panic]
         fn generate_private_keys_with_zero_bits() {
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut instance = RSAPrivateKeyPair::new();
+            let mut instance = legacy_private_key_pair();
             instance.generate(0, seed);
         }
     }
@@ -189,10 +394,10 @@ mod test {
         #[test]
         fn generate_rsa_private_from() {
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut first_instance = RSAPrivateKeyPair::new();
+            let mut first_instance = legacy_private_key_pair();
             first_instance.generate(1024, seed);
     
-            let mut second_instance = RSAPrivateKeyPair::new();
+            let mut second_instance = legacy_private_key_pair();
             second_instance.generate_from(
                 &first_instance.get_n(),
                 &first_instance.get_d(),
@@ -206,14 +411,29 @@ mod test {
             assert_eq!(first_instance.get_primes(), second_instance.get_primes());
         }
     
+        #[test]
+        fn get_primes_in_decimal_and_hex() {
+            let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+            let mut instance = legacy_private_key_pair();
+            instance.generate(1024, seed);
+
+            let primes = instance.get_primes();
+            let primes_hex = instance.get_primes_hex();
+            assert!(primes.chars().all(|c| c.is_ascii_digit() || c == '_'));
+            for (decimal, hex) in primes.split('_').zip(primes_hex.split('_')) {
+                assert_eq!(BigUint::from_str_radix(decimal, 10).unwrap(), BigUint::from_str_radix(hex, 16).unwrap());
+            }
+            assert_eq!(primes.split('_').count(), primes_hex.split('_').count());
+        }
+    
         #[test]
         #[should_panic]
         fn generate_rsa_private_from_without_n() {
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut first_instance = RSAPrivateKeyPair::new();
+            let mut first_instance = legacy_private_key_pair();
             first_instance.generate(1024, seed);
     
-            let mut second_instance = RSAPrivateKeyPair::new();
+            let mut second_instance = legacy_private_key_pair();
             second_instance.generate_from(
                 "",
                 &first_instance.get_d(),
@@ -226,10 +446,10 @@ mod test {
         #[should_panic]
         fn generate_rsa_private_from_without_d() {
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut first_instance = RSAPrivateKeyPair::new();
+            let mut first_instance = legacy_private_key_pair();
             first_instance.generate(1024, seed);
     
-            let mut second_instance = RSAPrivateKeyPair::new();
+            let mut second_instance = legacy_private_key_pair();
             second_instance.generate_from(
                 &first_instance.get_n(),
                 "",
@@ -242,10 +462,10 @@ mod test {
         #[should_panic]
         fn generate_rsa_private_from_without_e() {
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut first_instance = RSAPrivateKeyPair::new();
+            let mut first_instance = legacy_private_key_pair();
             first_instance.generate(1024, seed);
     
-            let mut second_instance = RSAPrivateKeyPair::new();
+            let mut second_instance = legacy_private_key_pair();
             second_instance.generate_from(
                 &first_instance.get_n(),
                 &first_instance.get_d(),
@@ -258,10 +478,10 @@ mod test {
         #[should_panic]
         fn generate_rsa_private_from_without_primes() {
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut first_instance = RSAPrivateKeyPair::new();
+            let mut first_instance = legacy_private_key_pair();
             first_instance.generate(1024, seed);
     
-            let mut second_instance = RSAPrivateKeyPair::new();
+            let mut second_instance = legacy_private_key_pair();
             second_instance.generate_from(
                 &first_instance.get_n(),
                 &first_instance.get_d(),
@@ -302,7 +522,7 @@ khSri8NDQTao0i43teKIMA==
 -----END PRIVATE KEY-----
 ";
 
-            let mut instance = RSAPrivateKeyPair::new();
+            let mut instance = legacy_private_key_pair();
             instance.from_pkcs8_pem(expected_pem);
             let actual_pem = instance.to_pkcs8_pem();
             assert_eq!(expected_pem, actual_pem)
@@ -311,7 +531,7 @@ khSri8NDQTao0i43teKIMA==
         #[test]
         fn generate_pem_from_new_keys() {
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut private_instance = RSAPrivateKeyPair::new();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             let private_pem = private_instance.to_pkcs8_pem();
             assert_ne!(private_pem, "".to_string());
@@ -327,11 +547,56 @@ khSri8NDQTao0i43teKIMA==
         #[should_panic]
         fn failed_create_instance_from_pem() {
             let invalid_pem = "invalid_pem";
-            let mut instance = RSAPrivateKeyPair::new();
+            let mut instance = legacy_private_key_pair();
             instance.from_pkcs8_pem(invalid_pem);
         }
     }
 
+    mod recover_from {
+        use super::*;
+        use fixtures::PRIVATE_KEY;
+
+        #[test]
+        fn recover_private_key() {
+            let mut instance = RSAPrivateKeyPair::new();
+            instance.from_pkcs8_pem(PRIVATE_KEY);
+            let mut recovered = legacy_private_key_pair();
+            recovered.recover_from(&instance.get_n(), &instance.get_d(), &instance.get_e());
+
+            let mut primes = instance.get_primes().split('_').map(String::from).collect::<Vec<String>>();
+            let mut recovered_primes = recovered.get_primes().split('_').map(String::from).collect::<Vec<String>>();
+            primes.sort();
+            recovered_primes.sort();
+            assert_eq!(recovered_primes, primes);
+            assert_eq!(recovered.sign_message("message"), instance.sign_message("message"));
+        }
+
+        #[test]
+        fn recover_private_key_with_lambda_exponent() {
+            use num_integer::Integer;
+
+            let mut instance = RSAPrivateKeyPair::new();
+            instance.from_pkcs8_pem(PRIVATE_KEY);
+            let primes = instance.instance().primes();
+            let lambda = (&primes[0] - 1u32).lcm(&(&primes[1] - 1u32));
+            let d = primitives::mod_inverse(instance.instance().e(), &lambda).unwrap();
+
+            let mut recovered = legacy_private_key_pair();
+            recovered.recover_from(&instance.get_n(), &d.to_str_radix(16), &instance.get_e());
+            assert_eq!(recovered.get_d(), d.to_str_radix(16));
+            assert_eq!(&recovered.instance().primes()[0], primes.iter().max().unwrap());
+        }
+
+        #[test]
+        #[should_panic(expected = "failed to recover primes")]
+        fn recover_with_other_d() {
+            let mut instance = RSAPrivateKeyPair::new();
+            instance.from_pkcs8_pem(PRIVATE_KEY);
+            let other_d = (instance.instance().d() + 2u32).to_str_radix(16);
+            RSAPrivateKeyPair::new().recover_from(&instance.get_n(), &other_d, &instance.get_e());
+        }
+    }
+
     mod sign {
         use super::*;
 
@@ -339,7 +604,7 @@ khSri8NDQTao0i43teKIMA==
         fn sign_message() {
             let message = "Hello";
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut instance = RSAPrivateKeyPair::new();
+            let mut instance = legacy_private_key_pair();
             instance.generate(1024, seed);
             assert_ne!(instance.sign_message(message), "".to_string());
         }
@@ -348,7 +613,7 @@ khSri8NDQTao0i43teKIMA==
         fn sign_message_with_long_data() {
             let message = "{ id: 'c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7, id2: c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7, id3: c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7, id4: c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7' }";
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut instance = RSAPrivateKeyPair::new();
+            let mut instance = legacy_private_key_pair();
             instance.generate(1024, seed);
             let signature = instance.sign_message(message);
             assert_ne!(signature, "".to_string());
@@ -362,18 +627,39 @@ khSri8NDQTao0i43teKIMA==
             instance.sign_message(message);
         }
     
+        #[test]
+        fn sign_message_pss() {
+            let message = "Hello";
+            let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+            let mut public_instance = legacy_public_key_pair();
+            let mut instance = legacy_private_key_pair();
+            instance.generate(1024, seed);
+            public_instance.create(&instance.get_n(), &instance.get_e());
+            let signature = instance.sign_message_pss(message, "SHA-384", seed);
+            assert!(public_instance.verify_message_pss(message, &signature, "SHA-384"));
+            assert!(!public_instance.verify_message_pss("hello", &signature, "SHA-384"));
+            assert!(!public_instance.verify_message_pss(message, &signature, "SHA-256"));
+        }
+
+        #[test]
+        #[should_panic]
+        fn sign_message_pss_without_keys() {
+            let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+            RSAPrivateKeyPair::new().sign_message_pss("Hello", "SHA-256", seed);
+        }
+
         #[test]
         fn sign_message_with_generate_keys_from_data() {
             let message = "hello";
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut private_instance = RSAPrivateKeyPair::new();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             let n = private_instance.get_n();
             let e = private_instance.get_e();
             let d = private_instance.get_d();
             let primes = private_instance.get_primes();
     
-            private_instance = RSAPrivateKeyPair::new();
+            private_instance = legacy_private_key_pair();
             private_instance.generate_from(&n, &d, &e, &primes);
             let signature = private_instance.sign_message(message);
             assert_ne!(signature, "".to_string())
@@ -387,8 +673,8 @@ khSri8NDQTao0i43teKIMA==
         fn decrypt_message() {
             let message = "hello";
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut public_instance = public_keys::RSAPublicKeyPair::new();
-            let mut private_instance = RSAPrivateKeyPair::new();
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             public_instance.create(&private_instance.get_n(), &private_instance.get_e());
             let encrypted_message = public_instance.encrypt(message, seed);
@@ -400,7 +686,7 @@ khSri8NDQTao0i43teKIMA==
         #[should_panic]
         fn decrypt_message_with_invalid_message() {
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut private_instance = RSAPrivateKeyPair::new();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             private_instance.decrypt("");
         }
@@ -410,12 +696,12 @@ khSri8NDQTao0i43teKIMA==
         fn decrypt_message_without_keys() {
             let message = "hello";
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut public_instance = public_keys::RSAPublicKeyPair::new();
-            let mut private_instance = RSAPrivateKeyPair::new();
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             public_instance.create(&private_instance.get_n(), &private_instance.get_e());
             let encrypted_message = public_instance.encrypt(message, seed);
-            private_instance = RSAPrivateKeyPair::new();
+            private_instance = legacy_private_key_pair();
             private_instance.decrypt(&encrypted_message);
         }
     
@@ -423,8 +709,8 @@ khSri8NDQTao0i43teKIMA==
         fn decrypt_message_with_generate_keys_from_data() {
             let message = "hello";
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut public_instance = public_keys::RSAPublicKeyPair::new();
-            let mut private_instance = RSAPrivateKeyPair::new();
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             let n = private_instance.get_n();
             let e = private_instance.get_e();
@@ -433,9 +719,143 @@ khSri8NDQTao0i43teKIMA==
     
             public_instance.create(&n, &e);
             let encrypted_message = public_instance.encrypt(message, seed);
-            private_instance = RSAPrivateKeyPair::new();
+            private_instance = legacy_private_key_pair();
             private_instance.generate_from(&n, &d, &e, &primes);
             private_instance.decrypt(&encrypted_message);
         }
     }
-}
\ No newline at end of file
+    mod unwrap_key {
+        use super::*;
+
+        #[test]
+        fn wrap_and_unwrap_key() {
+            let key = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+            let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
+            private_instance.generate(1024, seed);
+            public_instance.create(&private_instance.get_n(), &private_instance.get_e());
+            let wrapped_key = public_instance.wrap_key(key, "SHA-256", seed);
+            assert_eq!(private_instance.unwrap_key(&wrapped_key, "SHA-256"), key);
+        }
+
+        #[test]
+        #[should_panic]
+        fn unwrap_key_with_other_hash() {
+            let key = "000102030405060708090a0b0c0d0e0f";
+            let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
+            private_instance.generate(1024, seed);
+            public_instance.create(&private_instance.get_n(), &private_instance.get_e());
+            let wrapped_key = public_instance.wrap_key(key, "SHA-256", seed);
+            private_instance.unwrap_key(&wrapped_key, "SHA-1");
+        }
+
+        #[test]
+        fn wrap_and_unwrap_key_rsa_aes() {
+            let key = "00112233445566778899aabbccddeeff0011223344";
+            let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
+            private_instance.generate(1024, seed);
+            public_instance.create(&private_instance.get_n(), &private_instance.get_e());
+            let wrapped_key = public_instance.wrap_key_rsa_aes(key, 256, "SHA-1", seed);
+            assert_eq!(private_instance.unwrap_key_rsa_aes(&wrapped_key, "SHA-1"), key);
+        }
+
+        #[test]
+        #[should_panic]
+        fn unwrap_key_without_keys() {
+            RSAPrivateKeyPair::new().unwrap_key("00", "SHA-256");
+        }
+    }
+
+    mod crt_components {
+        use super::*;
+        use serde_json::Value;
+        use fixtures::PRIVATE_KEY;
+
+        fn components(instance: &RSAPrivateKeyPair) -> Value {
+            serde_json::from_str(&instance.get_crt_components()).unwrap()
+        }
+
+        fn hex_component(components: &Value, name: &str) -> String {
+            let bytes = base64::decode_config(components[name].as_str().unwrap(), base64::URL_SAFE_NO_PAD).unwrap();
+            BigUint::from_bytes_be(&bytes).to_str_radix(16)
+        }
+
+        #[test]
+        fn get_crt_components() {
+            let mut instance = RSAPrivateKeyPair::new();
+            instance.from_pkcs8_pem(PRIVATE_KEY);
+            let components = components(&instance);
+            assert_eq!(components["kty"], "RSA");
+            assert_eq!(components["e"], "AQAB");
+            assert_eq!(hex_component(&components, "n"), instance.get_n());
+            assert!(hex_component(&components, "dp").starts_with("47a37cdd796aa215abf23de0f6970bc5f18053fd"));
+            assert!(hex_component(&components, "dq").starts_with("d84aa26498e83c58457d68c8d8f782fa36409043"));
+            assert!(hex_component(&components, "qi").starts_with("304735f3b2b18ad87a64111dec117a248727431d"));
+            assert!(components.get("oth").is_none());
+
+            let mut imported = legacy_private_key_pair();
+            imported.from_crt_components(&components.to_string());
+            assert_eq!(imported.to_pkcs8_pem(), instance.to_pkcs8_pem());
+        }
+
+        #[test]
+        fn multi_prime_crt_components() {
+            let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+            let mut instance = legacy_private_key_pair();
+            instance.generate_with(1024, "10001", 3, seed);
+            let components = components(&instance);
+            assert_eq!(components["oth"].as_array().unwrap().len(), 1);
+
+            let mut imported = legacy_private_key_pair();
+            imported.from_crt_components(&components.to_string());
+            assert_eq!(imported.get_primes(), instance.get_primes());
+            assert_eq!(imported.get_d(), instance.get_d());
+        }
+
+        #[test]
+        #[should_panic(expected = "invalid private key: crtValues")]
+        fn from_inconsistent_crt_components() {
+            let mut instance = RSAPrivateKeyPair::new();
+            instance.from_pkcs8_pem(PRIVATE_KEY);
+            let mut components = components(&instance);
+            components["qi"] = Value::from("AQ");
+            RSAPrivateKeyPair::new().from_crt_components(&components.to_string());
+        }
+
+        #[test]
+        #[should_panic(expected = "invalid dq")]
+        fn from_hex_crt_components() {
+            let mut instance = RSAPrivateKeyPair::new();
+            instance.from_pkcs8_pem(PRIVATE_KEY);
+            let mut components = components(&instance);
+            components["dq"] = Value::from("d84aa26498e83c58457d68c8d8f782fa3640904+");
+            RSAPrivateKeyPair::new().from_crt_components(&components.to_string());
+        }
+
+        #[test]
+        #[should_panic(expected = "missing dq")]
+        fn from_crt_components_without_dq() {
+            let mut instance = RSAPrivateKeyPair::new();
+            instance.from_pkcs8_pem(PRIVATE_KEY);
+            let mut components = components(&instance);
+            components.as_object_mut().unwrap().remove("dq");
+            RSAPrivateKeyPair::new().from_crt_components(&components.to_string());
+        }
+    }
+
+    #[test]
+    fn debug_redacts_private_components() {
+        let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+        let mut instance = legacy_private_key_pair();
+        instance.generate(1024, seed);
+        let debug = format!("{:?}", instance);
+        assert!(debug.contains(&instance.get_n()));
+        assert!(!debug.contains(&instance.get_d()));
+        assert!(!debug.contains(&instance.instance().primes()[0].to_str_radix(16)));
+    }
+}
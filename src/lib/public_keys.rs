@@ -1,13 +1,17 @@
 use super::*;
 use sha2::{ Digest };
 use rsa::{ PublicKey, pkcs8::FromPublicKey };
+use hashing::HashAlgorithm;
+use policy::{ Padding, RSAPolicy };
+use zeroize::Zeroizing;
 
 #[wasm_bindgen]
 #[derive(Debug, Clone)]
 pub struct RSAPublicKeyPair {
     n: String,
     e: String,
-    public_instance: Option<RsaPublicKey>
+    public_instance: Option<RsaPublicKey>,
+    policy: RSAPolicy
 }
 
 #[wasm_bindgen]
@@ -17,10 +21,16 @@ impl RSAPublicKeyPair {
         RSAPublicKeyPair {
             n: "".to_string(),
             e: "".to_string(),
-            public_instance: None
+            public_instance: None,
+            policy: RSAPolicy::default()
         }
     }
 
+    /// Replaces the strict default policy, checked by every following import and operation.
+    pub fn set_policy(&mut self, policy: &RSAPolicy) {
+        self.policy = policy.clone();
+    }
+
     pub fn create(&mut self, n: &str, e: &str) {
         utils::set_panic_hook();
         let bn_e = BigUint::parse_bytes(e.as_bytes(), 16).expect("invalid e");
@@ -28,52 +38,100 @@ impl RSAPublicKeyPair {
 
         let pub_keys = RsaPublicKey::new(bn_n, bn_e).expect("invalid create public instance");
 
-        self.n = pub_keys.n().to_str_radix(16);
-        self.e = pub_keys.e().to_str_radix(16);
-        self.public_instance = Some(pub_keys);
+        self.set_instance(pub_keys);
     }
 
     pub fn encrypt(&self, message: &str, random_seed: &str) -> String {
         utils::set_panic_hook();
-        let mut seed_array: [u8; 32] = [0; 32];
-        let decode_seed = hex::decode(random_seed).expect("invalid decode");
-        seed_array.copy_from_slice(&decode_seed.as_slice());
-        
-        let mut rng: StdRng = SeedableRng::from_seed(seed_array);
-        match &self.public_instance {
-            Some(instance) => {
-                let encrypt_message = match instance.encrypt(
-                    &mut rng,
-                    PaddingScheme::new_pkcs1v15_encrypt(),
-                    message.as_bytes()
-                ) {
-                    Ok(res) => res,
-                    Err(e) => panic!("encrypt error {}", e)
-                };
-
-                hex::encode(&encrypt_message)
-            },
-            None => panic!("Instance not created")
-        }
+        let mut rng = utils::seeded_rng(random_seed);
+        let instance = self.encrypting_instance(Padding::Pkcs1v15Encrypt, None);
+
+        let encrypt_message = match instance.encrypt(
+            &mut rng,
+            PaddingScheme::new_pkcs1v15_encrypt(),
+            message.as_bytes()
+        ) {
+            Ok(res) => res,
+            Err(e) => panic!("encrypt error {}", e)
+        };
+
+        hex::encode(&encrypt_message)
+    }
+
+    /// `encrypt` with randomness from the platform CSPRNG.
+    pub fn encrypt_seedless(&self, message: &str) -> String {
+        self.encrypt(message, utils::OS_RANDOM)
     }
 
     pub fn verify_message(&self, message: &str, signature: &str) -> bool {
         utils::set_panic_hook();
         let decode_signature = hex::decode(signature).unwrap();
         let hash_mess = Sha256::digest(message.as_bytes());
-        
-        if let Some(instance) = &self.public_instance {
-            return match instance.verify(
-                PaddingScheme::new_pkcs1v15_sign(Some(Hash::SHA2_256)),
-                &hash_mess,
-                &decode_signature
-            ) {
-                Ok(_) => true,
-                Err(_) => false
-            }
+        let instance = self.verifying_instance(Padding::Pkcs1v15Sign, HashAlgorithm::Sha256);
+
+        instance.verify(
+            PaddingScheme::new_pkcs1v15_sign(Some(Hash::SHA2_256)),
+            &hash_mess,
+            &decode_signature
+        ).is_ok()
+    }
+
+    pub fn verify_message_pss(&self, message: &str, signature: &str, hash: &str) -> bool {
+        utils::set_panic_hook();
+        let decode_signature = hex::decode(signature).expect("invalid decode signature");
+        let hash = HashAlgorithm::from_name(hash);
+
+        padding::pss_verify(
+            self.verifying_instance(Padding::Pss, hash),
+            hash,
+            &hash.digest(message.as_bytes()),
+            &decode_signature,
+            None
+        )
+    }
+
+    pub fn wrap_key(&self, key: &str, hash: &str, random_seed: &str) -> String {
+        utils::set_panic_hook();
+        let decode_key = hex::decode(key).expect("invalid decode key");
+        if ![16, 24, 32].contains(&decode_key.len()) {
+            panic!("invalid key length {}", decode_key.len());
+        }
+
+        let mut rng = utils::seeded_rng(random_seed);
+        let hash = HashAlgorithm::from_name(hash);
+        let wrapped_key = padding::oaep_encrypt(
+            &mut rng,
+            self.encrypting_instance(Padding::Oaep, Some(hash)),
+            hash,
+            &[],
+            &decode_key
+        );
+
+        hex::encode(&wrapped_key)
+    }
+
+    pub fn wrap_key_rsa_aes(&self, key: &str, aes_key_bits: usize, hash: &str, random_seed: &str) -> String {
+        utils::set_panic_hook();
+        let decode_key = hex::decode(key).expect("invalid decode key");
+        if ![128, 192, 256].contains(&aes_key_bits) {
+            panic!("invalid aes key bits {}", aes_key_bits);
         }
 
-        panic!("Instance not created")
+        let mut rng = utils::seeded_rng(random_seed);
+        let mut aes_key = Zeroizing::new(vec![0u8; aes_key_bits / 8]);
+        rng.fill_bytes(&mut aes_key);
+
+        let hash = HashAlgorithm::from_name(hash);
+        let mut wrapped_key = padding::oaep_encrypt(
+            &mut rng,
+            self.encrypting_instance(Padding::Oaep, Some(hash)),
+            hash,
+            &[],
+            &aes_key
+        );
+        wrapped_key.extend(key_wrap::aes_key_wrap_pad(&aes_key, &decode_key));
+
+        hex::encode(&wrapped_key)
     }
 
     pub fn get_e(&self) -> String {
@@ -98,6 +156,39 @@ impl RSAPublicKeyPair {
     pub fn from_pkcs8_pem(&mut self, data: &str) {
         utils::set_panic_hook();
         let keys = RsaPublicKey::from_public_key_pem(data).expect("failed to parse public key");
+        self.set_instance(keys);
+    }
+}
+
+impl RSAPublicKeyPair {
+    pub(crate) fn instance(&self) -> &RsaPublicKey {
+        match &self.public_instance {
+            Some(instance) => instance,
+            None => panic!("Instance not created")
+        }
+    }
+
+    pub(crate) fn policy(&self) -> &RSAPolicy {
+        &self.policy
+    }
+
+    /// The key, once the policy allows verifying with it, `padding` and `hash`.
+    pub(crate) fn verifying_instance(&self, padding: Padding, hash: HashAlgorithm) -> &RsaPublicKey {
+        let instance = self.instance();
+        self.policy.check_verification(instance, padding, hash);
+        instance
+    }
+
+    /// The key, once the policy allows encrypting with it, `padding` and the OAEP `hash`.
+    pub(crate) fn encrypting_instance(&self, padding: Padding, hash: Option<HashAlgorithm>) -> &RsaPublicKey {
+        let instance = self.instance();
+        self.policy.check_operation(instance, padding, hash);
+        instance
+    }
+
+    /// Panics unless the policy allows `keys`.
+    pub(crate) fn set_instance(&mut self, keys: RsaPublicKey) {
+        self.policy.check_key(&keys);
         self.n = keys.n().to_str_radix(16);
         self.e = keys.e().to_str_radix(16);
         self.public_instance = Some(keys);
@@ -107,6 +198,8 @@ impl RSAPublicKeyPair {
 #[cfg(test)]
 mod test {
     use super::*;
+    use fixtures::legacy_private_key_pair;
+    use fixtures::legacy_public_key_pair;
 
     mod create {
         use super::*;
@@ -114,8 +207,8 @@ mod test {
         #[test]
         fn create_keys() {
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut public_instance = RSAPublicKeyPair::new();
-            let mut private_instance = private_keys::RSAPrivateKeyPair::new();
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             public_instance.create(&private_instance.get_n(), &private_instance.get_e());
             assert_ne!(public_instance.get_e(), "".to_string());
@@ -126,8 +219,8 @@ mod test {
         #[should_panic]
         fn create_keys_without_n() {
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut public_instance = RSAPublicKeyPair::new();
-            let mut private_instance = private_keys::RSAPrivateKeyPair::new();
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             public_instance.create("", &private_instance.get_e());
         }
@@ -136,8 +229,8 @@ mod test {
         #[should_panic]
         fn create_keys_without_e() {
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut public_instance = RSAPublicKeyPair::new();
-            let mut private_instance = private_keys::RSAPrivateKeyPair::new();
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             public_instance.create(&private_instance.get_n(), "");
         }
@@ -155,7 +248,7 @@ CwIDAQAB
 -----END PUBLIC KEY-----
 ";
 
-            let mut instance = RSAPublicKeyPair::new();
+            let mut instance = legacy_public_key_pair();
             instance.from_pkcs8_pem(expected_pem);
             let actual_pem = instance.to_pkcs8_pem();
             assert_eq!(expected_pem, actual_pem)
@@ -164,8 +257,8 @@ CwIDAQAB
         #[test]
         fn generate_pem_from_new_keys() {
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut public_instance = RSAPublicKeyPair::new();
-            let mut private_instance = private_keys::RSAPrivateKeyPair::new();
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             public_instance.create(&private_instance.get_n(), &private_instance.get_e());
             let public_pem = public_instance.to_pkcs8_pem();
@@ -182,7 +275,7 @@ CwIDAQAB
         #[should_panic]
         fn failed_create_instance_from_pem() {
             let invalid_pem = "invalid_pem";
-            let mut instance = RSAPublicKeyPair::new();
+            let mut instance = legacy_public_key_pair();
             instance.from_pkcs8_pem(invalid_pem);
         }
     }
@@ -194,8 +287,8 @@ CwIDAQAB
         fn encrypt_message() {
             let message = "hello";
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut public_instance = RSAPublicKeyPair::new();
-            let mut private_instance = private_keys::RSAPrivateKeyPair::new();
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             public_instance.create(&private_instance.get_n(), &private_instance.get_e());
             let encrypted_message = public_instance.encrypt(message, seed);
@@ -206,8 +299,8 @@ CwIDAQAB
         fn encrypt_message_with_empty_string() {
             let message = "";
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut public_instance = RSAPublicKeyPair::new();
-            let mut private_instance = private_keys::RSAPrivateKeyPair::new();
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             public_instance.create(&private_instance.get_n(), &private_instance.get_e());
             let encrypted_message = public_instance.encrypt(message, seed);
@@ -219,13 +312,26 @@ CwIDAQAB
         fn encrypt_message_without_seed() {
             let message = "hello";
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut public_instance = RSAPublicKeyPair::new();
-            let mut private_instance = private_keys::RSAPrivateKeyPair::new();
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             public_instance.create(&private_instance.get_n(), &private_instance.get_e());
             public_instance.encrypt(message, "");
         }
     
+        #[test]
+        fn encrypt_message_seedless() {
+            let message = "hello";
+            let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
+            private_instance.generate(1024, seed);
+            public_instance.create(&private_instance.get_n(), &private_instance.get_e());
+            let encrypted_message = public_instance.encrypt_seedless(message);
+            assert_ne!(encrypted_message, public_instance.encrypt_seedless(message));
+            assert_eq!(private_instance.decrypt(&encrypted_message), message);
+        }
+    
         #[test]
         #[should_panic]
         fn encrypt_message_without_keys() {
@@ -243,8 +349,8 @@ CwIDAQAB
         fn verify_message() {
             let message = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut public_instance = RSAPublicKeyPair::new();
-            let mut private_instance = private_keys::RSAPrivateKeyPair::new();
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             public_instance.create(&private_instance.get_n(), &private_instance.get_e());
             let signature = private_instance.sign_message(message);  
@@ -256,8 +362,8 @@ CwIDAQAB
         fn verify_message_with_invalid_message() {
             let message = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut public_instance = RSAPublicKeyPair::new();
-            let mut private_instance = private_keys::RSAPrivateKeyPair::new();
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             public_instance.create(&private_instance.get_n(), &private_instance.get_e());
             let signature = private_instance.sign_message(message);  
@@ -269,8 +375,8 @@ CwIDAQAB
         fn verify_message_with_invalid_signature() {
             let message = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut public_instance = RSAPublicKeyPair::new();
-            let mut private_instance = private_keys::RSAPrivateKeyPair::new();
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             public_instance.create(&private_instance.get_n(), &private_instance.get_e());
             let verify = public_instance.verify_message(message, "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7");
@@ -282,8 +388,8 @@ CwIDAQAB
         fn verify_message_with_invalid_signature_length() {
             let message = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut public_instance = RSAPublicKeyPair::new();
-            let mut private_instance = private_keys::RSAPrivateKeyPair::new();
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             public_instance.create(&private_instance.get_n(), &private_instance.get_e());
             public_instance.verify_message(message, "c993abb");
@@ -294,8 +400,8 @@ CwIDAQAB
         fn verify_message_with_nonhex_signature() {
             let message = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
-            let mut public_instance = RSAPublicKeyPair::new();
-            let mut private_instance = private_keys::RSAPrivateKeyPair::new();
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             public_instance.create(&private_instance.get_n(), &private_instance.get_e());
             public_instance.verify_message(message, "hello");
@@ -307,10 +413,54 @@ CwIDAQAB
             let message = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
             let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
             let public_instance = RSAPublicKeyPair::new();
-            let mut private_instance = private_keys::RSAPrivateKeyPair::new();
+            let mut private_instance = legacy_private_key_pair();
             private_instance.generate(1024, seed);
             let signature = private_instance.sign_message(message);
             public_instance.verify_message(message, &signature);
         }
     }
+    mod wrap_key {
+        use super::*;
+
+        #[test]
+        fn wrap_key() {
+            let key = "000102030405060708090a0b0c0d0e0f";
+            let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
+            private_instance.generate(1024, seed);
+            public_instance.create(&private_instance.get_n(), &private_instance.get_e());
+            let wrapped_key = public_instance.wrap_key(key, "SHA-256", seed);
+            assert_eq!(wrapped_key.len(), 256);
+        }
+
+        #[test]
+        #[should_panic]
+        fn wrap_key_with_invalid_length() {
+            let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
+            private_instance.generate(1024, seed);
+            public_instance.create(&private_instance.get_n(), &private_instance.get_e());
+            public_instance.wrap_key("0001020304050607", "SHA-256", seed);
+        }
+
+        #[test]
+        #[should_panic]
+        fn wrap_key_rsa_aes_with_invalid_aes_bits() {
+            let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+            let mut public_instance = legacy_public_key_pair();
+            let mut private_instance = legacy_private_key_pair();
+            private_instance.generate(1024, seed);
+            public_instance.create(&private_instance.get_n(), &private_instance.get_e());
+            public_instance.wrap_key_rsa_aes("000102030405060708090a0b0c0d0e0f", 64, "SHA-256", seed);
+        }
+
+        #[test]
+        #[should_panic]
+        fn wrap_key_without_keys() {
+            let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+            RSAPublicKeyPair::new().wrap_key("000102030405060708090a0b0c0d0e0f", "SHA-256", seed);
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,180 @@
+use super::*;
+use rsa::PublicKey;
+use hashing::{ HashAlgorithm, Hasher };
+use public_keys::RSAPublicKeyPair;
+use private_keys::RSAPrivateKeyPair;
+use policy::Padding;
+use std::fmt;
+
+#[wasm_bindgen]
+pub struct RSASigner {
+    hash: HashAlgorithm,
+    hasher: Option<Hasher>,
+    private_instance: RsaPrivateKey
+}
+
+impl fmt::Debug for RSASigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RSASigner")
+            .field("hash", &self.hash)
+            .field("hasher", &self.hasher)
+            .field("private_instance", &"[redacted]")
+            .finish()
+    }
+}
+
+#[wasm_bindgen]
+impl RSASigner {
+    #[wasm_bindgen(constructor)]
+    pub fn new(keys: &RSAPrivateKeyPair, hash: &str) -> Self {
+        utils::set_panic_hook();
+        let hash = HashAlgorithm::from_name(hash);
+        RSASigner {
+            hash,
+            hasher: Some(hash.hasher()),
+            private_instance: keys.signing_instance(Padding::Pkcs1v15Sign, hash).clone()
+        }
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) {
+        utils::set_panic_hook();
+        match &mut self.hasher {
+            Some(hasher) => hasher.update(chunk),
+            None => panic!("Signer already finalized")
+        }
+    }
+
+    pub fn update_str(&mut self, chunk: &str) {
+        self.update(chunk.as_bytes());
+    }
+
+    pub fn finalize(&mut self) -> String {
+        utils::set_panic_hook();
+        let digest = match self.hasher.take() {
+            Some(hasher) => hasher.finalize(),
+            None => panic!("Signer already finalized")
+        };
+
+        let sign = match self.private_instance.sign(
+            PaddingScheme::new_pkcs1v15_sign(Some(self.hash.pkcs1v15_hash())),
+            &digest
+        ) {
+            Ok(res) => res,
+            Err(e) => panic!("sign error {}", e)
+        };
+
+        hex::encode(&sign)
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct RSAVerifier {
+    hash: HashAlgorithm,
+    hasher: Option<Hasher>,
+    public_instance: RsaPublicKey
+}
+
+#[wasm_bindgen]
+impl RSAVerifier {
+    #[wasm_bindgen(constructor)]
+    pub fn new(keys: &RSAPublicKeyPair, hash: &str) -> Self {
+        utils::set_panic_hook();
+        let hash = HashAlgorithm::from_name(hash);
+        RSAVerifier {
+            hash,
+            hasher: Some(hash.hasher()),
+            public_instance: keys.verifying_instance(Padding::Pkcs1v15Sign, hash).clone()
+        }
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) {
+        utils::set_panic_hook();
+        match &mut self.hasher {
+            Some(hasher) => hasher.update(chunk),
+            None => panic!("Verifier already finalized")
+        }
+    }
+
+    pub fn update_str(&mut self, chunk: &str) {
+        self.update(chunk.as_bytes());
+    }
+
+    pub fn finalize(&mut self, signature: &str) -> bool {
+        utils::set_panic_hook();
+        let decode_signature = hex::decode(signature).expect("invalid decode signature");
+        let digest = match self.hasher.take() {
+            Some(hasher) => hasher.finalize(),
+            None => panic!("Verifier already finalized")
+        };
+
+        self.public_instance.verify(
+            PaddingScheme::new_pkcs1v15_sign(Some(self.hash.pkcs1v15_hash())),
+            &digest,
+            &decode_signature
+        ).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fixtures::key_pairs;
+
+    #[test]
+    fn streaming_signature_matches_sign_message() {
+        let (private_instance, _) = key_pairs();
+        let mut signer = RSASigner::new(&private_instance, "SHA-256");
+        signer.update_str("hello ");
+        signer.update(b"world");
+        assert_eq!(signer.finalize(), private_instance.sign_message("hello world"));
+    }
+
+    #[test]
+    fn streaming_verify() {
+        let (private_instance, public_instance) = key_pairs();
+        let mut signer = RSASigner::new(&private_instance, "SHA-512");
+        let mut verifier = RSAVerifier::new(&public_instance, "SHA-512");
+        for chunk in [0u8; 10000].chunks(1000) {
+            signer.update(chunk);
+            verifier.update(chunk);
+        }
+
+        let signature = signer.finalize();
+        assert!(verifier.finalize(&signature));
+    }
+
+    #[test]
+    fn streaming_verify_existing_signature() {
+        let (private_instance, public_instance) = key_pairs();
+        let signature = private_instance.sign_message("hello world");
+        let mut verifier = RSAVerifier::new(&public_instance, "SHA-256");
+        verifier.update_str("hello");
+        verifier.update_str(" world");
+        assert!(verifier.finalize(&signature));
+    }
+
+    #[test]
+    fn streaming_verify_with_other_data() {
+        let (private_instance, public_instance) = key_pairs();
+        let signature = private_instance.sign_message("hello world");
+        let mut verifier = RSAVerifier::new(&public_instance, "SHA-256");
+        verifier.update_str("hello");
+        assert!(!verifier.finalize(&signature));
+    }
+
+    #[test]
+    #[should_panic]
+    fn finalize_twice() {
+        let (private_instance, _) = key_pairs();
+        let mut signer = RSASigner::new(&private_instance, "SHA-256");
+        signer.finalize();
+        signer.finalize();
+    }
+
+    #[test]
+    #[should_panic]
+    fn signer_without_keys() {
+        RSASigner::new(&RSAPrivateKeyPair::new(), "SHA-256");
+    }
+}
@@ -1,4 +1,7 @@
 use cfg_if::cfg_if;
+use rand::prelude::*;
+use rand::{ SeedableRng };
+use rand::rngs::OsRng;
 
 cfg_if! {
     if #[cfg(feature = "console_error_panic_hook")] {
@@ -9,3 +12,57 @@ cfg_if! {
         pub fn set_panic_hook() {}
     }
 }
+
+/// Seed argument that makes any method draw from the platform CSPRNG instead.
+pub const OS_RANDOM: &str = "os";
+
+/// Caller supplied seeds are refused with the `forbid-caller-seeds` feature, except in test builds.
+pub fn caller_seeds_allowed() -> bool {
+    cfg!(any(test, not(feature = "forbid-caller-seeds")))
+}
+
+/// Generator seeded from the platform CSPRNG: `crypto.getRandomValues` in the browser, the OS otherwise.
+pub fn os_rng() -> StdRng {
+    StdRng::from_rng(OsRng).expect("platform random number generator failed")
+}
+
+/// Generator for a hex `random_seed` of 32 bytes, or `os_rng` for `OS_RANDOM`.
+pub fn seeded_rng(random_seed: &str) -> StdRng {
+    if random_seed == OS_RANDOM {
+        return os_rng();
+    }
+    if !caller_seeds_allowed() {
+        panic!("caller supplied random seeds are forbidden, pass \"{}\" instead", OS_RANDOM);
+    }
+
+    let mut seed_array: [u8; 32] = [0; 32];
+    let decode_seed = hex::decode(random_seed).expect("invalid decode");
+    seed_array.copy_from_slice(decode_seed.as_slice());
+
+    SeedableRng::from_seed(seed_array)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn os_random_seed() {
+        assert_ne!(seeded_rng(OS_RANDOM).next_u64(), seeded_rng(OS_RANDOM).next_u64());
+
+        let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+        assert_eq!(seeded_rng(seed).next_u64(), seeded_rng(seed).next_u64());
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid decode")]
+    fn invalid_seed() {
+        seeded_rng("not hex");
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty_seed() {
+        seeded_rng("");
+    }
+}
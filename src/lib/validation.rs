@@ -0,0 +1,215 @@
+use super::*;
+use num_bigint_dig::prime::probably_prime;
+use num_integer::Integer;
+use num_traits::{ One, Zero };
+use primitives::{ crt_coefficients, crt_exponents };
+use private_keys::RSAPrivateKeyPair;
+
+const MILLER_RABIN_ROUNDS: usize = 20;
+const MIN_MODULUS_BITS: usize = 512;
+const MAX_MODULUS_BITS: usize = 16384;
+
+/// CRT exponents and coefficients supplied with an imported key, in RFC 8017 order.
+pub struct CrtValues {
+    pub exponents: Vec<BigUint>,
+    pub coefficients: Vec<BigUint>
+}
+
+/// CRT values of a PKCS#8 `PrivateKeyInfo` holding an RFC 8017 `RSAPrivateKey`.
+pub fn pkcs8_crt_values(private_key_info: &[u8]) -> CrtValues {
+    let private_key_info = der::parse_single(private_key_info).expect_tag(der::TAG_SEQUENCE).children();
+    let private_key = private_key_info.get(2).expect("invalid private key info").expect_tag(der::TAG_OCTET_STRING);
+    let fields = der::parse_single(private_key.content).expect_tag(der::TAG_SEQUENCE).children();
+    if fields.len() < 9 {
+        panic!("invalid rsa private key");
+    }
+
+    let mut values = CrtValues {
+        exponents: vec![fields[6].as_biguint(), fields[7].as_biguint()],
+        coefficients: vec![fields[8].as_biguint()]
+    };
+    for other_prime in fields.get(9).map(|field| field.expect_tag(der::TAG_SEQUENCE).children()).unwrap_or_default() {
+        let other_prime = other_prime.expect_tag(der::TAG_SEQUENCE).children();
+        if other_prime.len() != 3 {
+            panic!("invalid rsa private key");
+        }
+        values.exponents.push(other_prime[1].as_biguint());
+        values.coefficients.push(other_prime[2].as_biguint());
+    }
+
+    values
+}
+
+/// Outcome of every check, in the order they ran.
+pub struct ValidationReport {
+    checks: Vec<(&'static str, bool)>
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.checks.iter().all(|(_, passed)| *passed)
+    }
+
+    pub fn failed(&self) -> Vec<&'static str> {
+        self.checks.iter().filter(|(_, passed)| !passed).map(|(name, _)| *name).collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        let checks: serde_json::Map<String, serde_json::Value> = self.checks.iter()
+            .map(|(name, passed)| (name.to_string(), serde_json::Value::Bool(*passed)))
+            .collect();
+
+        serde_json::json!({
+            "valid": self.is_valid(),
+            "checks": checks,
+            "failed": self.failed()
+        }).to_string()
+    }
+
+    /// Panics with the names of the failed checks.
+    pub fn expect_valid(&self) {
+        if !self.is_valid() {
+            panic!("invalid private key: {}", self.failed().join(", "));
+        }
+    }
+}
+
+/// Checks the size bounds, `n = r_1 * ... * r_k`, that every prime is a distinct probable prime,
+/// `e` and `e * d = 1 mod λ(n)` and, when given, the CRT values against the ones computed from the primes.
+pub fn validate_key(key: &RsaPrivateKey, crt: Option<&CrtValues>) -> ValidationReport {
+    let one = BigUint::one();
+    let (n, e, d, primes) = (key.n(), key.e(), key.d(), key.primes());
+    let mut checks = vec![];
+
+    checks.push(("modulusSize", (MIN_MODULUS_BITS..=MAX_MODULUS_BITS).contains(&n.bits())));
+
+    let distinct = (1..primes.len()).all(|index| !primes[..index].contains(&primes[index]));
+    checks.push(("primeCount", primes.len() >= 2 && distinct));
+
+    let product = primes.iter().fold(one.clone(), |product, prime| product * prime);
+    let modulus = &product == n;
+    checks.push(("modulus", modulus));
+
+    let prime_factors = primes.iter().all(|prime| prime > &one && probably_prime(prime, MILLER_RABIN_ROUNDS));
+    checks.push(("primes", prime_factors));
+
+    checks.push(("publicExponent", e >= &BigUint::from(3u32) && e < n && !(e % 2u32).is_zero()));
+
+    let structure_valid = primes.len() >= 2 && distinct && modulus && prime_factors;
+    let private_exponent = structure_valid && !d.is_zero() && d < n && {
+        let lambda = primes.iter().fold(one.clone(), |lambda, prime| lambda.lcm(&(prime - &one)));
+        (e * d) % lambda == one
+    };
+    checks.push(("privateExponent", private_exponent));
+
+    if let Some(crt) = crt {
+        let crt_valid = structure_valid
+            && *crt_exponents(key) == crt.exponents
+            && *crt_coefficients(key) == crt.coefficients;
+        checks.push(("crtValues", crt_valid));
+    }
+
+    ValidationReport { checks }
+}
+
+#[wasm_bindgen]
+impl RSAPrivateKeyPair {
+    /// Runs every check of `validate_key` and returns JSON with `valid`, `checks` (name to result)
+    /// and the names of the `failed` checks.
+    pub fn validate(&self) -> String {
+        utils::set_panic_hook();
+        validate_key(self.instance(), None).to_json()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::Value;
+    use fixtures::PRIVATE_KEY;
+    use policy::RSAPolicy;
+
+    fn key() -> RsaPrivateKey {
+        let mut private_instance = RSAPrivateKeyPair::new();
+        private_instance.from_pkcs8_pem(PRIVATE_KEY);
+        private_instance.instance().clone()
+    }
+
+    fn failed(key: &RsaPrivateKey, crt: Option<&CrtValues>) -> Vec<&'static str> {
+        validate_key(key, crt).failed()
+    }
+
+    fn pkcs8_coefficient(data: &[u8]) -> &[u8] {
+        let private_key = der::parse_single(data).children()[2].content;
+        der::parse_single(private_key).children()[8].content
+    }
+
+    #[test]
+    fn validate_valid_key() {
+        let key = key();
+        let crt = pkcs8_crt_values(&der::from_pem_or_hex(PRIVATE_KEY));
+        assert!(validate_key(&key, Some(&crt)).is_valid());
+
+        let mut private_instance = RSAPrivateKeyPair::new();
+        private_instance.set_instance(key);
+        let report: Value = serde_json::from_str(&private_instance.validate()).unwrap();
+        assert_eq!(report["valid"], true);
+        assert_eq!(report["checks"]["privateExponent"], true);
+        assert!(report["checks"].get("crtValues").is_none());
+        assert_eq!(report["failed"].as_array().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn validate_corrupted_components() {
+        let key = key();
+        let (n, e, d, primes) = (key.n().clone(), key.e().clone(), key.d().clone(), key.primes().to_vec());
+
+        let corrupted_d = RsaPrivateKey::from_components(n.clone(), e.clone(), &d + 2u32, primes.clone());
+        assert_eq!(failed(&corrupted_d, None), vec!["privateExponent"]);
+
+        let corrupted_n = RsaPrivateKey::from_components(&n + 2u32, e.clone(), d.clone(), primes.clone());
+        assert_eq!(failed(&corrupted_n, None), vec!["modulus", "privateExponent"]);
+
+        let composite = vec![&primes[0] * 3u32, primes[1].clone()];
+        let composite_prime = RsaPrivateKey::from_components(&composite[0] * &composite[1], e.clone(), d.clone(), composite);
+        assert!(failed(&composite_prime, None).contains(&"primes"));
+
+        let even_e = RsaPrivateKey::from_components(n.clone(), BigUint::from(65536u32), d.clone(), primes.clone());
+        assert!(failed(&even_e, None).contains(&"publicExponent"));
+
+        let mut crt = pkcs8_crt_values(&der::from_pem_or_hex(PRIVATE_KEY));
+        crt.coefficients[0] += 1u32;
+        assert_eq!(failed(&key, Some(&crt)), vec!["crtValues"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid private key: crtValues")]
+    fn import_pem_with_corrupted_coefficient() {
+        let mut data = der::from_pem_or_hex(PRIVATE_KEY);
+        let offset = {
+            let coefficient = pkcs8_coefficient(&data);
+            coefficient.as_ptr() as usize - data.as_ptr() as usize + coefficient.len() - 1
+        };
+        data[offset] ^= 1;
+
+        RSAPrivateKeyPair::new().from_pkcs8_pem(&der::to_pem("PRIVATE KEY", &data));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid private key: privateExponent")]
+    fn import_components_with_corrupted_d() {
+        let key = key();
+        let mut private_instance = RSAPrivateKeyPair::new();
+        private_instance.set_instance(key.clone());
+        let d = (key.d() + 2u32).to_str_radix(16);
+        RSAPrivateKeyPair::new().generate_from(&private_instance.get_n(), &d, &private_instance.get_e(), &private_instance.get_primes());
+    }
+
+    #[test]
+    fn validate_small_modulus() {
+        let mut private_instance = RSAPrivateKeyPair::new();
+        private_instance.set_policy(&RSAPolicy::from_json(r#"{"minModulusBits":256}"#));
+        private_instance.generate(256, "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7");
+        assert_eq!(failed(private_instance.instance(), None), vec!["modulusSize"]);
+    }
+}
@@ -0,0 +1,246 @@
+use super::*;
+use serde_json::Value;
+use cose::CoseAlgorithm;
+use hashing::HashAlgorithm;
+use policy::{ Padding, RSAPolicy };
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_BACKUP_ELIGIBLE: u8 = 0x08;
+const FLAG_BACKUP_STATE: u8 = 0x10;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+const FLAG_EXTENSION_DATA: u8 = 0x80;
+
+/// Fixed part of authenticator data: rpIdHash (32), flags (1) and signCount (4).
+const AUTHENTICATOR_DATA_MIN_LEN: usize = 37;
+
+/// Verifies a WebAuthn assertion signed with RS256 (COSE alg -257), as in WebAuthn Level 2 section 7.2.
+///
+/// `verified` requires a valid signature, client data of type `webauthn.get` and the user present flag.
+/// Returns JSON with `verified`, the authenticator data flags, `signCount`, hex `rpIdHash` and the
+/// `type`, `challenge` and `origin` of the client data. Checking the challenge, origin, rpIdHash and
+/// sign counter against the relying party state is left to the caller. Panics unless the credential
+/// key carries `alg` -257 and `policy` allows it for RSASSA-PKCS1-v1_5 with SHA-256.
+#[wasm_bindgen]
+pub fn verify_webauthn_assertion(
+    credential_public_key: &[u8],
+    authenticator_data: &[u8],
+    client_data_json: &[u8],
+    signature: &[u8],
+    policy: &RSAPolicy
+) -> String {
+    utils::set_panic_hook();
+    match cose::key_algorithm(credential_public_key) {
+        Some(-257) => {},
+        Some(algorithm) => panic!("unsupported credential algorithm {}", algorithm),
+        None => panic!("credential public key without alg")
+    }
+    if authenticator_data.len() < AUTHENTICATOR_DATA_MIN_LEN {
+        panic!("invalid authenticator data");
+    }
+
+    let client_data: Value = serde_json::from_slice(client_data_json).expect("invalid client data json");
+    let client_data_type = client_data["type"].as_str().unwrap_or_default();
+
+    let public_key = cose::public_key_from_cose(credential_public_key);
+    policy.check_verification(&public_key, Padding::Pkcs1v15Sign, HashAlgorithm::Sha256);
+
+    let flags = authenticator_data[32];
+    let signed_data = [authenticator_data, &HashAlgorithm::Sha256.digest(client_data_json)].concat();
+    let verified = client_data_type == "webauthn.get"
+        && flags & FLAG_USER_PRESENT != 0
+        && CoseAlgorithm::Rs256.verify(&public_key, &signed_data, signature);
+
+    let mut sign_count = [0u8; 4];
+    sign_count.copy_from_slice(&authenticator_data[33..37]);
+
+    serde_json::json!({
+        "verified": verified,
+        "userPresent": flags & FLAG_USER_PRESENT != 0,
+        "userVerified": flags & FLAG_USER_VERIFIED != 0,
+        "backupEligible": flags & FLAG_BACKUP_ELIGIBLE != 0,
+        "backupState": flags & FLAG_BACKUP_STATE != 0,
+        "attestedCredentialData": flags & FLAG_ATTESTED_CREDENTIAL_DATA != 0,
+        "extensionData": flags & FLAG_EXTENSION_DATA != 0,
+        "signCount": u32::from_be_bytes(sign_count),
+        "rpIdHash": hex::encode(&authenticator_data[..32]),
+        "type": client_data_type,
+        "challenge": client_data["challenge"].as_str().unwrap_or_default(),
+        "origin": client_data["origin"].as_str().unwrap_or_default()
+    }).to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use private_keys::RSAPrivateKeyPair;
+    use fixtures::PRIVATE_KEY;
+
+    const SEED: &str = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+    const CLIENT_DATA: &str = r#"{"type":"webauthn.get","challenge":"dGVzdC1jaGFsbGVuZ2U","origin":"https://example.com","crossOrigin":false}"#;
+
+    fn private_key_pair() -> RSAPrivateKeyPair {
+        let mut private_instance = RSAPrivateKeyPair::new();
+        private_instance.from_pkcs8_pem(PRIVATE_KEY);
+        private_instance
+    }
+
+    fn authenticator_data(flags: u8, sign_count: u32) -> Vec<u8> {
+        [HashAlgorithm::Sha256.digest(b"example.com"), vec![flags], sign_count.to_be_bytes().to_vec()].concat()
+    }
+
+    fn assert_signature(private_instance: &RSAPrivateKeyPair, authenticator_data: &[u8], client_data_json: &[u8]) -> Vec<u8> {
+        let signed_data = [authenticator_data, &HashAlgorithm::Sha256.digest(client_data_json)].concat();
+        let mut rng = utils::seeded_rng(SEED);
+        CoseAlgorithm::Rs256.sign(&mut rng, private_instance.instance(), &signed_data)
+    }
+
+    fn credential_public_key(private_instance: &RSAPrivateKeyPair, algorithm: &str) -> Vec<u8> {
+        cose::public_key_to_cose(&private_instance.instance().to_public_key(), None, Some(algorithm.to_string()))
+    }
+
+    #[test]
+    fn verify_assertion() {
+        let private_instance = private_key_pair();
+        let authenticator_data = authenticator_data(0x05, 42);
+        let signature = assert_signature(&private_instance, &authenticator_data, CLIENT_DATA.as_bytes());
+
+        let result: Value = serde_json::from_str(&verify_webauthn_assertion(
+            &credential_public_key(&private_instance, "RS256"),
+            &authenticator_data,
+            CLIENT_DATA.as_bytes(),
+            &signature,
+            &RSAPolicy::default()
+        )).unwrap();
+        assert_eq!(result["verified"], true);
+        assert_eq!(result["userPresent"], true);
+        assert_eq!(result["userVerified"], true);
+        assert_eq!(result["backupEligible"], false);
+        assert_eq!(result["signCount"], 42);
+        assert_eq!(result["rpIdHash"], "a379a6f6eeafb9a55e378c118034e2751e682fab9f2d30ab13d2125586ce1947");
+        assert_eq!(result["challenge"], "dGVzdC1jaGFsbGVuZ2U");
+        assert_eq!(result["origin"], "https://example.com");
+    }
+
+    #[test]
+    fn verify_tampered_assertion() {
+        let private_instance = private_key_pair();
+        let public_key = credential_public_key(&private_instance, "RS256");
+        let authenticator_data = authenticator_data(0x01, 7);
+        let signature = assert_signature(&private_instance, &authenticator_data, CLIENT_DATA.as_bytes());
+
+        let tampered_counter = self::authenticator_data(0x01, 8);
+        let result: Value = serde_json::from_str(
+            &verify_webauthn_assertion(&public_key, &tampered_counter, CLIENT_DATA.as_bytes(), &signature, &RSAPolicy::default())
+        ).unwrap();
+        assert_eq!(result["verified"], false);
+        assert_eq!(result["signCount"], 8);
+
+        let other_client_data = CLIENT_DATA.replace("example.com", "example.org");
+        let result: Value = serde_json::from_str(
+            &verify_webauthn_assertion(&public_key, &authenticator_data, other_client_data.as_bytes(), &signature, &RSAPolicy::default())
+        ).unwrap();
+        assert_eq!(result["verified"], false);
+    }
+
+    #[test]
+    fn verify_without_user_presence() {
+        let private_instance = private_key_pair();
+        let authenticator_data = authenticator_data(0x00, 3);
+        let signature = assert_signature(&private_instance, &authenticator_data, CLIENT_DATA.as_bytes());
+
+        let result: Value = serde_json::from_str(&verify_webauthn_assertion(
+            &credential_public_key(&private_instance, "RS256"),
+            &authenticator_data,
+            CLIENT_DATA.as_bytes(),
+            &signature,
+            &RSAPolicy::default()
+        )).unwrap();
+        assert_eq!(result["verified"], false);
+        assert_eq!(result["userPresent"], false);
+    }
+
+    #[test]
+    fn verify_independent_assertion() {
+        // assertion assembled and signed by pyca/cryptography, with its own CBOR encoding of the COSE key
+        let vector: Value = serde_json::from_str(include_str!("testdata/webauthn_assertion.json")).unwrap();
+        let field = |name: &str| hex::decode(vector[name].as_str().unwrap()).unwrap();
+        let result: Value = serde_json::from_str(&verify_webauthn_assertion(
+            &field("credentialPublicKey"),
+            &field("authenticatorData"),
+            &field("clientDataJSON"),
+            &field("signature"),
+            &RSAPolicy::default()
+        )).unwrap();
+        assert_eq!(result["verified"], true);
+        assert_eq!(result["userPresent"], true);
+        assert_eq!(result["userVerified"], true);
+        assert_eq!(result["signCount"], 17);
+        assert_eq!(result["origin"], "https://webauthn.example");
+    }
+
+    #[test]
+    fn verify_registration_client_data() {
+        let private_instance = private_key_pair();
+        let authenticator_data = authenticator_data(0x01, 0);
+        let client_data = CLIENT_DATA.replace("webauthn.get", "webauthn.create");
+        let signature = assert_signature(&private_instance, &authenticator_data, client_data.as_bytes());
+
+        let result: Value = serde_json::from_str(&verify_webauthn_assertion(
+            &credential_public_key(&private_instance, "RS256"),
+            &authenticator_data,
+            client_data.as_bytes(),
+            &signature,
+            &RSAPolicy::default()
+        )).unwrap();
+        assert_eq!(result["verified"], false);
+        assert_eq!(result["type"], "webauthn.create");
+    }
+
+    #[test]
+    #[should_panic]
+    fn verify_with_pss_credential() {
+        let private_instance = private_key_pair();
+        let authenticator_data = authenticator_data(0x01, 0);
+        let signature = assert_signature(&private_instance, &authenticator_data, CLIENT_DATA.as_bytes());
+        verify_webauthn_assertion(
+            &credential_public_key(&private_instance, "PS256"),
+            &authenticator_data,
+            CLIENT_DATA.as_bytes(),
+            &signature,
+            &RSAPolicy::default()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "credential public key without alg")]
+    fn verify_with_credential_without_algorithm() {
+        let private_instance = private_key_pair();
+        let authenticator_data = authenticator_data(0x01, 0);
+        let signature = assert_signature(&private_instance, &authenticator_data, CLIENT_DATA.as_bytes());
+        let public_key = cose::public_key_to_cose(&private_instance.instance().to_public_key(), None, None);
+        verify_webauthn_assertion(&public_key, &authenticator_data, CLIENT_DATA.as_bytes(), &signature, &RSAPolicy::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "key size 2048 is below the policy minimum of 3072 bits")]
+    fn verify_with_credential_below_policy() {
+        let private_instance = private_key_pair();
+        let authenticator_data = authenticator_data(0x01, 0);
+        let signature = assert_signature(&private_instance, &authenticator_data, CLIENT_DATA.as_bytes());
+        verify_webauthn_assertion(
+            &credential_public_key(&private_instance, "RS256"),
+            &authenticator_data,
+            CLIENT_DATA.as_bytes(),
+            &signature,
+            &RSAPolicy::from_json(r#"{"minModulusBits":3072}"#)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn verify_short_authenticator_data() {
+        let private_instance = private_key_pair();
+        verify_webauthn_assertion(&credential_public_key(&private_instance, "RS256"), &[0u8; 36], CLIENT_DATA.as_bytes(), &[], &RSAPolicy::default());
+    }
+}
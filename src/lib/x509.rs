@@ -0,0 +1,69 @@
+use super::*;
+use rsa::pkcs8::FromPublicKey;
+use der::DerObject;
+
+/// Fields of an X.509 certificate needed to identify and use an RSA signer or recipient.
+#[derive(Debug, Clone)]
+pub struct Certificate {
+    pub raw: Vec<u8>,
+    pub serial_number: Vec<u8>,
+    pub issuer: Vec<u8>,
+    pub public_key: RsaPublicKey
+}
+
+impl Certificate {
+    pub fn from_der(data: &[u8]) -> Certificate {
+        let certificate = der::parse_single(data);
+        let tbs_certificate = certificate.expect_tag(der::TAG_SEQUENCE).children()[0];
+        let mut fields = tbs_certificate.expect_tag(der::TAG_SEQUENCE).children();
+        if fields[0].tag == 0xa0 {
+            fields.remove(0);
+        }
+
+        if fields.len() < 6 {
+            panic!("invalid certificate");
+        }
+
+        let public_key = RsaPublicKey::from_public_key_der(fields[5].raw).expect("certificate public key is not rsa");
+
+        Certificate {
+            raw: data.to_vec(),
+            serial_number: fields[0].expect_tag(der::TAG_INTEGER).raw.to_vec(),
+            issuer: fields[2].expect_tag(der::TAG_SEQUENCE).raw.to_vec(),
+            public_key
+        }
+    }
+
+    pub fn from_pem_or_hex(data: &str) -> Certificate {
+        Certificate::from_der(&der::from_pem_or_hex(data))
+    }
+
+    /// IssuerAndSerialNumber from RFC 5652 10.2.4.
+    pub fn issuer_and_serial_number(&self) -> Vec<u8> {
+        der::sequence(&[self.issuer.clone(), self.serial_number.clone()])
+    }
+
+    pub fn matches(&self, issuer_and_serial_number: &DerObject) -> bool {
+        issuer_and_serial_number.raw == self.issuer_and_serial_number().as_slice()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fixtures::CERTIFICATE;
+
+    #[test]
+    fn parse_certificate() {
+        let certificate = Certificate::from_pem_or_hex(CERTIFICATE);
+        assert_eq!(hex::encode(&certificate.serial_number), "02021092");
+        assert_eq!(certificate.public_key.e().to_str_radix(16), "10001");
+        assert_eq!(certificate.public_key.n().bits(), 2048);
+    }
+
+    #[test]
+    #[should_panic]
+    fn parse_invalid_certificate() {
+        Certificate::from_pem_or_hex("3003020101");
+    }
+}
@@ -13,6 +13,8 @@ describe('OK: RSA encrypt/decrypt', () => {
   beforeEach(async () => {
     rsaOne = await RSASetup()
     rsaTwo = await RSASetup()
+    rsaOne.setRSAPolicy('legacy')
+    rsaTwo.setRSAPolicy('legacy')
 
     const privateKey = rsaOne.generateRSAPrivate(2048)
     n = privateKey.n
@@ -10,6 +10,8 @@ describe('RSA generate keys', () => {
   beforeEach(async () => {
     rsaOne = await RSASetup()
     rsaTwo = await RSASetup()
+    rsaOne.setRSAPolicy('legacy')
+    rsaTwo.setRSAPolicy('legacy')
   })
 
   it('OK: generate private key pair', () => {
@@ -47,6 +49,165 @@ describe('RSA generate keys', () => {
     expect(errorMessage).not.to.be.eq(null)
   })
 
+  it('FAIL: generate private key pair below strict policy', () => {
+    // Arrange
+    let errorMessage = null
+    rsaOne.setRSAPolicy('strict')
+    // Act
+    try {
+      rsaOne.generateRSAPrivate(1024)
+    } catch (error) {
+      errorMessage = error.message
+    }
+    // Assert
+    expect(errorMessage).not.to.be.eq(null)
+  })
+
+  it('OK: set custom policy', () => {
+    // Act
+    const policy = rsaOne.setRSAPolicy({ minModulusBits: 3072, hashes: ['SHA-384', 'SHA-512'] })
+    // Assert
+    expect(policy.minModulusBits).to.be.eq(3072)
+    expect(policy.exponents).to.deep.eq(['10001'])
+    expect(policy.sha1Verification).to.be.eq(false)
+  })
+
+  it('OK: generate private key pair with exponent and primes', () => {
+    // Act
+    const key = rsaOne.generateRSAPrivate(bits, { publicExponent: '11', primeCount: 3 })
+    // Assert
+    expect(key.e).to.be.eq('11')
+    expect(key.primes.length).to.be.eq(3)
+  })
+
+  it('FAIL: generate private key pair with too many primes', () => {
+    // Arrange
+    let errorMessage = null
+    // Act
+    try {
+      rsaOne.generateRSAPrivate(bits, { primeCount: 5 })
+    } catch (error) {
+      errorMessage = error.message
+    }
+    // Assert
+    expect(errorMessage).not.to.be.eq(null)
+  })
+
+  it('OK: generate FIPS 186-5 private key pair', () => {
+    // Act
+    const key = rsaOne.generateRSAPrivateFips(bits)
+    // Assert
+    expect(key.e).to.be.eq('10001')
+    expect(key.primes.length).to.be.eq(2)
+    expect(key.report.standard).to.be.eq('FIPS 186-5')
+    expect(key.report.method).to.be.eq('A.1.3')
+    expect(key.report.randomBitGenerator).to.be.eq('HMAC_DRBG with SHA-256 (SP 800-90A)')
+  })
+
+  it('OK: generate FIPS 186-5 private key pair from provable primes', () => {
+    // Act
+    const key = rsaOne.generateRSAPrivateFips(bits, 'A.1.2')
+    // Assert
+    expect(key.primes.length).to.be.eq(2)
+    expect(key.report.method).to.be.eq('A.1.2')
+    expect(key.report.primes).to.be.eq('provable primes')
+  })
+
+  it('FAIL: generate FIPS 186-5 private key pair with unapproved size', () => {
+    // Arrange
+    let errorMessage = null
+    // Act
+    try {
+      rsaOne.generateRSAPrivateFips(1024)
+    } catch (error) {
+      errorMessage = error.message
+    }
+    // Assert
+    expect(errorMessage).not.to.be.eq(null)
+  })
+
+  it('OK: derive private key pair from mnemonic', () => {
+    // Arrange
+    const mnemonic = 'abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about'
+    // Act
+    const key = rsaOne.deriveRSAPrivateFromMnemonic(1024, mnemonic)
+    const sameKey = rsaTwo.deriveRSAPrivateFromMnemonic(1024, mnemonic)
+    // Assert
+    expect(key.n.startsWith('f26c3f0ee8c987bee7115fa8574e5035')).to.be.eq(true)
+    expect(sameKey.d).to.be.eq(key.d)
+  })
+
+  it('OK: derive private key pair from passphrase', () => {
+    // Act
+    const key = rsaOne.deriveRSAPrivateFromPassphrase(1024, 'passphrase', 'user@example.com')
+    const otherSalt = rsaTwo.deriveRSAPrivateFromPassphrase(1024, 'passphrase', 'other@example.com')
+    // Assert
+    expect(otherSalt.n).not.to.be.eq(key.n)
+  })
+
+  it('OK: export and import CRT components', () => {
+    // Arrange
+    const key = rsaOne.generateRSAPrivate(bits)
+    // Act
+    const components = rsaOne.getRSAPrivateCrt()
+    const imported = rsaTwo.createRSAPrivateFromCrt(components)
+    // Assert
+    expect(components.kty).to.be.eq('RSA')
+    expect(Buffer.from(components.n, 'base64').toString('hex').replace(/^0+/, '')).to.be.eq(key.n)
+    expect(components.dp.length).to.be.least(1)
+    expect(imported.d).to.be.eq(key.d)
+  })
+
+  it('FAIL: import inconsistent CRT components', () => {
+    // Arrange
+    let errorMessage = null
+    rsaOne.generateRSAPrivate(bits)
+    const components = rsaOne.getRSAPrivateCrt()
+    // Act
+    try {
+      rsaTwo.createRSAPrivateFromCrt({ ...components, qi: 'AQ' })
+    } catch (error) {
+      errorMessage = error.message
+    }
+    // Assert
+    expect(errorMessage).not.to.be.eq(null)
+  })
+
+  it('OK: recover private key pair from n, d, e', () => {
+    // Arrange
+    const { n, d, e, primes } = rsaOne.generateRSAPrivate(bits)
+    // Act
+    const recovered = rsaTwo.recoverRSAPrivateFrom(n, d, e)
+    // Assert
+    expect(recovered.primes.slice().sort()).to.be.deep.eq(primes.slice().sort())
+  })
+
+  it('OK: validate private key pair', () => {
+    // Arrange
+    rsaOne.generateRSAPrivate(bits)
+    // Act
+    const report = rsaOne.validateRSAPrivate()
+    // Assert
+    expect(report.valid).to.be.eq(true)
+    expect(report.checks.privateExponent).to.be.eq(true)
+    expect(report.failed.length).to.be.eq(0)
+  })
+
+  it('FAIL: generate private key pair from corrupted d', () => {
+    // Arrange
+    let errorMessage = null
+    const { n, d, e, primes } = rsaOne.generateRSAPrivate(bits)
+    const corruptedD = d.slice(0, -1) + (d.endsWith('0') ? '1' : '0')
+    // Act
+    try {
+      rsaTwo.generateRSAPrivateFrom(n, corruptedD, e, primes)
+    } catch (error) {
+      errorMessage = error.message
+    }
+    // Assert
+    expect(errorMessage).not.to.be.eq(null)
+  })
+
   it('OK: create public key', () => {
     // Act
     const { n, e } = rsaOne.generateRSAPrivate(bits)
@@ -59,6 +220,37 @@ describe('RSA generate keys', () => {
     expect(Number(publicKey.e)).not.to.be.eq(NaN)
   })
 
+  it('OK: analyze public key', () => {
+    // Arrange
+    const { n, e } = rsaOne.generateRSAPrivate(2048)
+    // Act
+    const analysis = rsaOne.analyzeRSAPublic(n, e)
+    // Assert
+    expect(analysis.bits).to.be.eq(2048)
+    expect(analysis.weak).to.be.eq(false)
+    expect(analysis.smallFactor).to.be.eq(null)
+  })
+
+  it('OK: analyze public key with small exponent', () => {
+    // Arrange
+    const { n } = rsaOne.generateRSAPrivate(bits)
+    // Act
+    const analysis = rsaOne.analyzeRSAPublic(n, '3')
+    // Assert
+    expect(analysis.weak).to.be.eq(true)
+    expect(analysis.weaknesses).to.include('exponentTooSmall')
+  })
+
+  it('OK: find shared factors', () => {
+    // Arrange
+    const keys = [rsaOne.generateRSAPrivate(bits), rsaOne.generateRSAPrivate(bits)]
+    // Act
+    const sharedFactors = rsaOne.findSharedFactors([...keys, keys[0]])
+    // Assert
+    expect(sharedFactors.groups.length).to.be.eq(0)
+    expect(sharedFactors.duplicates).to.deep.eq([[0, 2]])
+  })
+
   it('FAIL: create public key with invalid e', () => {
     // Arrange
     let errorMessage = null
@@ -353,4 +545,48 @@ CwIDAQAB
     // Assert
     expect(errorMessage).not.to.be.eq(null)
   })
+
+  it('OK: generate private key pair asynchronously', async () => {
+    // Arrange
+    const progress = []
+    // Act
+    const key = await rsaOne.generateRSAPrivateAsync(bits, (state) => progress.push(state)).promise
+    const publicKey = rsaTwo.createRSAPublic(key.n, key.e)
+    const signature = rsaOne.signMessage('hello')
+    // Assert
+    expect(key.n).to.be.eq(rsaOne.getRSAPrivate().n)
+    expect(key.primes.length).to.be.eq(2)
+    expect(progress.length).to.be.least(1)
+    expect(progress[progress.length - 1].primesFound).to.be.eq(2)
+    expect(rsaTwo.verify('hello', signature)).to.be.eq(true)
+  })
+
+  it('FAIL: cancel asynchronous key generation', async () => {
+    // Arrange
+    let errorMessage = null
+    const generation = rsaOne.generateRSAPrivateAsync(bits)
+    // Act
+    generation.cancel()
+    try {
+      await generation.promise
+    } catch (error) {
+      errorMessage = error.message
+    }
+    // Assert
+    expect(errorMessage).to.be.eq('key generation cancelled')
+  })
+
+  it('FAIL: start asynchronous key generation below strict policy', () => {
+    // Arrange
+    let errorMessage = null
+    rsaOne.setRSAPolicy('strict')
+    // Act
+    try {
+      rsaOne.generateRSAPrivateAsync(1024)
+    } catch (error) {
+      errorMessage = error.message
+    }
+    // Assert
+    expect(errorMessage).not.to.be.eq(null)
+  })
 })
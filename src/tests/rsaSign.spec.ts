@@ -9,6 +9,8 @@ describe('RSA sign/verify', () => {
   beforeEach(async () => {
     rsaOne = await RSASetup()
     rsaTwo = await RSASetup()
+    rsaOne.setRSAPolicy('legacy')
+    rsaTwo.setRSAPolicy('legacy')
 
     const privateKey = rsaOne.generateRSAPrivate(1024)
     rsaTwo.createRSAPublic(privateKey.n, privateKey.e)
@@ -79,4 +81,18 @@ describe('RSA sign/verify', () => {
     // Assert
     expect(errorMessage).not.to.be.eq(null)
   })
+
+  it('OK: blind sign and finalize', () => {
+    // Arrange
+    const message = 'voucher'
+    const blinding = rsaTwo.blindMessage(message)
+    // Act
+    const blindSignature = rsaOne.blindSign(blinding.get_blinded_message())
+    const signature = rsaTwo.finalizeBlindSignature(message, blindSignature, blinding)
+    const messagePrefix = blinding.get_message_prefix()
+    blinding.free()
+    // Assert
+    expect(rsaTwo.verifyBlindSignature(message, signature, messagePrefix)).to.be.eq(true)
+    expect(rsaTwo.verifyBlindSignature('other voucher', signature, messagePrefix)).to.be.eq(false)
+  })
 })
@@ -0,0 +1,137 @@
+//! Searches the wasm linear memory for the private exponent before and after `free()`, which
+//! drops the key pair. Run with `wasm-pack test --node`.
+#![cfg(target_arch = "wasm32")]
+
+use std::alloc::{ GlobalAlloc, Layout, System };
+use std::cell::UnsafeCell;
+use js_sys::{ Array, Function, Uint8Array, WebAssembly };
+use rsa_lib::policy::RSAPolicy;
+use rsa_lib::private_keys::RSAPrivateKeyPair;
+use wasm_bindgen::{ JsCast, JsValue };
+use wasm_bindgen_test::*;
+
+const SLOTS: usize = 1 << 16;
+
+/// `System` recording the start and size of every live allocation in an open addressing table,
+/// so copies in live values can be told apart from leftovers in freed memory.
+struct TrackingAllocator {
+    allocations: UnsafeCell<[(usize, usize); SLOTS]>
+}
+
+// wasm32-unknown-unknown runs the tests on a single thread.
+unsafe impl Sync for TrackingAllocator {}
+
+fn home(start: usize) -> usize {
+    (start >> 3).wrapping_mul(0x9e37_79b1) % SLOTS
+}
+
+impl TrackingAllocator {
+    unsafe fn insert(&self, start: usize, size: usize) {
+        let allocations = &mut *self.allocations.get();
+        let mut index = home(start);
+        while allocations[index].0 != 0 {
+            index = (index + 1) % SLOTS;
+        }
+        allocations[index] = (start, size);
+    }
+
+    /// Linear probing deletion that shifts later entries back instead of leaving tombstones.
+    unsafe fn remove(&self, start: usize) {
+        let allocations = &mut *self.allocations.get();
+        let mut index = home(start);
+        while allocations[index].0 != start {
+            if allocations[index].0 == 0 {
+                return;
+            }
+            index = (index + 1) % SLOTS;
+        }
+        allocations[index] = (0, 0);
+        let mut next = index;
+        loop {
+            next = (next + 1) % SLOTS;
+            if allocations[next].0 == 0 {
+                return;
+            }
+            let wanted = home(allocations[next].0);
+            let between = if index <= next { index < wanted && wanted <= next } else { index < wanted || wanted <= next };
+            if !between {
+                allocations[index] = allocations[next];
+                allocations[next] = (0, 0);
+                index = next;
+            }
+        }
+    }
+
+    fn is_live(&self, offset: u32) -> bool {
+        let offset = offset as usize;
+        unsafe { &*self.allocations.get() }.iter().any(|(start, size)| *start != 0 && (*start..start + size).contains(&offset))
+    }
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            self.insert(ptr as usize, layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.remove(ptr as usize);
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator { allocations: UnsafeCell::new([(0, 0); SLOTS]) };
+
+/// Offsets in linear memory of the complement of `inverted`. The needle is only ever held
+/// complemented on this side, so the search does not find its own copy.
+fn find(inverted: &[u8]) -> Vec<u32> {
+    let memory = wasm_bindgen::memory().unchecked_into::<WebAssembly::Memory>();
+    let search = Function::new_with_args("buffer, inverted", "
+        const memory = new Uint8Array(buffer);
+        const needle = inverted.map(byte => ~byte & 0xff);
+        const offsets = [];
+        for (let offset = memory.indexOf(needle[0]); offset >= 0 && offset + needle.length <= memory.length;
+             offset = memory.indexOf(needle[0], offset + 1)) {
+            if (needle.every((byte, index) => memory[offset + index] === byte)) {
+                offsets.push(offset);
+            }
+        }
+        return offsets;
+    ");
+    let offsets = search.call2(&JsValue::NULL, &memory.buffer(), &Uint8Array::from(inverted)).unwrap();
+    offsets.unchecked_into::<Array>().iter().map(|offset| offset.as_f64().unwrap() as u32).collect()
+}
+
+/// Complement of the low 32 bytes of the hex `value`, in the little-endian order of its limbs.
+/// Decoded a byte at a time so the plain bytes never sit in memory together.
+fn inverted_low_bytes(value: &str) -> [u8; 32] {
+    let mut inverted = [0u8; 32];
+    for (index, byte) in inverted.iter_mut().enumerate() {
+        let end = value.len() - 2 * index;
+        *byte = !u8::from_str_radix(&value[end - 2..end], 16).unwrap();
+    }
+    inverted
+}
+
+/// Copies left behind by temporaries freed during generation are found both times. The copies
+/// in live allocations, which only the key pair holds, have to be gone after `free()`.
+#[wasm_bindgen_test]
+fn free_wipes_linear_memory() {
+    let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+    let mut instance = RSAPrivateKeyPair::new();
+    instance.set_policy(&RSAPolicy::legacy());
+    instance.generate(1024, seed);
+    let inverted = inverted_low_bytes(&instance.get_d());
+
+    let before = find(&inverted);
+    let owned: Vec<u32> = before.iter().copied().filter(|offset| ALLOCATOR.is_live(*offset)).collect();
+    assert!(!owned.is_empty());
+    drop(instance);
+    let after = find(&inverted);
+    assert!(after.iter().all(|offset| before.contains(offset)));
+    assert!(owned.iter().all(|offset| !after.contains(offset)), "copies at {:?} survived free, found {:?}", owned, after);
+}
@@ -0,0 +1,94 @@
+//! Replaces the global allocator to scan freed blocks for key material, so it runs in a test
+//! binary of its own.
+
+use num_bigint_dig::BigUint;
+use num_traits::Num;
+use rsa_lib::policy::RSAPolicy;
+use rsa_lib::private_keys::RSAPrivateKeyPair;
+use std::alloc::{ GlobalAlloc, Layout, System };
+use std::cell::Cell;
+
+/// Counts, on the watching thread, the freed blocks that still hold the needle.
+struct ResidueScanner;
+
+thread_local! {
+    static NEEDLE: Cell<Option<[u8; 32]>> = const { Cell::new(None) };
+    static RESIDUE: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for ResidueScanner {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ = NEEDLE.try_with(|needle| {
+            if let Some(needle) = needle.get() {
+                let block = std::slice::from_raw_parts(ptr, layout.size());
+                if block.windows(needle.len()).any(|window| window == needle) {
+                    RESIDUE.with(|residue| residue.set(residue.get() + 1));
+                }
+            }
+        });
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: ResidueScanner = ResidueScanner;
+
+/// Low 32 bytes of `secret` in the little-endian order its limbs are stored in.
+fn needle(secret: &BigUint) -> [u8; 32] {
+    let mut needle = [0u8; 32];
+    needle.copy_from_slice(&secret.to_bytes_le()[..32]);
+    needle
+}
+
+/// Freed blocks holding `needle` while `f` ran.
+fn residue(needle: [u8; 32], f: impl FnOnce()) -> usize {
+    RESIDUE.with(|residue| residue.set(0));
+    NEEDLE.with(|watched| watched.set(Some(needle)));
+    f();
+    NEEDLE.with(|watched| watched.set(None));
+    RESIDUE.with(|residue| residue.get())
+}
+
+fn hex_integer(value: &str) -> BigUint {
+    BigUint::from_str_radix(value, 16).unwrap()
+}
+
+fn private_key_pair() -> RSAPrivateKeyPair {
+    let seed = "c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b7";
+    let mut instance = RSAPrivateKeyPair::new();
+    instance.set_policy(&RSAPolicy::legacy());
+    instance.generate(1024, seed);
+    instance
+}
+
+#[test]
+fn scanner_finds_residue() {
+    let instance = private_key_pair();
+    let d = hex_integer(&instance.get_d());
+    let copy = d.clone();
+    assert_eq!(residue(needle(&d), || drop(copy)), 1);
+}
+
+#[test]
+fn free_wipes_private_components() {
+    let instance = private_key_pair();
+    let d = needle(&hex_integer(&instance.get_d()));
+    assert_eq!(residue(d, || drop(instance)), 0);
+
+    let instance = private_key_pair();
+    let prime = needle(&hex_integer(instance.get_primes().split('_').next().unwrap()));
+    assert_eq!(residue(prime, || drop(instance)), 0);
+}
+
+#[test]
+fn rekey_wipes_previous_key() {
+    let mut instance = private_key_pair();
+    let d = instance.get_d();
+    let other_seed = "0c993abb954f4ad796efa851ce4276f12250633ec4a8da1d1c8f37a82b633c1b";
+    assert_eq!(residue(needle(&hex_integer(&d)), || instance.generate(1024, other_seed)), 0);
+    assert_ne!(instance.get_d(), d);
+}